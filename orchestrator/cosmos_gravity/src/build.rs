@@ -5,13 +5,13 @@ use ethers::prelude::*;
 use ethers::utils::keccak256;
 use gravity_proto::gravity as proto;
 use gravity_proto::ToAny;
-use gravity_utils::ethereum::{downcast_to_u64, format_eth_address};
+use gravity_utils::ethereum::format_eth_address;
 use gravity_utils::message_signatures::{
     encode_logic_call_confirm, encode_tx_batch_confirm, encode_valset_confirm,
 };
 use gravity_utils::types::*;
-use std::collections::BTreeMap;
 
+use crate::claims::ethereum_event_claims;
 use crate::crypto::CosmosSigner;
 
 pub async fn signer_set_tx_confirmation_messages<S: Signer, CS: CosmosSigner>(
@@ -139,101 +139,20 @@ pub fn ethereum_event_messages<CS: CosmosSigner>(
 ) -> (Vec<Msg>, u64) {
     let cosmos_address = cosmos_key.to_address(&contact.get_prefix()).unwrap();
 
-    // This sorts oracle messages by event nonce before submitting them. It's not a pretty implementation because
-    // we're missing an intermediary layer of abstraction. We could implement 'EventTrait' and then implement sort
-    // for it, but then when we go to transform 'EventTrait' objects into GravityMsg enum values we'll have all sorts
-    // of issues extracting the inner object from the TraitObject. Likewise we could implement sort of GravityMsg but that
-    // would require a truly horrendous (nearly 100 line) match statement to deal with all combinations. That match statement
-    // could be reduced by adding two traits to sort against but really this is the easiest option.
-    //
-    // We index the events by event nonce in an unordered hashmap and then play them back in order into a vec
-    let mut unordered_msgs = BTreeMap::new();
-    for deposit in deposits {
-        let event = proto::SendToCosmosEvent {
-            event_nonce: downcast_to_u64(deposit.event_nonce).unwrap(),
-            ethereum_height: downcast_to_u64(deposit.block_height).unwrap(),
-            token_contract: format_eth_address(deposit.erc20),
-            amount: deposit.amount.to_string(),
-            cosmos_receiver: deposit.destination.to_string(),
-            ethereum_sender: format_eth_address(deposit.sender),
-        };
-        let msg = proto::MsgSubmitEthereumEvent {
-            signer: cosmos_address.to_string(),
-            event: event.to_any(),
-        };
-        let msg = Msg::new("/gravity.v1.MsgSubmitEthereumEvent", msg);
-        unordered_msgs.insert(deposit.event_nonce, msg);
-    }
-    for batch in batches {
-        let event = proto::BatchExecutedEvent {
-            event_nonce: downcast_to_u64(batch.event_nonce).unwrap(),
-            batch_nonce: downcast_to_u64(batch.batch_nonce).unwrap(),
-            ethereum_height: downcast_to_u64(batch.block_height).unwrap(),
-            token_contract: format_eth_address(batch.erc20),
-        };
-        let msg = proto::MsgSubmitEthereumEvent {
-            signer: cosmos_address.to_string(),
-            event: event.to_any(),
-        };
-        let msg = Msg::new("/gravity.v1.MsgSubmitEthereumEvent", msg);
-        unordered_msgs.insert(batch.event_nonce, msg);
-    }
-    for deploy in erc20_deploys {
-        let event = proto::Erc20DeployedEvent {
-            event_nonce: downcast_to_u64(deploy.event_nonce).unwrap(),
-            ethereum_height: downcast_to_u64(deploy.block_height).unwrap(),
-            cosmos_denom: deploy.cosmos_denom,
-            token_contract: format_eth_address(deploy.erc20_address),
-            erc20_name: deploy.name,
-            erc20_symbol: deploy.symbol,
-            erc20_decimals: deploy.decimals as u64,
-        };
-        let msg = proto::MsgSubmitEthereumEvent {
-            signer: cosmos_address.to_string(),
-            event: event.to_any(),
-        };
-        let msg = Msg::new("/gravity.v1.MsgSubmitEthereumEvent", msg);
-        unordered_msgs.insert(deploy.event_nonce, msg);
-    }
-    for logic_call in logic_calls {
-        let event = proto::ContractCallExecutedEvent {
-            event_nonce: downcast_to_u64(logic_call.event_nonce).unwrap(),
-            ethereum_height: downcast_to_u64(logic_call.block_height).unwrap(),
-            invalidation_scope: logic_call.invalidation_id,
-            invalidation_nonce: downcast_to_u64(logic_call.invalidation_nonce).unwrap(),
-        };
-        let msg = proto::MsgSubmitEthereumEvent {
-            signer: cosmos_address.to_string(),
-            event: event.to_any(),
-        };
-        let msg = Msg::new("/gravity.v1.MsgSubmitEthereumEvent", msg);
-        unordered_msgs.insert(logic_call.event_nonce, msg);
-    }
-    for valset in valsets {
-        // note that SignerSetTxExecutedEvent does not include reward amount or
-        // reward token, which is fine since we are not actually using them at the
-        // moment, but it is part of the contract-defined event
-        let event = proto::SignerSetTxExecutedEvent {
-            event_nonce: downcast_to_u64(valset.event_nonce).unwrap(),
-            signer_set_tx_nonce: downcast_to_u64(valset.valset_nonce).unwrap(),
-            ethereum_height: downcast_to_u64(valset.block_height).unwrap(),
-            members: valset.members.iter().map(|v| v.into()).collect(),
-        };
-        let msg = proto::MsgSubmitEthereumEvent {
-            signer: cosmos_address.to_string(),
-            event: event.to_any(),
-        };
-        let msg = Msg::new("/gravity.v1.MsgSubmitEthereumEvent", msg);
-        unordered_msgs.insert(valset.event_nonce, msg);
-    }
+    // claims are ordered by event nonce so that the chain can apply them in order
+    let claims = ethereum_event_claims(&deposits, &batches, &erc20_deploys, &logic_calls, &valsets);
+    let last_nonce = claims.last().map_or(0, |claim| claim.event_nonce());
 
-    let mut msgs = Vec::new();
-    let mut last_nonce: U256 = U256::zero();
-    for (i, _) in unordered_msgs.clone().iter() {
-        let msg = unordered_msgs.remove_entry(i).unwrap().1;
-        last_nonce = *i;
-        msgs.push(msg);
-    }
+    let msgs = claims
+        .iter()
+        .map(|claim| {
+            let msg = proto::MsgSubmitEthereumEvent {
+                signer: cosmos_address.to_string(),
+                event: claim.to_any(),
+            };
+            Msg::new("/gravity.v1.MsgSubmitEthereumEvent", msg)
+        })
+        .collect();
 
-    (msgs, last_nonce.as_u64())
+    (msgs, last_nonce)
 }
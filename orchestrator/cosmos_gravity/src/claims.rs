@@ -0,0 +1,176 @@
+//! The Ethereum events the oracle claims on Cosmos, in the proto form they are submitted in. Building
+//! the claims in one place lets the oracle compare what it parsed from Ethereum with the claims other
+//! validators committed to the chain.
+
+use gravity_proto::gravity as proto;
+use gravity_proto::ToAny;
+use gravity_utils::ethereum::{downcast_to_u64, format_eth_address};
+use gravity_utils::types::*;
+use prost::Message;
+use prost_types::Any;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EthereumEventClaim {
+    SendToCosmos(proto::SendToCosmosEvent),
+    BatchExecuted(proto::BatchExecutedEvent),
+    Erc20Deployed(proto::Erc20DeployedEvent),
+    ContractCallExecuted(proto::ContractCallExecutedEvent),
+    SignerSetTxExecuted(proto::SignerSetTxExecutedEvent),
+}
+
+impl EthereumEventClaim {
+    pub fn event_nonce(&self) -> u64 {
+        match self {
+            EthereumEventClaim::SendToCosmos(event) => event.event_nonce,
+            EthereumEventClaim::BatchExecuted(event) => event.event_nonce,
+            EthereumEventClaim::Erc20Deployed(event) => event.event_nonce,
+            EthereumEventClaim::ContractCallExecuted(event) => event.event_nonce,
+            EthereumEventClaim::SignerSetTxExecuted(event) => event.event_nonce,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EthereumEventClaim::SendToCosmos(_) => "send_to_cosmos",
+            EthereumEventClaim::BatchExecuted(_) => "batch_executed",
+            EthereumEventClaim::Erc20Deployed(_) => "erc20_deployed",
+            EthereumEventClaim::ContractCallExecuted(_) => "contract_call_executed",
+            EthereumEventClaim::SignerSetTxExecuted(_) => "signer_set_tx_executed",
+        }
+    }
+
+    pub fn to_any(&self) -> Option<Any> {
+        match self {
+            EthereumEventClaim::SendToCosmos(event) => event.to_any(),
+            EthereumEventClaim::BatchExecuted(event) => event.to_any(),
+            EthereumEventClaim::Erc20Deployed(event) => event.to_any(),
+            EthereumEventClaim::ContractCallExecuted(event) => event.to_any(),
+            EthereumEventClaim::SignerSetTxExecuted(event) => event.to_any(),
+        }
+    }
+
+    /// Decodes the event packed into a MsgSubmitEthereumEvent, None if it's not an event we know
+    pub fn from_any(any: &Any) -> Option<Self> {
+        let value = any.value.as_slice();
+        let claim = match any.type_url.as_str() {
+            "/gravity.v1.SendToCosmosEvent" => {
+                EthereumEventClaim::SendToCosmos(proto::SendToCosmosEvent::decode(value).ok()?)
+            }
+            "/gravity.v1.BatchExecutedEvent" => {
+                EthereumEventClaim::BatchExecuted(proto::BatchExecutedEvent::decode(value).ok()?)
+            }
+            "/gravity.v1.ERC20DeployedEvent" => {
+                EthereumEventClaim::Erc20Deployed(proto::Erc20DeployedEvent::decode(value).ok()?)
+            }
+            "/gravity.v1.ContractCallExecutedEvent" => EthereumEventClaim::ContractCallExecuted(
+                proto::ContractCallExecutedEvent::decode(value).ok()?,
+            ),
+            "/gravity.v1.SignerSetTxExecutedEvent" => EthereumEventClaim::SignerSetTxExecuted(
+                proto::SignerSetTxExecutedEvent::decode(value).ok()?,
+            ),
+            _ => return None,
+        };
+        Some(claim)
+    }
+}
+
+impl From<&SendToCosmosEvent> for EthereumEventClaim {
+    fn from(deposit: &SendToCosmosEvent) -> Self {
+        EthereumEventClaim::SendToCosmos(proto::SendToCosmosEvent {
+            event_nonce: downcast_to_u64(deposit.event_nonce).unwrap(),
+            ethereum_height: downcast_to_u64(deposit.block_height).unwrap(),
+            token_contract: format_eth_address(deposit.erc20),
+            amount: deposit.amount.to_string(),
+            cosmos_receiver: deposit.destination.to_string(),
+            ethereum_sender: format_eth_address(deposit.sender),
+        })
+    }
+}
+
+impl From<&TransactionBatchExecutedEvent> for EthereumEventClaim {
+    fn from(batch: &TransactionBatchExecutedEvent) -> Self {
+        EthereumEventClaim::BatchExecuted(proto::BatchExecutedEvent {
+            event_nonce: downcast_to_u64(batch.event_nonce).unwrap(),
+            batch_nonce: downcast_to_u64(batch.batch_nonce).unwrap(),
+            ethereum_height: downcast_to_u64(batch.block_height).unwrap(),
+            token_contract: format_eth_address(batch.erc20),
+        })
+    }
+}
+
+impl From<&Erc20DeployedEvent> for EthereumEventClaim {
+    fn from(deploy: &Erc20DeployedEvent) -> Self {
+        EthereumEventClaim::Erc20Deployed(proto::Erc20DeployedEvent {
+            event_nonce: downcast_to_u64(deploy.event_nonce).unwrap(),
+            ethereum_height: downcast_to_u64(deploy.block_height).unwrap(),
+            cosmos_denom: deploy.cosmos_denom.clone(),
+            token_contract: format_eth_address(deploy.erc20_address),
+            erc20_name: deploy.name.clone(),
+            erc20_symbol: deploy.symbol.clone(),
+            erc20_decimals: deploy.decimals as u64,
+        })
+    }
+}
+
+impl From<&LogicCallExecutedEvent> for EthereumEventClaim {
+    fn from(logic_call: &LogicCallExecutedEvent) -> Self {
+        EthereumEventClaim::ContractCallExecuted(proto::ContractCallExecutedEvent {
+            event_nonce: downcast_to_u64(logic_call.event_nonce).unwrap(),
+            ethereum_height: downcast_to_u64(logic_call.block_height).unwrap(),
+            invalidation_scope: logic_call.invalidation_id.clone(),
+            invalidation_nonce: downcast_to_u64(logic_call.invalidation_nonce).unwrap(),
+        })
+    }
+}
+
+impl From<&ValsetUpdatedEvent> for EthereumEventClaim {
+    fn from(valset: &ValsetUpdatedEvent) -> Self {
+        // note that SignerSetTxExecutedEvent does not include reward amount or
+        // reward token, which is fine since we are not actually using them at the
+        // moment, but it is part of the contract-defined event
+        EthereumEventClaim::SignerSetTxExecuted(proto::SignerSetTxExecutedEvent {
+            event_nonce: downcast_to_u64(valset.event_nonce).unwrap(),
+            signer_set_tx_nonce: downcast_to_u64(valset.valset_nonce).unwrap(),
+            ethereum_height: downcast_to_u64(valset.block_height).unwrap(),
+            members: valset.members.iter().map(|v| v.into()).collect(),
+        })
+    }
+}
+
+/// Every claim for the given events, ordered by event nonce
+pub fn ethereum_event_claims(
+    deposits: &[SendToCosmosEvent],
+    batches: &[TransactionBatchExecutedEvent],
+    erc20_deploys: &[Erc20DeployedEvent],
+    logic_calls: &[LogicCallExecutedEvent],
+    valsets: &[ValsetUpdatedEvent],
+) -> Vec<EthereumEventClaim> {
+    let mut claims: Vec<EthereumEventClaim> = deposits
+        .iter()
+        .map(EthereumEventClaim::from)
+        .chain(batches.iter().map(EthereumEventClaim::from))
+        .chain(erc20_deploys.iter().map(EthereumEventClaim::from))
+        .chain(logic_calls.iter().map(EthereumEventClaim::from))
+        .chain(valsets.iter().map(EthereumEventClaim::from))
+        .collect();
+    claims.sort_by_key(|claim| claim.event_nonce());
+    claims
+}
+
+#[test]
+fn test_claim_any_round_trip() {
+    let claim = EthereumEventClaim::BatchExecuted(proto::BatchExecutedEvent {
+        event_nonce: 7,
+        batch_nonce: 3,
+        ethereum_height: 1000,
+        token_contract: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(),
+    });
+    let any = claim.to_any().unwrap();
+    assert_eq!(EthereumEventClaim::from_any(&any), Some(claim));
+
+    let unknown = Any {
+        type_url: "/gravity.v1.BatchTxConfirmation".to_string(),
+        value: any.value,
+    };
+    assert_eq!(EthereumEventClaim::from_any(&unknown), None);
+}
@@ -1,11 +1,18 @@
+use crate::claims::EthereumEventClaim;
 use deep_space::address::Address;
 use deep_space::error::CosmosGrpcError;
-use ethers::types::Address as EthAddress;
+use ethers::types::{Address as EthAddress, U256};
+use gravity_proto::cosmos_sdk_proto::cosmos::base::query::v1beta1::PageRequest;
+use gravity_proto::cosmos_sdk_proto::cosmos::tx::v1beta1::{
+    service_client::ServiceClient as TxServiceClient, GetTxsEventRequest, OrderBy,
+};
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_proto::gravity::*;
 use gravity_utils::error::GravityError;
 use gravity_utils::ethereum::format_eth_address;
 use gravity_utils::types::*;
+use prost::Message;
+use std::collections::HashMap;
 use tonic::transport::Channel;
 
 /// get the valset for a given nonce (block) height
@@ -22,6 +29,33 @@ pub async fn get_valset(
     Ok(valset)
 }
 
+/// get every valset with a nonce of at least `start_nonce`, oldest first. Signer sets are stored
+/// under their big endian nonce, so using it as the pagination key skips all older sets
+pub async fn get_valsets_from(
+    client: &mut GravityQueryClient<Channel>,
+    start_nonce: u64,
+) -> Result<Vec<Valset>, GravityError> {
+    let mut out = Vec::new();
+    let mut next_key = start_nonce.to_be_bytes().to_vec();
+    loop {
+        let response = client
+            .signer_set_txs(SignerSetTxsRequest {
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    ..Default::default()
+                }),
+            })
+            .await?
+            .into_inner();
+        out.extend(response.signer_sets.into_iter().map(Valset::from));
+        match response.pagination {
+            Some(page) if !page.next_key.is_empty() => next_key = page.next_key,
+            _ => break,
+        }
+    }
+    Ok(out)
+}
+
 /// This hits the /pending_valset_requests endpoint and will provide
 /// an array of validator sets we have not already signed
 pub async fn get_oldest_unsigned_valsets(
@@ -119,6 +153,65 @@ pub async fn get_latest_batch(
     }
 }
 
+/// gets every transaction a given sender has waiting in the unbatched pool, following
+/// pagination until the chain reports there are no more
+pub async fn get_unbatched_send_to_ethereums(
+    client: &mut GravityQueryClient<Channel>,
+    sender_address: String,
+) -> Result<Vec<BatchTransaction>, GravityError> {
+    let mut out = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let request = client
+            .unbatched_send_to_ethereums(UnbatchedSendToEthereumsRequest {
+                sender_address: sender_address.clone(),
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    ..Default::default()
+                }),
+            })
+            .await?
+            .into_inner();
+        for send in request.send_to_ethereums {
+            out.push(BatchTransaction::from_proto(send)?);
+        }
+        match request.pagination {
+            Some(page) if !page.next_key.is_empty() => next_key = page.next_key,
+            _ => break,
+        }
+    }
+    Ok(out)
+}
+
+/// gets the fees the module reports through its BatchTxFees query, summed by denom
+pub async fn get_batch_tx_fees(
+    client: &mut GravityQueryClient<Channel>,
+) -> Result<HashMap<String, U256>, GravityError> {
+    let request = client.batch_tx_fees(BatchTxFeesRequest {}).await?;
+    let mut out: HashMap<String, U256> = HashMap::new();
+    for fee in request.into_inner().fees {
+        let amount = U256::from_dec_str(&fee.amount)?;
+        let total = out.entry(fee.denom).or_insert_with(U256::zero);
+        *total = total.checked_add(amount).ok_or_else(|| {
+            GravityError::OverflowError("U256 overflow when adding batch fees".to_string())
+        })?;
+    }
+    Ok(out)
+}
+
+/// gets the Cosmos denom for a given ERC20 token contract
+pub async fn get_erc20_denom(
+    client: &mut GravityQueryClient<Channel>,
+    erc20: EthAddress,
+) -> Result<String, GravityError> {
+    let request = client
+        .erc20_to_denom(Erc20ToDenomRequest {
+            erc20: format_eth_address(erc20),
+        })
+        .await?;
+    Ok(request.into_inner().denom)
+}
+
 // If we can't serialize a batch from a proto, but it was committed to the chain,
 // we should just ignore it. It should eventually time out and be canceled.
 fn extract_valid_batches(batches: Vec<BatchTx>) -> Vec<TransactionBatch> {
@@ -152,6 +245,31 @@ pub async fn get_transaction_batch_signatures(
     Ok(out)
 }
 
+/// Gets the parameters of the gravity module, such as the slashing windows
+pub async fn get_gravity_params(
+    client: &mut GravityQueryClient<Channel>,
+) -> Result<Params, GravityError> {
+    let request = client.params(ParamsRequest {}).await?;
+    request.into_inner().params.ok_or_else(|| {
+        GravityError::CosmosGrpcError(CosmosGrpcError::BadResponse(
+            "Gravity params query returned no params".to_string(),
+        ))
+    })
+}
+
+/// Gets the last Ethereum height the validators agreed on, with the Cosmos height it was observed at
+pub async fn get_last_observed_ethereum_height(
+    client: &mut GravityQueryClient<Channel>,
+) -> Result<LatestEthereumBlockHeight, GravityError> {
+    let request = client
+        .last_observed_ethereum_height(LastObservedEthereumHeightRequest {})
+        .await?;
+    Ok(request
+        .into_inner()
+        .last_observed_ethereum_height
+        .unwrap_or_default())
+}
+
 /// Gets the last event nonce that a given validator has attested to, this lets us
 /// catch up with what the current event nonce should be if a oracle is restarted
 pub async fn get_last_event_nonce(
@@ -265,3 +383,74 @@ fn extract_valid_batches_test() {
     let should_be_empty = extract_valid_batches(vec![invalid_batch]);
     assert_eq!(should_be_empty.len(), 0);
 }
+
+/// Gets the Ethereum address of the ERC20 representing a given Cosmos denom and whether the denom
+/// originated on Cosmos
+pub async fn get_denom_erc20(
+    client: &mut GravityQueryClient<Channel>,
+    denom: String,
+) -> Result<DenomToErc20Response, GravityError> {
+    let request = client.denom_to_erc20(DenomToErc20Request { denom }).await?;
+    Ok(request.into_inner())
+}
+
+/// An Ethereum event claim committed to the chain in a successful MsgSubmitEthereumEvent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmittedClaim {
+    pub signer: String,
+    pub height: i64,
+    pub tx_hash: String,
+    pub claim: EthereumEventClaim,
+}
+
+/// Gets the claims in the latest `limit` transactions submitting Ethereum events, newest first.
+/// There is no query for the claims the chain has recorded, so they are read back from the transactions
+pub async fn get_submitted_claims(
+    client: &mut TxServiceClient<Channel>,
+    limit: u64,
+) -> Result<Vec<SubmittedClaim>, GravityError> {
+    let response = client
+        .get_txs_event(GetTxsEventRequest {
+            events: vec!["message.action='/gravity.v1.MsgSubmitEthereumEvent'".to_string()],
+            pagination: Some(PageRequest {
+                limit,
+                ..Default::default()
+            }),
+            order_by: OrderBy::Desc as i32,
+        })
+        .await?
+        .into_inner();
+
+    let mut out = Vec::new();
+    for (tx, tx_response) in response.txs.iter().zip(response.tx_responses.iter()) {
+        // failed transactions are still indexed but their claims were never recorded
+        if tx_response.code != 0 {
+            continue;
+        }
+        let messages = tx.body.iter().flat_map(|body| body.messages.iter());
+        for message in messages {
+            if message.type_url != "/gravity.v1.MsgSubmitEthereumEvent" {
+                continue;
+            }
+            let msg = match MsgSubmitEthereumEvent::decode(message.value.as_slice()) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    warn!(
+                        "Could not decode MsgSubmitEthereumEvent in tx {}: {}",
+                        tx_response.txhash, e
+                    );
+                    continue;
+                }
+            };
+            if let Some(claim) = msg.event.as_ref().and_then(EthereumEventClaim::from_any) {
+                out.push(SubmittedClaim {
+                    signer: msg.signer,
+                    height: tx_response.height,
+                    tx_hash: tx_response.txhash.clone(),
+                    claim,
+                });
+            }
+        }
+    }
+    Ok(out)
+}
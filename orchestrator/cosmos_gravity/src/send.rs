@@ -13,15 +13,19 @@ use gravity_proto::cosmos_sdk_proto::cosmos::tx::v1beta1::BroadcastMode;
 use gravity_proto::gravity as proto;
 use gravity_utils::error::GravityError;
 use gravity_utils::ethereum::format_eth_address;
+use gravity_utils::health;
 use prost::Message;
 use std::cmp;
 use std::collections::HashSet;
 use std::{result::Result, time::Duration};
+use tracing::{field, info_span, Instrument, Span};
 
 use crate::crypto::CosmosSigner;
 
 pub const MEMO: &str = "Sent using Gravity Bridge Orchestrator";
 pub const TIMEOUT: Duration = Duration::from_secs(60);
+/// How often the send loop reports that it's alive while it waits for messages
+pub const SEND_LOOP_HEALTH_PERIOD: Duration = Duration::from_secs(60);
 
 /// Send a transaction updating the eth address for the sending
 /// Cosmos address. The sending Cosmos address should be a validator
@@ -214,6 +218,23 @@ pub async fn send_messages<CS: CosmosSigner>(
     Ok(contact.wait_for_tx(response, TIMEOUT).await?)
 }
 
+/// Messages for `send_main_loop` along with the span they were built in, so that the Cosmos
+/// transaction carrying them is traced back to the Ethereum event or object they are for
+pub struct OutgoingMessages {
+    pub messages: Vec<Msg>,
+    pub span: Span,
+}
+
+impl OutgoingMessages {
+    /// Takes the messages along with the current span
+    pub fn new(messages: Vec<Msg>) -> Self {
+        OutgoingMessages {
+            messages,
+            span: Span::current(),
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn send_main_loop<CS: CosmosSigner>(
     contact: &Contact,
@@ -221,13 +242,30 @@ pub async fn send_main_loop<CS: CosmosSigner>(
     cosmos_granter: Option<String>,
     gas_price: (f64, String),
     gas_limit: u64,
-    mut rx: tokio::sync::mpsc::Receiver<Vec<Msg>>,
+    mut rx: tokio::sync::mpsc::Receiver<OutgoingMessages>,
     gas_adjustment: f64,
     msg_batch_size: usize,
 ) {
-    while let Some(messages) = rx.recv().await {
+    health::register_loop("cosmos_send", SEND_LOOP_HEALTH_PERIOD);
+    loop {
+        // messages may not arrive for a while, wake up regularly to show the loop is still alive
+        let OutgoingMessages { messages, span } =
+            match tokio::time::timeout(SEND_LOOP_HEALTH_PERIOD, rx.recv()).await {
+                Ok(Some(outgoing)) => outgoing,
+                Ok(None) => break,
+                Err(_) => {
+                    health::loop_ticked("cosmos_send");
+                    continue;
+                }
+            };
         for msg_chunk in messages.chunks(msg_batch_size) {
-            match send_messages(
+            let tx_span = info_span!(
+                parent: &span,
+                "cosmos_tx",
+                messages = msg_chunk.len(),
+                tx_hash = field::Empty
+            );
+            let result = send_messages(
                 contact,
                 cosmos_key.clone(),
                 cosmos_granter.to_owned(),
@@ -236,9 +274,20 @@ pub async fn send_main_loop<CS: CosmosSigner>(
                 msg_chunk.to_vec(),
                 gas_adjustment,
             )
-            .await
-            {
-                Ok(res) => trace!("okay: {:?}", res),
+            .instrument(tx_span.clone())
+            .await;
+
+            let _enter = tx_span.enter();
+            match result {
+                Ok(res) => {
+                    tx_span.record("tx_hash", res.txhash.as_str());
+                    info!(
+                        "Sent {} messages to Cosmos in tx {}",
+                        msg_chunk.len(),
+                        res.txhash
+                    );
+                    trace!("okay: {:?}", res)
+                }
                 Err(err) => {
                     let msg_types = msg_chunk
                         .iter()
@@ -254,5 +303,6 @@ pub async fn send_main_loop<CS: CosmosSigner>(
                 }
             }
         }
+        health::loop_ticked("cosmos_send");
     }
 }
@@ -0,0 +1,251 @@
+//! Where the gas price for Ethereum transactions comes from. The oracle is set once at startup from the
+//! config and used by every transaction this process sends, prices are cached for each block so that
+//! relaying several valsets and batches in one loop only asks the node, or Etherscan, once.
+
+use crate::types::EthClient;
+use ethers::middleware::gas_oracle::Etherscan;
+use ethers::prelude::gas_oracle::GasOracle as EthersGasOracle;
+use ethers::prelude::*;
+use gravity_utils::error::GravityError;
+use gravity_utils::ethereum::downcast_to_u64;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// One way of getting a gas price
+#[derive(Clone, PartialEq)]
+pub enum GasPriceStrategy {
+    /// the node's eth_gasPrice
+    Node,
+    /// the next block's base fee plus the median over `blocks` recent blocks of the priority fee
+    /// paid at `percentile`, from eth_feeHistory
+    FeeHistory { blocks: u64, percentile: f64 },
+    /// always the same price, in wei
+    Fixed(U256),
+    /// the Etherscan gas tracker, only used when explicitly configured
+    Etherscan { api_key: String },
+}
+
+// written by hand so the Etherscan API key never ends up in the logs
+impl fmt::Debug for GasPriceStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasPriceStrategy::Node => write!(f, "Node"),
+            GasPriceStrategy::FeeHistory { blocks, percentile } => f
+                .debug_struct("FeeHistory")
+                .field("blocks", blocks)
+                .field("percentile", percentile)
+                .finish(),
+            GasPriceStrategy::Fixed(gas_price) => f.debug_tuple("Fixed").field(gas_price).finish(),
+            GasPriceStrategy::Etherscan { .. } => f
+                .debug_struct("Etherscan")
+                .field("api_key", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+/// Gets gas prices from one or more strategies. With several strategies the highest price of those
+/// that answer is used, and the price is then clamped to `min` and `max` when they are set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasOracle {
+    pub strategies: Vec<GasPriceStrategy>,
+    pub min: Option<U256>,
+    pub max: Option<U256>,
+}
+
+impl Default for GasOracle {
+    fn default() -> Self {
+        GasOracle {
+            strategies: vec![GasPriceStrategy::Node],
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl GasOracle {
+    pub fn clamp(&self, gas_price: U256) -> U256 {
+        let gas_price = match self.min {
+            Some(min) => gas_price.max(min),
+            None => gas_price,
+        };
+        match self.max {
+            Some(max) => gas_price.min(max),
+            None => gas_price,
+        }
+    }
+
+    /// If the price doesn't depend on the chain, so there is nothing to cache
+    fn is_fixed(&self) -> bool {
+        self.strategies
+            .iter()
+            .all(|strategy| matches!(strategy, GasPriceStrategy::Fixed(_)))
+    }
+
+    async fn fetch<S: Signer + 'static>(
+        &self,
+        eth_client: EthClient<S>,
+    ) -> Result<U256, GravityError> {
+        let mut gas_price: Option<U256> = None;
+        let mut last_error = None;
+        for strategy in &self.strategies {
+            match fetch_gas_price(strategy, eth_client.clone()).await {
+                Ok(price) => gas_price = Some(gas_price.map_or(price, |p| p.max(price))),
+                Err(e) => {
+                    warn!("Gas price strategy {:?} failed: {}", strategy, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match (gas_price, last_error) {
+            (Some(gas_price), _) => Ok(self.clamp(gas_price)),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(GravityError::InvalidArgumentError(
+                "No gas price strategies are configured".to_string(),
+            )),
+        }
+    }
+}
+
+lazy_static! {
+    static ref GAS_ORACLE: RwLock<Arc<GasOracle>> = RwLock::new(Arc::new(GasOracle::default()));
+    /// The last price fetched on each chain, with the block it was fetched at
+    static ref GAS_PRICE_CACHE: Mutex<HashMap<u64, (U64, U256)>> = Mutex::new(HashMap::new());
+}
+
+/// Sets the gas oracle used for every transaction this process sends
+pub fn set_gas_oracle(oracle: GasOracle) {
+    info!("Using gas oracle {:?}", oracle);
+    *GAS_ORACLE.write().unwrap() = Arc::new(oracle);
+    GAS_PRICE_CACHE.lock().unwrap().clear();
+}
+
+pub fn gas_oracle() -> Arc<GasOracle> {
+    GAS_ORACLE.read().unwrap().clone()
+}
+
+/// Gets the gas price from the configured oracle, reusing the price fetched earlier in the same block
+pub async fn get_gas_price<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+) -> Result<U256, GravityError> {
+    let oracle = gas_oracle();
+    if oracle.is_fixed() {
+        return oracle.fetch(eth_client).await;
+    }
+
+    let chain_id = eth_client.signer().chain_id();
+    let block = eth_client.get_block_number().await?;
+    if let Some((cached_block, gas_price)) = GAS_PRICE_CACHE.lock().unwrap().get(&chain_id) {
+        if *cached_block == block {
+            return Ok(*gas_price);
+        }
+    }
+
+    let gas_price = oracle.fetch(eth_client).await?;
+    GAS_PRICE_CACHE
+        .lock()
+        .unwrap()
+        .insert(chain_id, (block, gas_price));
+    Ok(gas_price)
+}
+
+async fn fetch_gas_price<S: Signer + 'static>(
+    strategy: &GasPriceStrategy,
+    eth_client: EthClient<S>,
+) -> Result<U256, GravityError> {
+    match strategy {
+        GasPriceStrategy::Node => Ok(eth_client.get_gas_price().await?),
+        GasPriceStrategy::FeeHistory { blocks, percentile } => {
+            let history = eth_client
+                .fee_history(*blocks, BlockNumber::Latest, &[*percentile])
+                .await?;
+            fee_history_gas_price(&history.base_fee_per_gas, &history.reward).ok_or_else(|| {
+                GravityError::EthereumBadDataError(
+                    "Fee history has no base fees, the node may not support EIP-1559".to_string(),
+                )
+            })
+        }
+        GasPriceStrategy::Fixed(gas_price) => Ok(*gas_price),
+        GasPriceStrategy::Etherscan { api_key } => {
+            let chain = get_chain(eth_client).await?;
+            let etherscan_client = Client::new(chain, api_key)?;
+            Ok(Etherscan::new(etherscan_client).fetch().await?)
+        }
+    }
+}
+
+/// The next block's base fee, the last of `base_fees`, plus the median of the priority fees in `rewards`
+pub fn fee_history_gas_price(base_fees: &[U256], rewards: &[Vec<U256>]) -> Option<U256> {
+    let next_base_fee = *base_fees.last()?;
+    let mut tips: Vec<U256> = rewards
+        .iter()
+        .filter_map(|reward| reward.first().copied())
+        .collect();
+    tips.sort_unstable();
+    let tip = tips.get(tips.len() / 2).copied().unwrap_or_default();
+    Some(next_base_fee.saturating_add(tip))
+}
+
+/// Gets the chain the client is connected to, needed by Etherscan
+pub async fn get_chain<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+) -> Result<Chain, GravityError> {
+    let chain_id_result = eth_client.get_chainid().await?;
+    let chain_id = downcast_to_u64(chain_id_result).ok_or_else(|| {
+        GravityError::EthereumBadDataError(format!(
+            "Chain ID is larger than u64 max: {chain_id_result}"
+        ))
+    })?;
+
+    Chain::try_from(chain_id).map_err(|_| {
+        GravityError::EthereumBadDataError(format!("Chain ID {chain_id} is not a known chain"))
+    })
+}
+
+#[test]
+fn test_gas_oracle() {
+    let gwei = |gwei: u64| U256::from(gwei) * U256::exp10(9);
+
+    let base_fees = vec![gwei(10), gwei(12), gwei(14)];
+    let rewards = vec![vec![gwei(3)], vec![gwei(1)], vec![gwei(2)]];
+    assert_eq!(fee_history_gas_price(&base_fees, &rewards), Some(gwei(16)));
+    assert_eq!(fee_history_gas_price(&base_fees, &[]), Some(gwei(14)));
+    assert_eq!(fee_history_gas_price(&[], &rewards), None);
+
+    let oracle = GasOracle {
+        strategies: vec![GasPriceStrategy::Node],
+        min: Some(gwei(5)),
+        max: Some(gwei(100)),
+    };
+    assert_eq!(oracle.clamp(gwei(1)), gwei(5));
+    assert_eq!(oracle.clamp(gwei(50)), gwei(50));
+    assert_eq!(oracle.clamp(gwei(500)), gwei(100));
+    assert_eq!(GasOracle::default().clamp(gwei(500)), gwei(500));
+
+    assert!(!oracle.is_fixed());
+    let fixed = GasOracle {
+        strategies: vec![GasPriceStrategy::Fixed(gwei(30))],
+        ..GasOracle::default()
+    };
+    assert!(fixed.is_fixed());
+}
+
+#[test]
+fn test_gas_price_strategy_debug_hides_api_key() {
+    let oracle = GasOracle {
+        strategies: vec![
+            GasPriceStrategy::Node,
+            GasPriceStrategy::Etherscan {
+                api_key: "SECRETKEY123".to_string(),
+            },
+        ],
+        ..GasOracle::default()
+    };
+    let formatted = format!("{:?}", oracle);
+    assert!(!formatted.contains("SECRETKEY123"));
+    assert!(formatted.contains(r#"Etherscan { api_key: "<redacted>" }"#));
+    assert!(formatted.contains("Node"));
+}
@@ -2,12 +2,16 @@
 
 use ethers::types::U256;
 
+#[macro_use]
+extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
 pub mod deploy_erc20;
 pub mod erc20_utils;
+pub mod gas_oracle;
 pub mod logic_call;
+pub mod nonce_manager;
 pub mod send_to_cosmos;
 pub mod submit_batch;
 pub mod types;
@@ -1,6 +1,7 @@
 use crate::{
+    nonce_manager::AccountNonce,
     types::{EthClient, EthSignerMiddleware},
-    utils::{get_gas_price, get_logic_call_nonce, GasCost},
+    utils::{check_relay_receipt, get_gas_price, get_logic_call_nonce, GasCost, RelayOutcome},
 };
 use ethers::contract::builders::ContractCall;
 use ethers::prelude::*;
@@ -24,8 +25,9 @@ pub async fn send_eth_logic_call<S: Signer + 'static>(
     gravity_id: String,
     gas_cost: GasCost,
     eth_client: EthClient<S>,
+    account_nonce: &AccountNonce,
     logic_call_skips: &mut LogicCallSkips,
-) -> Result<(), GravityError> {
+) -> Result<RelayOutcome, GravityError> {
     let new_call_nonce = call.invalidation_nonce;
     info!(
         "Ordering signatures and submitting LogicCall {}:{} to Ethereum",
@@ -51,7 +53,7 @@ pub async fn send_eth_logic_call<S: Signer + 'static>(
         );
 
         logic_call_skips.skip(&call);
-        return Ok(());
+        return Ok(RelayOutcome::NotSent);
     } else if current_block_height > call.timeout.into() {
         info!(
             "This LogicCall is timed out. timeout block: {} current block: {}, exiting early",
@@ -59,7 +61,7 @@ pub async fn send_eth_logic_call<S: Signer + 'static>(
         );
 
         logic_call_skips.skip(&call);
-        return Ok(());
+        return Ok(RelayOutcome::NotSent);
     }
 
     let contract_call = build_send_logic_call_contract_call(
@@ -77,40 +79,39 @@ pub async fn send_eth_logic_call<S: Signer + 'static>(
         .gas_price(gas_cost.gas_price)
         .legacy(); // must submit transactions as legacy due to bug in manually-specified EIP1559 gas limits
 
+    let nonce = account_nonce.lease(eth_client.clone()).await?;
+    let contract_call = contract_call.nonce(nonce.nonce());
     let pending_tx = contract_call.send().await?;
+    nonce.used();
     let tx_hash = *pending_tx;
     info!("Sent logic call with txid {}", tx_hash);
     // TODO(bolten): ethers interval default is 7s, this mirrors what web30 was doing, should we adjust?
     // additionally we are mirroring only waiting for 1 confirmation by leaving that as default
     let pending_tx = pending_tx.interval(Duration::from_secs(1));
 
-    match tokio::time::timeout(timeout, pending_tx).await?? {
-        Some(_) => (),
-        None => error!(
-            "Did not receive transaction receipt when submitting batch: {}",
-            tx_hash
-        ),
-    }
-
-    let last_nonce = get_logic_call_nonce(
-        gravity_contract_address,
-        call.invalidation_id,
-        eth_client.clone(),
-    )
-    .await?;
+    // the transaction is out once sent, so a receipt that doesn't come back in time is reported
+    // the same as a dropped transaction rather than as an error
+    let receipt = tokio::time::timeout(timeout, pending_tx)
+        .await
+        .unwrap_or(Ok(None))?;
+    let receipt = match receipt {
+        Some(receipt) => receipt,
+        None => {
+            error!(
+                "Did not receive transaction receipt when submitting logic call: {}",
+                tx_hash
+            );
+            account_nonce.reset().await;
+            return Ok(RelayOutcome::Dropped(tx_hash));
+        }
+    };
 
-    if last_nonce != new_call_nonce {
-        error!(
-            "Current nonce is {} expected to update to nonce {}",
-            last_nonce, new_call_nonce
-        );
-    } else {
-        info!(
-            "Successfully updated LogicCall with new Nonce {:?}",
-            last_nonce
-        );
-    }
-    Ok(())
+    let outcome = check_relay_receipt(receipt, &contract_call.tx, eth_client).await?;
+    info!(
+        "Successfully updated LogicCall with new Nonce {}",
+        new_call_nonce
+    );
+    Ok(outcome)
 }
 
 /// Returns the cost in Eth of sending this batch
@@ -151,6 +152,8 @@ pub fn build_send_logic_call_contract_call<S: Signer + 'static>(
     let (current_addresses, current_powers) = current_valset.filter_empty_addresses();
     let current_powers: Vec<U256> = current_powers.iter().map(|power| (*power).into()).collect();
     let current_valset_nonce = current_valset.nonce;
+    let current_valset_reward_amount = current_valset.reward_amount;
+    let current_valset_reward_token = current_valset.reward_token;
     let hash = encode_logic_call_confirm_hashed(gravity_id, call.clone());
     let sig_data = current_valset.order_sigs(&hash, confirms)?;
 
@@ -178,8 +181,8 @@ pub fn build_send_logic_call_contract_call<S: Signer + 'static>(
                 validators: current_addresses,
                 powers: current_powers,
                 valset_nonce: current_valset_nonce.into(),
-                reward_amount: U256::zero(),
-                reward_token: H160::zero(),
+                reward_amount: current_valset_reward_amount,
+                reward_token: current_valset_reward_token,
             },
             sig_data
                 .iter()
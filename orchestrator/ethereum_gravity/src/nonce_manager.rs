@@ -0,0 +1,114 @@
+//! Several bridges relayed from one process may share an Ethereum account on the same network, possibly
+//! through different nodes. Taking the nonce from the pending transaction count of whichever node a bridge
+//! uses would let two bridges pick the same nonce and one transaction would replace the other, so nonces
+//! are handed out per account instead. An account is only locked while a transaction is broadcast, not
+//! while waiting on its receipt.
+
+use crate::types::EthClient;
+use ethers::prelude::*;
+use ethers::types::Address as EthAddress;
+use gravity_utils::error::GravityError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// The nonces of one Ethereum account, shared by everything sending from it
+#[derive(Debug, Clone, Default)]
+pub struct AccountNonce {
+    /// the nonce after the last transaction broadcast, unknown until the account first sends
+    next: Arc<tokio::sync::Mutex<Option<U256>>>,
+}
+
+impl AccountNonce {
+    /// Locks the account and picks the nonce of its next transaction. The account stays locked until
+    /// the lease is dropped, `NonceLease::used` records that the nonce was broadcast.
+    pub async fn lease<S: Signer + 'static>(
+        &self,
+        eth_client: EthClient<S>,
+    ) -> Result<NonceLease<'_>, GravityError> {
+        let next = self.next.lock().await;
+        let pending = eth_client
+            .get_transaction_count(eth_client.address(), Some(BlockNumber::Pending.into()))
+            .await?;
+        let nonce = pick_nonce(*next, pending);
+        Ok(NonceLease { next, nonce })
+    }
+
+    /// Forgets the last nonce broadcast, for when a transaction got no receipt and may have been
+    /// dropped, so the next transaction doesn't wait behind a nonce that will never be mined
+    pub async fn reset(&self) {
+        *self.next.lock().await = None;
+    }
+}
+
+/// A node that hasn't seen a transaction sent through another node yet reports a pending count
+/// below the nonce after it, so the later of the two is used
+fn pick_nonce(next: Option<U256>, pending: U256) -> U256 {
+    match next {
+        Some(next) if next > pending => next,
+        _ => pending,
+    }
+}
+
+/// The nonce of the next transaction of an account, which stays locked while this is held
+pub struct NonceLease<'a> {
+    next: tokio::sync::MutexGuard<'a, Option<U256>>,
+    nonce: U256,
+}
+
+impl NonceLease<'_> {
+    pub fn nonce(&self) -> U256 {
+        self.nonce
+    }
+
+    /// Records that a transaction was broadcast with this nonce and unlocks the account
+    pub fn used(mut self) {
+        *self.next = Some(self.nonce + 1);
+    }
+}
+
+/// Hands out one `AccountNonce` per account and network
+#[derive(Debug, Clone, Default)]
+pub struct NonceManager {
+    accounts: Arc<Mutex<HashMap<(U256, EthAddress), AccountNonce>>>,
+}
+
+impl NonceManager {
+    /// The nonces of an account on the network with the given chain id, shared by every caller
+    /// asking for the same account
+    pub fn account_nonce(&self, chain_id: U256, address: EthAddress) -> AccountNonce {
+        self.accounts
+            .lock()
+            .unwrap()
+            .entry((chain_id, address))
+            .or_default()
+            .clone()
+    }
+}
+
+#[test]
+fn test_account_nonce_is_shared() {
+    let nonce_manager = NonceManager::default();
+    let address = EthAddress::from_low_u64_be(1);
+
+    let account = nonce_manager.account_nonce(1u64.into(), address);
+    assert!(Arc::ptr_eq(
+        &account.next,
+        &nonce_manager
+            .clone()
+            .account_nonce(1u64.into(), address)
+            .next
+    ));
+    assert!(!Arc::ptr_eq(
+        &account.next,
+        &nonce_manager.account_nonce(5u64.into(), address).next
+    ));
+}
+
+#[test]
+fn test_pick_nonce() {
+    assert_eq!(pick_nonce(None, 7u64.into()), 7u64.into());
+    // another bridge broadcast nonce 7 through a node this one's node hasn't heard from yet
+    assert_eq!(pick_nonce(Some(8u64.into()), 7u64.into()), 8u64.into());
+    // transactions sent from the account outside this process
+    assert_eq!(pick_nonce(Some(8u64.into()), 10u64.into()), 10u64.into());
+}
@@ -1,5 +1,6 @@
 use ethers::prelude::*;
+use gravity_utils::eth_transport::EthTransport;
 use std::sync::Arc;
 
-pub type EthSignerMiddleware<S> = SignerMiddleware<Provider<Http>, S>;
+pub type EthSignerMiddleware<S> = SignerMiddleware<Provider<EthTransport>, S>;
 pub type EthClient<S> = Arc<EthSignerMiddleware<S>>;
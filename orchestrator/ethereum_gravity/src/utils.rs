@@ -1,15 +1,21 @@
-use crate::types::EthClient;
+use crate::gas_oracle;
+use crate::types::{EthClient, EthSignerMiddleware};
 use deep_space::error::CosmosGrpcError;
-use ethers::middleware::gas_oracle::Etherscan;
-use ethers::prelude::gas_oracle::GasOracle;
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::types::Address as EthAddress;
+use gravity_abi::erc20::ERC20;
 use gravity_abi::gravity::*;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
-use gravity_utils::error::GravityError;
+use gravity_utils::error::{GravityError, RevertedTransaction};
+use gravity_utils::eth_transport::{EthTransport, EthTransportError};
 use gravity_utils::ethereum::{downcast_to_u64, hex_str_to_bytes, vec_u8_to_fixed_32};
 use gravity_utils::types::{decode_gravity_error, GravityContractError};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::future::Future;
 use std::result::Result;
+use std::sync::Mutex;
 use tonic::transport::Channel;
 
 /// Gets the latest validator set nonce
@@ -116,6 +122,243 @@ pub async fn get_event_nonce<S: Signer + 'static>(
     Ok(downcast_to_u64(event_nonce).expect("EventNonce nonce overflow! Bridge Halt!"))
 }
 
+/// The Multicall3 contract, deployed at the same address on most chains
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+lazy_static! {
+    /// If Multicall3 is deployed on each chain, so that it's only checked once
+    static ref MULTICALL_DEPLOYED: Mutex<HashMap<u64, bool>> = Mutex::new(HashMap::new());
+}
+
+/// The Gravity contract nonces to read in `get_gravity_nonces`
+#[derive(Debug, Clone, Default)]
+pub struct NonceRequest {
+    pub valset_nonce: bool,
+    pub event_nonce: bool,
+    pub batch_tokens: Vec<EthAddress>,
+    pub logic_call_invalidation_ids: Vec<Vec<u8>>,
+}
+
+impl NonceRequest {
+    pub fn is_empty(&self) -> bool {
+        !self.valset_nonce
+            && !self.event_nonce
+            && self.batch_tokens.is_empty()
+            && self.logic_call_invalidation_ids.is_empty()
+    }
+}
+
+/// The nonces read by `get_gravity_nonces`, only the requested ones are set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GravityNonces {
+    pub valset_nonce: Option<u64>,
+    pub event_nonce: Option<u64>,
+    pub batch_nonces: HashMap<EthAddress, u64>,
+    pub logic_call_nonces: HashMap<Vec<u8>, u64>,
+}
+
+/// Reads many Gravity contract nonces in a single eth_call through Multicall3, falling back to
+/// one call per nonce when Multicall3 is not deployed on the chain or the multicall fails
+pub async fn get_gravity_nonces<S: Signer + 'static>(
+    gravity_contract_address: EthAddress,
+    request: &NonceRequest,
+    eth_client: EthClient<S>,
+) -> Result<GravityNonces, GravityError> {
+    if request.is_empty() {
+        return Ok(GravityNonces::default());
+    }
+
+    let calls = request.calls();
+    let deployed = multicall_deployed(eth_client.clone()).await;
+    read_gravity_nonces(
+        &calls,
+        deployed,
+        || multicall_gravity_nonces(gravity_contract_address, &calls, eth_client.clone()),
+        |call| read_nonce(gravity_contract_address, call, eth_client.clone()),
+    )
+    .await
+}
+
+/// One nonce read by `get_gravity_nonces`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NonceCall {
+    Valset,
+    Event,
+    Batch(EthAddress),
+    LogicCall(Vec<u8>),
+}
+
+impl NonceRequest {
+    /// The nonces to read, in the order their results are collected by `collect_nonces`
+    fn calls(&self) -> Vec<NonceCall> {
+        let mut calls = Vec::new();
+        if self.valset_nonce {
+            calls.push(NonceCall::Valset);
+        }
+        if self.event_nonce {
+            calls.push(NonceCall::Event);
+        }
+        calls.extend(self.batch_tokens.iter().copied().map(NonceCall::Batch));
+        calls.extend(
+            self.logic_call_invalidation_ids
+                .iter()
+                .cloned()
+                .map(NonceCall::LogicCall),
+        );
+        calls
+    }
+}
+
+/// Reads the nonces with `multicall` when Multicall3 is deployed, otherwise or if the multicall
+/// fails each nonce is read on its own with `read_one`
+async fn read_gravity_nonces<M, MF, R, RF>(
+    calls: &[NonceCall],
+    multicall_deployed: bool,
+    multicall: M,
+    mut read_one: R,
+) -> Result<GravityNonces, GravityError>
+where
+    M: FnOnce() -> MF,
+    MF: Future<Output = Result<Vec<u64>, GravityError>>,
+    R: FnMut(NonceCall) -> RF,
+    RF: Future<Output = Result<u64, GravityError>>,
+{
+    if multicall_deployed {
+        match multicall()
+            .await
+            .and_then(|results| collect_nonces(calls, results))
+        {
+            Ok(nonces) => return Ok(nonces),
+            Err(e) => warn!(
+                "Failed to read nonces through Multicall, reading them one by one: {}",
+                e
+            ),
+        }
+    }
+
+    let mut results = Vec::with_capacity(calls.len());
+    for call in calls {
+        results.push(read_one(call.clone()).await?);
+    }
+    collect_nonces(calls, results)
+}
+
+/// Matches each nonce read to the call it was read with, `results` must be in the order of `calls`
+fn collect_nonces(calls: &[NonceCall], results: Vec<u64>) -> Result<GravityNonces, GravityError> {
+    if results.len() != calls.len() {
+        return Err(GravityError::EthereumBadDataError(format!(
+            "Read {} nonces for {} calls",
+            results.len(),
+            calls.len()
+        )));
+    }
+
+    let mut nonces = GravityNonces::default();
+    for (call, nonce) in calls.iter().zip(results) {
+        match call {
+            NonceCall::Valset => nonces.valset_nonce = Some(nonce),
+            NonceCall::Event => nonces.event_nonce = Some(nonce),
+            NonceCall::Batch(token) => {
+                nonces.batch_nonces.insert(*token, nonce);
+            }
+            NonceCall::LogicCall(invalidation_id) => {
+                nonces
+                    .logic_call_nonces
+                    .insert(invalidation_id.clone(), nonce);
+            }
+        }
+    }
+    Ok(nonces)
+}
+
+async fn read_nonce<S: Signer + 'static>(
+    gravity_contract_address: EthAddress,
+    call: NonceCall,
+    eth_client: EthClient<S>,
+) -> Result<u64, GravityError> {
+    match call {
+        NonceCall::Valset => get_valset_nonce(gravity_contract_address, eth_client).await,
+        NonceCall::Event => get_event_nonce(gravity_contract_address, eth_client).await,
+        NonceCall::Batch(token) => {
+            get_tx_batch_nonce(gravity_contract_address, token, eth_client).await
+        }
+        NonceCall::LogicCall(invalidation_id) => {
+            get_logic_call_nonce(gravity_contract_address, invalidation_id, eth_client).await
+        }
+    }
+}
+
+async fn multicall_deployed<S: Signer + 'static>(eth_client: EthClient<S>) -> bool {
+    let chain_id = eth_client.signer().chain_id();
+    if let Some(deployed) = MULTICALL_DEPLOYED.lock().unwrap().get(&chain_id) {
+        return *deployed;
+    }
+
+    let address: EthAddress = MULTICALL3_ADDRESS.parse().unwrap();
+    match eth_client.get_code(address, None).await {
+        Ok(code) => {
+            let deployed = !code.as_ref().is_empty();
+            if !deployed {
+                info!(
+                    "Multicall3 is not deployed on chain {}, nonces will be read one by one",
+                    chain_id
+                );
+            }
+            MULTICALL_DEPLOYED
+                .lock()
+                .unwrap()
+                .insert(chain_id, deployed);
+            deployed
+        }
+        // try again next time rather than caching a guess
+        Err(e) => {
+            warn!("Could not check for Multicall3: {:?}", e);
+            false
+        }
+    }
+}
+
+async fn multicall_gravity_nonces<S: Signer + 'static>(
+    gravity_contract_address: EthAddress,
+    calls: &[NonceCall],
+    eth_client: EthClient<S>,
+) -> Result<Vec<u64>, GravityError> {
+    let gravity = Gravity::new(gravity_contract_address, eth_client.clone());
+    let mut multicall = Multicall::new(
+        eth_client.clone(),
+        Some(MULTICALL3_ADDRESS.parse().unwrap()),
+    )
+    .await
+    .map_err(|e| GravityError::EthereumContractError(format!("Multicall error: {e}")))?;
+
+    for call in calls {
+        match call {
+            NonceCall::Valset => multicall.add_call(gravity.state_last_valset_nonce(), false),
+            NonceCall::Event => multicall.add_call(gravity.state_last_event_nonce(), false),
+            NonceCall::Batch(token) => multicall.add_call(gravity.last_batch_nonce(*token), false),
+            NonceCall::LogicCall(invalidation_id) => {
+                let invalidation_id = vec_u8_to_fixed_32(invalidation_id.clone())?;
+                multicall.add_call(gravity.last_logic_call_nonce(invalidation_id), false)
+            }
+        };
+    }
+
+    let results = multicall
+        .call_raw()
+        .await
+        .map_err(|e| GravityError::EthereumContractError(format!("Multicall error: {e}")))?;
+    results
+        .into_iter()
+        .map(|token| {
+            token.into_uint().and_then(downcast_to_u64).ok_or_else(|| {
+                GravityError::EthereumBadDataError(
+                    "Multicall returned a nonce that is not a u64".to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
 /// Gets the gravityID
 pub async fn get_gravity_id<S: Signer + 'static>(
     gravity_contract_address: EthAddress,
@@ -170,45 +413,89 @@ pub async fn get_gravity_id<S: Signer + 'static>(
     }
 }
 
-/// If ETHERSCAN_API_KEY env var is set, we'll call out to Etherscan for a gas estimate.
-/// Otherwise, just call eth_gasPrice.
+/// Gets the number of decimals of an ERC20 token
+pub async fn get_erc20_decimals<S: Signer + 'static>(
+    erc20: EthAddress,
+    eth_client: EthClient<S>,
+) -> Result<u8, GravityError> {
+    Ok(ERC20::new(erc20, eth_client).decimals().call().await?)
+}
+
+/// Gets the gas price from the gas oracle set at startup, see `gas_oracle`
 pub async fn get_gas_price<S: Signer + 'static>(
     eth_client: EthClient<S>,
 ) -> Result<U256, GravityError> {
-    if std::env::var("ETHERSCAN_API_KEY").is_ok() {
-        let chain = get_chain(eth_client.clone()).await?;
-        let etherscan_client = Client::new_from_env(chain)?;
-        let etherscan_oracle = Etherscan::new(etherscan_client);
-        return Ok(etherscan_oracle.fetch().await?);
+    gas_oracle::get_gas_price(eth_client).await
+}
+
+/// What happened to a relay handed to one of the `send_eth_*` functions
+#[derive(Debug, Clone)]
+pub enum RelayOutcome {
+    /// the contract was already past it or it timed out, so nothing was sent
+    NotSent,
+    /// the transaction was mined and succeeded, reverted transactions are returned as
+    /// `GravityError::TransactionReverted`
+    Mined(TransactionReceipt),
+    /// the transaction was sent but no receipt came back before the timeout, it was dropped or
+    /// may still be mined
+    Dropped(TxHash),
+}
+
+impl RelayOutcome {
+    /// The hash of the transaction that was sent, if one was
+    pub fn tx_hash(&self) -> Option<TxHash> {
+        match self {
+            RelayOutcome::NotSent => None,
+            RelayOutcome::Mined(receipt) => Some(receipt.transaction_hash),
+            RelayOutcome::Dropped(tx_hash) => Some(*tx_hash),
+        }
     }
+}
 
-    Ok(eth_client.get_gas_price().await?)
+/// What a mined transaction cost, the gas it used at its effective gas price
+pub fn receipt_cost(receipt: &TransactionReceipt) -> Option<U256> {
+    Some(
+        receipt
+            .gas_used?
+            .saturating_mul(receipt.effective_gas_price?),
+    )
 }
 
-pub async fn get_chain<S: Signer + 'static>(
+/// Checks the receipt of a relay transaction. A reverted transaction is replayed with `eth_call`
+/// at the block it was mined in to recover its revert data, the replay runs against the state at
+/// the end of that block so a relay another relayer beat in the same block reports the nonce error.
+pub async fn check_relay_receipt<S: Signer + 'static>(
+    receipt: TransactionReceipt,
+    tx: &TypedTransaction,
     eth_client: EthClient<S>,
-) -> Result<Chain, GravityError> {
-    let chain_id_result = eth_client.get_chainid().await?;
-    let chain_id = downcast_to_u64(chain_id_result);
-
-    if chain_id.is_none() {
-        return Err(GravityError::EthereumBadDataError(format!(
-            "Chain ID is larger than u64 max: {chain_id_result}"
-        )));
+) -> Result<RelayOutcome, GravityError> {
+    if receipt.status == Some(1u64.into()) {
+        return Ok(RelayOutcome::Mined(receipt));
     }
 
-    // We're only currently looking for ETHERSCAN_API_KEY, so only support
-    // Ethereum networks. Returning mainnet as a default in absence of a better
-    // option. Strangely there is no function in ethers to convert from a chain
-    // ID to a Chain enum value.
-    Ok(match chain_id.unwrap() {
-        1 => Chain::Mainnet,
-        3 => Chain::Ropsten,
-        4 => Chain::Rinkeby,
-        5 => Chain::Goerli,
-        42 => Chain::Kovan,
-        _ => Chain::Mainnet,
-    })
+    let contract_error = match receipt.block_number {
+        Some(block) => {
+            let mut tx = tx.clone();
+            tx.set_from(eth_client.address());
+            match eth_client.call(&tx, Some(block.into())).await {
+                Ok(_) => {
+                    warn!(
+                        "Transaction {:?} reverted but succeeds when replayed at block {}",
+                        receipt.transaction_hash, block
+                    );
+                    None
+                }
+                Err(e) => extract_gravity_contract_error::<S>(e.into()),
+            }
+        }
+        None => None,
+    };
+    Err(GravityError::TransactionReverted(Box::new(
+        RevertedTransaction {
+            receipt,
+            contract_error,
+        },
+    )))
 }
 
 /// Just a helper struct to represent the cost of actions on Ethereum
@@ -219,117 +506,313 @@ pub struct GasCost {
 }
 
 impl GasCost {
+    /// The total cost in wei, saturating at U256::MAX
     pub fn get_total(&self) -> U256 {
-        self.gas * self.gas_price
+        self.gas.saturating_mul(self.gas_price)
+    }
+
+    /// Applies the relayer's gas price and gas limit multipliers, given in basis points
+    pub fn with_multipliers(
+        &self,
+        gas_price_multiplier_bps: u32,
+        gas_multiplier_bps: u32,
+    ) -> GasCost {
+        GasCost {
+            gas: apply_bps(self.gas, gas_multiplier_bps),
+            gas_price: apply_bps(self.gas_price, gas_price_multiplier_bps),
+        }
     }
 }
 
-// returns a bool indicating whether or not this error means we should permanently
-// skip this logic call
-pub fn handle_contract_error<S: Signer + 'static>(gravity_error: GravityError) -> bool {
-    let error_string = format!("LogicCall error: {gravity_error:?}");
+/// Multipliers are fixed point numbers in basis points, this is 1x
+pub const ONE_BPS: u32 = 10_000;
 
-    if let Some(gravity_contract_error) = extract_gravity_contract_error::<S>(gravity_error) {
-        match gravity_contract_error {
-            GravityContractError::InvalidLogicCallNonce(nonce_error) => {
-                info!(
-                    "LogicCall already processed, skipping until observed on chain: {}",
-                    nonce_error.message()
-                );
-                return true;
-            }
-            GravityContractError::LogicCallTimedOut(timeout_error) => {
-                info!(
-                    "LogicCall is timed out, will be skipped until timeout on chain: {}",
-                    timeout_error.message()
-                );
-                return true;
-            }
-            // TODO(bolten): implement other cases if necessary
-            _ => {
-                error!("Unspecified gravity contract error: {error_string}")
-            }
+/// Converts a configured multiplier such as 1.5 into basis points, rounding to the nearest basis
+/// point. Negative and NaN multipliers become zero, huge ones saturate at u32::MAX.
+pub fn multiplier_to_bps(multiplier: f32) -> u32 {
+    // float to int casts saturate and map NaN to zero
+    (f64::from(multiplier) * f64::from(ONE_BPS)).round() as u32
+}
+
+/// Scales a value by a multiplier in basis points, rounding down and saturating at U256::MAX
+pub fn apply_bps(value: U256, multiplier_bps: u32) -> U256 {
+    let scaled = value.full_mul(multiplier_bps.into()) / U512::from(ONE_BPS);
+    U256::try_from(scaled).unwrap_or(U256::MAX)
+}
+
+/// Formats an amount of wei as ETH with four decimals, without going through floats
+pub fn format_eth(wei: U256) -> String {
+    let one_eth = U256::exp10(18);
+    let fraction = (wei % one_eth) / U256::exp10(14);
+    format!("{}.{:04}", wei / one_eth, fraction.as_u64())
+}
+
+/// What the relayer should do with a valset, batch or logic call after the Gravity contract rejected it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractErrorAction {
+    /// it can never succeed, for example it was already relayed or has timed out
+    SkipPermanently,
+    /// it may succeed later, for example once more signatures arrive or the validator set is updated
+    Backoff,
+    /// not a Gravity contract error, try again on the next loop
+    Retry,
+}
+
+/// A failed relay classified by the Gravity contract error that caused it
+#[derive(Debug, Clone)]
+pub struct ClassifiedContractError {
+    pub action: ContractErrorAction,
+    /// the contract was already past the relay's nonce, another relayer got there first
+    pub already_relayed: bool,
+    /// the Solidity error name, or "Other" if the failure was not a Gravity contract error
+    pub name: &'static str,
+    pub message: String,
+}
+
+pub fn classify_contract_error<S: Signer + 'static>(
+    gravity_error: GravityError,
+) -> ClassifiedContractError {
+    // a transaction that reverted for an unknown reason cost gas, so it is not retried right away
+    let (unknown_action, error_string) = match &gravity_error {
+        GravityError::TransactionReverted(_) => {
+            (ContractErrorAction::Backoff, gravity_error.to_string())
         }
-    } else {
-        error!("Non-gravity contract error: {error_string}");
+        _ => (ContractErrorAction::Retry, format!("{gravity_error:?}")),
+    };
+
+    match extract_gravity_contract_error::<S>(gravity_error) {
+        Some(contract_error) => ClassifiedContractError {
+            action: contract_error_action(&contract_error),
+            already_relayed: contract_error.is_already_relayed(),
+            name: contract_error.name(),
+            message: contract_error.message(),
+        },
+        None => ClassifiedContractError {
+            action: unknown_action,
+            already_relayed: false,
+            name: "Other",
+            message: error_string,
+        },
     }
+}
 
-    false
+pub fn contract_error_action(contract_error: &GravityContractError) -> ContractErrorAction {
+    match contract_error {
+        // another relayer got there first, or it can no longer be relayed
+        GravityContractError::InvalidValsetNonce(_)
+        | GravityContractError::InvalidBatchNonce(_)
+        | GravityContractError::InvalidLogicCallNonce(_)
+        | GravityContractError::BatchTimedOut(_)
+        | GravityContractError::LogicCallTimedOut(_) => ContractErrorAction::SkipPermanently,
+        // the signed data is invalid, no amount of waiting will fix it
+        GravityContractError::MalformedNewValidatorSet(_)
+        | GravityContractError::MalformedBatch(_) => ContractErrorAction::SkipPermanently,
+        // the signatures don't match the validator set in the contract, which is fixed by more
+        // confirmations or by relaying a validator set update first
+        GravityContractError::InvalidSignature(_)
+        | GravityContractError::InsufficientPower(_)
+        | GravityContractError::IncorrectCheckpoint(_)
+        | GravityContractError::MalformedCurrentValidatorSet(_) => ContractErrorAction::Backoff,
+        GravityContractError::InvalidLogicCallTransfers(_)
+        | GravityContractError::InvalidLogicCallFees(_)
+        | GravityContractError::InvalidSendToCosmos(_) => ContractErrorAction::Backoff,
+    }
 }
 
-// ethers is providing an extremely nested set of enums as an error type and decomposing it
-// results in this nightmare
 pub fn extract_gravity_contract_error<S: Signer + 'static>(
     gravity_error: GravityError,
 ) -> Option<GravityContractError> {
-    // TODO: test if this works (it's an attempt to rewrite the below commented out nested-match code)
-    if let GravityError::EthersContractError(ce) = gravity_error {
-        let cce = ce
-            .downcast_ref::<ethers::contract::ContractError<SignerMiddleware<Provider<Http>, S>>>(
-            )?;
-        if let ethers::contract::ContractError::MiddlewareError(sme) = cce {
-            let csme = <dyn std::any::Any>::downcast_ref::<ethers::providers::ProviderError>(sme)?;
-
-            if let ethers::providers::ProviderError::JsonRpcClientError(jrpce) = csme {
-                let httpe = jrpce.downcast_ref::<ethers::providers::HttpClientError>()?;
-                if let ethers::providers::HttpClientError::JsonRpcError(jre) = httpe {
-                    if jre.code == 3 && jre.data.is_some() {
-                        let data = jre.data.as_ref().unwrap();
-                        if let Some(data_str) = data.as_str() {
-                            let data_bytes = hex_str_to_bytes(data_str);
-                            if let Ok(db) = data_bytes {
-                                return decode_gravity_error(db);
-                            }
-                        }
-                    }
-                }
+    if let GravityError::TransactionReverted(reverted) = gravity_error {
+        return reverted.contract_error;
+    }
+    decode_gravity_error(revert_data::<S>(&gravity_error)?)
+}
+
+/// Gets the revert data the node returned for a failed call or gas estimate, the same over
+/// every transport since they all report it as JSON-RPC error data
+pub fn revert_data<S: Signer + 'static>(gravity_error: &GravityError) -> Option<Vec<u8>> {
+    let provider_error = match gravity_error {
+        GravityError::EthersContractError(e) => {
+            match e.downcast_ref::<ContractError<EthSignerMiddleware<S>>>()? {
+                ContractError::Revert(data) => return Some(data.to_vec()),
+                ContractError::MiddlewareError(SignerMiddlewareError::MiddlewareError(e)) => e,
+                ContractError::ProviderError(e) => e,
+                _ => return None,
             }
         }
+        GravityError::EthereumRestError(e) => {
+            match e.downcast_ref::<SignerMiddlewareError<Provider<EthTransport>, S>>()? {
+                SignerMiddlewareError::MiddlewareError(e) => e,
+                _ => return None,
+            }
+        }
+        GravityError::EthersProviderError(e) => e,
+        _ => return None,
+    };
+
+    let transport_error = match provider_error {
+        ProviderError::JsonRpcClientError(e) => e.downcast_ref::<EthTransportError>()?,
+        _ => return None,
+    };
+    let data = transport_error.as_json_rpc_error()?.data.as_ref()?;
+    hex_str_to_bytes(data.as_str()?).ok()
+}
+
+#[test]
+fn test_gas_multipliers() {
+    assert_eq!(multiplier_to_bps(1.0), ONE_BPS);
+    assert_eq!(multiplier_to_bps(1.5), 15_000);
+    assert_eq!(multiplier_to_bps(1.1), 11_000);
+    assert_eq!(multiplier_to_bps(-1.0), 0);
+    assert_eq!(multiplier_to_bps(f32::NAN), 0);
+    assert_eq!(multiplier_to_bps(f32::INFINITY), u32::MAX);
+
+    let cost = GasCost {
+        gas: 100_000u64.into(),
+        gas_price: 3u64.into(),
+    };
+    let scaled = cost.with_multipliers(15_000, 12_500);
+    assert_eq!(scaled.gas_price, 4u64.into());
+    assert_eq!(scaled.gas, 125_000u64.into());
+
+    let huge = GasCost {
+        gas: U256::MAX,
+        gas_price: U256::MAX,
+    };
+    assert_eq!(huge.get_total(), U256::MAX);
+    assert_eq!(huge.with_multipliers(20_000, 20_000).gas, U256::MAX);
+
+    assert_eq!(format_eth(U256::exp10(18) * 3 / 2), "1.5000");
+    assert_eq!(format_eth(123u64.into()), "0.0000");
+}
+
+#[test]
+fn test_nonce_results_follow_request_order() {
+    let token_a: EthAddress = "0x0000000000000000000000000000000000000001"
+        .parse()
+        .unwrap();
+    let token_b: EthAddress = "0x0000000000000000000000000000000000000002"
+        .parse()
+        .unwrap();
+    let request = NonceRequest {
+        valset_nonce: true,
+        event_nonce: true,
+        batch_tokens: vec![token_b, token_a],
+        logic_call_invalidation_ids: vec![vec![7u8; 32]],
+    };
+    let calls = request.calls();
+    assert_eq!(
+        calls,
+        vec![
+            NonceCall::Valset,
+            NonceCall::Event,
+            NonceCall::Batch(token_b),
+            NonceCall::Batch(token_a),
+            NonceCall::LogicCall(vec![7u8; 32]),
+        ]
+    );
+
+    let nonces = collect_nonces(&calls, vec![1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(nonces.valset_nonce, Some(1));
+    assert_eq!(nonces.event_nonce, Some(2));
+    assert_eq!(nonces.batch_nonces[&token_b], 3);
+    assert_eq!(nonces.batch_nonces[&token_a], 4);
+    assert_eq!(nonces.logic_call_nonces[&vec![7u8; 32]], 5);
+
+    // a multicall that returns too few or too many results must not be matched up
+    assert!(collect_nonces(&calls, vec![1, 2, 3, 4]).is_err());
+    assert!(collect_nonces(&calls, vec![1, 2, 3, 4, 5, 6]).is_err());
+}
+
+#[tokio::test]
+async fn test_nonces_read_one_by_one_without_multicall() {
+    let token: EthAddress = "0x0000000000000000000000000000000000000001"
+        .parse()
+        .unwrap();
+    let request = NonceRequest {
+        valset_nonce: true,
+        batch_tokens: vec![token],
+        ..NonceRequest::default()
+    };
+    let calls = request.calls();
+
+    // Multicall3 is not deployed, every nonce is read on its own
+    let mut read = Vec::new();
+    let nonces = read_gravity_nonces(
+        &calls,
+        false,
+        || std::future::ready(Ok(vec![100, 100])),
+        |call| {
+            read.push(call.clone());
+            std::future::ready(Ok(match call {
+                NonceCall::Valset => 10,
+                _ => 20,
+            }))
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(read, calls);
+    assert_eq!(nonces.valset_nonce, Some(10));
+    assert_eq!(nonces.batch_nonces[&token], 20);
+
+    // the multicall is used when it's deployed
+    let nonces = read_gravity_nonces(
+        &calls,
+        true,
+        || std::future::ready(Ok(vec![30, 40])),
+        |_| std::future::ready(Err(GravityError::InvalidBridgeStateError(String::new()))),
+    )
+    .await
+    .unwrap();
+    assert_eq!(nonces.valset_nonce, Some(30));
+    assert_eq!(nonces.batch_nonces[&token], 40);
+
+    // and a failed multicall falls back to reading the nonces one by one
+    let nonces = read_gravity_nonces(
+        &calls,
+        true,
+        || {
+            std::future::ready(Err(GravityError::EthereumContractError(
+                "Multicall error".to_string(),
+            )))
+        },
+        |call| {
+            std::future::ready(Ok(match call {
+                NonceCall::Valset => 50,
+                _ => 60,
+            }))
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(nonces.valset_nonce, Some(50));
+    assert_eq!(nonces.batch_nonces[&token], 60);
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn prop_apply_bps_matches_u128(value in proptest::prelude::any::<u64>(), bps in proptest::prelude::any::<u32>()) {
+        let expected = u128::from(value) * u128::from(bps) / u128::from(ONE_BPS);
+        proptest::prop_assert_eq!(apply_bps(value.into(), bps), U256::from(expected));
+    }
+
+    #[test]
+    fn prop_apply_bps_is_monotonic(bytes in proptest::prelude::any::<[u8; 32]>(), a in proptest::prelude::any::<u32>(), b in proptest::prelude::any::<u32>()) {
+        let value = U256::from_big_endian(&bytes);
+        let (low, high) = (a.min(b), a.max(b));
+        proptest::prop_assert!(apply_bps(value, low) <= apply_bps(value, high));
+        proptest::prop_assert_eq!(apply_bps(value, ONE_BPS), value);
+        if high <= ONE_BPS {
+            proptest::prop_assert!(apply_bps(value, high) <= value);
+        } else {
+            proptest::prop_assert!(apply_bps(value, high) >= value);
+        }
+    }
+
+    #[test]
+    fn prop_multiplier_to_bps_round_trips(bps in 0u32..1_000_000) {
+        proptest::prop_assert_eq!(multiplier_to_bps(bps as f32 / ONE_BPS as f32), bps);
     }
-    None
-
-    // match gravity_error {
-    //     GravityError::EthersContractError(ce) => match ce {
-    //         ethers::contract::ContractError::MiddlewareError(me) => match me {
-    //             ethers::middleware::signer::SignerMiddlewareError::MiddlewareError(sme) => {
-    //                 match sme {
-    //                     ethers::providers::ProviderError::JsonRpcClientError(jrpce) => {
-    //                         if jrpce.is::<ethers::providers::HttpClientError>() {
-    //                             let httpe = *jrpce
-    //                                 .downcast::<ethers::providers::HttpClientError>()
-    //                                 .unwrap();
-    //                             match httpe {
-    //                                 ethers::providers::HttpClientError::JsonRpcError(jre) => {
-    //                                     if jre.code == 3 && jre.data.is_some() {
-    //                                         let data = jre.data.unwrap();
-    //                                         if data.is_string() {
-    //                                             let data_bytes =
-    //                                                 hex_str_to_bytes::<S>(data.as_str().unwrap());
-    //                                             if data_bytes.is_ok() {
-    //                                                 decode_gravity_error(data_bytes.unwrap())
-    //                                             } else {
-    //                                                 None
-    //                                             }
-    //                                         } else {
-    //                                             None
-    //                                         }
-    //                                     } else {
-    //                                         None
-    //                                     }
-    //                                 }
-    //                                 _ => None,
-    //                             }
-    //                         } else {
-    //                             None
-    //                         }
-    //                     }
-    //                     _ => None,
-    //                 }
-    //             }
-    //             _ => None,
-    //         },
-    //         _ => None,
-    //     },
-    //     _ => None,
-    // }
 }
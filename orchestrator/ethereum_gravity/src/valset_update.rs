@@ -1,6 +1,7 @@
 use crate::{
+    nonce_manager::AccountNonce,
     types::{EthClient, EthSignerMiddleware},
-    utils::{get_gas_price, get_valset_nonce, GasCost},
+    utils::{check_relay_receipt, get_gas_price, get_valset_nonce, GasCost, RelayOutcome},
 };
 use ethers::contract::builders::ContractCall;
 use ethers::prelude::*;
@@ -23,7 +24,8 @@ pub async fn send_eth_valset_update<S: Signer + 'static>(
     gravity_id: String,
     gas_cost: GasCost,
     eth_client: EthClient<S>,
-) -> Result<(), GravityError> {
+    account_nonce: &AccountNonce,
+) -> Result<RelayOutcome, GravityError> {
     let old_nonce = old_valset.nonce;
     let new_nonce = new_valset.nonce;
 
@@ -37,7 +39,7 @@ pub async fn send_eth_valset_update<S: Signer + 'static>(
             "Someone else updated the valset to {}, exiting early",
             before_nonce
         );
-        return Ok(());
+        return Ok(RelayOutcome::NotSent);
     }
 
     let contract_call = build_valset_update_contract_call(
@@ -53,35 +55,36 @@ pub async fn send_eth_valset_update<S: Signer + 'static>(
         .gas_price(gas_cost.gas_price)
         .legacy(); // must submit transactions as legacy due to bug in manually-specified EIP1559 gas limits
 
+    let nonce = account_nonce.lease(eth_client.clone()).await?;
+    let contract_call = contract_call.nonce(nonce.nonce());
     let pending_tx = contract_call.send().await?;
+    nonce.used();
     let tx_hash = *pending_tx;
     info!("Sent valset update with txid {}", tx_hash);
     // TODO(bolten): ethers interval default is 7s, this mirrors what web30 was doing, should we adjust?
     // additionally we are mirroring only waiting for 1 confirmation by leaving that as default
     let pending_tx = pending_tx.interval(Duration::from_secs(1));
 
-    match tokio::time::timeout(timeout, pending_tx).await?? {
-        Some(_) => (),
-        None => error!(
-            "Did not receive transaction receipt when sending valset update: {}",
-            tx_hash
-        ),
-    }
-
-    let last_nonce = get_valset_nonce(gravity_contract_address, eth_client.clone()).await?;
-    if last_nonce != new_nonce {
-        error!(
-            "Current nonce is {} expected to update to nonce {}",
-            last_nonce, new_nonce
-        );
-    } else {
-        info!(
-            "Successfully updated Valset with new Nonce {:?}",
-            last_nonce
-        );
-    }
+    // the transaction is out once sent, so a receipt that doesn't come back in time is reported
+    // the same as a dropped transaction rather than as an error
+    let receipt = tokio::time::timeout(timeout, pending_tx)
+        .await
+        .unwrap_or(Ok(None))?;
+    let receipt = match receipt {
+        Some(receipt) => receipt,
+        None => {
+            error!(
+                "Did not receive transaction receipt when sending valset update: {}",
+                tx_hash
+            );
+            account_nonce.reset().await;
+            return Ok(RelayOutcome::Dropped(tx_hash));
+        }
+    };
 
-    Ok(())
+    let outcome = check_relay_receipt(receipt, &contract_call.tx, eth_client).await?;
+    info!("Successfully updated Valset with new Nonce {}", new_nonce);
+    Ok(outcome)
 }
 
 /// Returns the cost in Eth of sending this valset update
@@ -135,15 +138,15 @@ pub fn build_valset_update_contract_call<S: Signer + 'static>(
                 validators: new_addresses,
                 powers: new_powers,
                 valset_nonce: new_valset.nonce.into(),
-                reward_amount: U256::zero(),
-                reward_token: H160::zero(),
+                reward_amount: new_valset.reward_amount,
+                reward_token: new_valset.reward_token,
             },
             ValsetArgs {
                 validators: old_addresses,
                 powers: old_powers,
                 valset_nonce: old_valset.nonce.into(),
-                reward_amount: U256::zero(),
-                reward_token: H160::zero(),
+                reward_amount: old_valset.reward_amount,
+                reward_token: old_valset.reward_token,
             },
             sig_data
                 .iter()
@@ -1,11 +1,14 @@
 //! Gorc Abscissa Application
 
-use crate::{commands::EntryPoint, config::GorcConfig};
+use crate::{commands::EntryPoint, config::GorcConfig, output, telemetry};
 use abscissa_core::{
     application::{self, cell::AppCell},
     config::{self, CfgCell},
-    trace, Application, FrameworkError, StandardPaths,
+    terminal::component::Terminal,
+    Application, FrameworkError, FrameworkErrorKind, StandardPaths,
 };
+use ethereum_gravity::gas_oracle::set_gas_oracle;
+use gravity_utils::notifier;
 
 /// Application state
 pub static APP: AppCell<GorcApp> = AppCell::new();
@@ -18,6 +21,9 @@ pub struct GorcApp {
 
     /// Application state.
     state: application::State<Self>,
+
+    /// Filter for log records and spans, from the command line or RUST_LOG
+    log_filter: String,
 }
 
 /// Initialize a new application instance.
@@ -29,6 +35,7 @@ impl Default for GorcApp {
         Self {
             config: CfgCell::default(),
             state: application::State::default(),
+            log_filter: "info".to_owned(),
         }
     }
 }
@@ -58,12 +65,19 @@ impl Application for GorcApp {
     /// If you would like to add additional components to your application
     /// beyond the default ones provided by the framework, this is the place
     /// to do so.
+    ///
+    /// The framework's tracing component is replaced by `telemetry::init` once the config is loaded,
+    /// as the log format and trace exporter are configured there.
     fn register_components(&mut self, command: &Self::Cmd) -> Result<(), FrameworkError> {
-        let mut framework_components = self.framework_components(command)?;
+        output::init(command.output);
+        self.log_filter = log_filter(command);
+        let terminal = Terminal::new(self.term_colors(command));
         let mut app_components = self.state.components_mut();
-        framework_components.push(Box::new(abscissa_tokio::TokioComponent::new()?));
 
-        app_components.register(framework_components)
+        app_components.register(vec![
+            Box::new(terminal),
+            Box::new(abscissa_tokio::TokioComponent::new()?),
+        ])
     }
 
     /// Post-configuration lifecycle callback.
@@ -75,25 +89,29 @@ impl Application for GorcApp {
         // Configure components
         let mut components = self.state.components_mut();
         components.after_config(&config)?;
+        telemetry::init(&self.log_filter, &config.telemetry);
+        let gas_oracle = config
+            .ethereum
+            .gas_oracle
+            .gas_oracle()
+            .map_err(|e| FrameworkErrorKind::ConfigError.context(e))?;
+        set_gas_oracle(gas_oracle);
+        if let Some(notifier_config) = config.notifier_config() {
+            notifier::init(notifier_config);
+        }
         self.config.set_once(config);
         Ok(())
     }
+}
 
-    /// Get tracing configuration from command-line options
-    fn tracing_config(&self, command: &EntryPoint) -> trace::Config {
-        if command.verbose {
-            trace::Config::verbose()
-        } else {
-            match std::env::var("RUST_LOG") {
-                Ok(val) => {
-                    if !val.is_empty() {
-                        val.into()
-                    } else {
-                        trace::Config::default()
-                    }
-                }
-                Err(_) => trace::Config::default(),
-            }
+/// Get the log filter from command-line options
+fn log_filter(command: &EntryPoint) -> String {
+    if command.verbose {
+        "debug".to_owned()
+    } else {
+        match std::env::var("RUST_LOG") {
+            Ok(val) if !val.is_empty() => val,
+            _ => "info".to_owned(),
         }
     }
 }
@@ -14,6 +14,7 @@ mod tests;
 mod tx;
 
 use crate::config::GorcConfig;
+use crate::output::OutputFormat;
 use abscissa_core::{clap::Parser, Command, Configurable, FrameworkError, Runnable};
 use std::path::PathBuf;
 
@@ -67,6 +68,10 @@ pub struct EntryPoint {
     /// Use the specified config file
     #[clap(short, long)]
     pub config: Option<String>,
+
+    /// Print command results as text or as a single JSON document
+    #[clap(long, global = true, arg_enum, default_value = "text")]
+    pub output: OutputFormat,
 }
 
 impl Runnable for EntryPoint {
@@ -1,5 +1,6 @@
 use crate::application::APP;
-use abscissa_core::{clap::Parser, status_err, Application, Command, Runnable};
+use crate::output::{self, CoinOutput, TxOutput};
+use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use clarity::Uint256;
 use cosmos_gravity::{
     crypto::CosmosSigner,
@@ -9,7 +10,9 @@ use deep_space::coin::Coin;
 use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::DenomToErc20Request;
 use gravity_utils::connection_prep::{check_for_fee_denom, create_rpc_connections};
-use std::{process::exit, time::Duration};
+use gravity_utils::ethereum::format_eth_address;
+use serde::Serialize;
+use std::time::Duration;
 
 const TIMEOUT: Duration = Duration::from_secs(60);
 
@@ -22,6 +25,19 @@ pub struct CosmosToEthCmd {
     pub flag_no_batch: bool,
 }
 
+#[derive(Debug, Default, Serialize)]
+struct CosmosToEthOutput {
+    sender: String,
+    denom: String,
+    erc20: String,
+    eth_destination: String,
+    amount: String,
+    times: usize,
+    balances: Vec<CoinOutput>,
+    sends: Vec<TxOutput>,
+    batch_requested: bool,
+}
+
 pub fn one_eth() -> f64 {
     1000000000000000000f64
 }
@@ -62,134 +78,150 @@ impl Runnable for CosmosToEthCmd {
         let cosmos_address = cosmos_key.to_address(cosmos_prefix).unwrap();
         let cosmos_grpc = config.cosmos.grpc.trim();
         let cosmos_granter = config.cosmos.granter.clone();
-        println!("Sending from Cosmos address {cosmos_address}");
+        output::progress(format!("Sending from Cosmos address {cosmos_address}"));
         abscissa_tokio::run_with_actix(&APP, async {
-        let connections = create_rpc_connections(
-            cosmos_prefix.to_string(),
-            Some(cosmos_grpc.to_string()),
-            None,
-            TIMEOUT,
-        )
-        .await;
-        let contact = connections.contact.unwrap();
-        let mut grpc = connections.grpc.unwrap();
-        let res = grpc
-            .denom_to_erc20(DenomToErc20Request {
+            let mut result = CosmosToEthOutput {
+                sender: cosmos_address.to_string(),
                 denom: gravity_denom.clone(),
-            })
+                amount: amount.to_string(),
+                ..Default::default()
+            };
+
+            let connections = create_rpc_connections(
+                cosmos_prefix.to_string(),
+                Some(cosmos_grpc.to_string()),
+                None,
+                TIMEOUT,
+            )
             .await;
-        match res {
-            Ok(val) => println!(
-                "Asset {} has ERC20 representation {}",
-                gravity_denom,
-                val.into_inner().erc20
-            ),
-            Err(_e) => {
-                println!(
-                    "Asset {gravity_denom} has no ERC20 representation, you may need to deploy an ERC20 for it!"
-                );
-                exit(1);
+            let contact = connections.contact.unwrap();
+            let mut grpc = connections.grpc.unwrap();
+            let res = grpc
+                .denom_to_erc20(DenomToErc20Request {
+                    denom: gravity_denom.clone(),
+                })
+                .await;
+            match res {
+                Ok(val) => {
+                    result.erc20 = val.into_inner().erc20;
+                    output::progress(format!(
+                        "Asset {} has ERC20 representation {}",
+                        gravity_denom, result.erc20
+                    ));
+                }
+                Err(_e) => output::fail(
+                    "no_erc20_representation",
+                    format!("Asset {gravity_denom} has no ERC20 representation, you may need to deploy an ERC20 for it!"),
+                ),
             }
-        }
-
-        let amount = Coin {
-            amount: amount.clone(),
-            denom: gravity_denom.clone(),
-        };
-        let bridge_fee = Coin {
-            amount: 1u64.into(),
-            denom: gravity_denom.clone(),
-        };
-
-        let eth_dest = self.args.get(3).expect("ethereum destination is required");
-        let eth_dest: EthAddress = eth_dest.parse().expect("cannot parse ethereum address");
-        check_for_fee_denom(&gravity_denom, cosmos_address, &contact).await;
-
-        let balances = contact
-            .get_balances(cosmos_address)
-            .await
-            .expect("Failed to get balances!");
-        let mut found = None;
-        for coin in balances.iter() {
-            if coin.denom == gravity_denom {
-                found = Some(coin);
+
+            let amount = Coin {
+                amount: amount.clone(),
+                denom: gravity_denom.clone(),
+            };
+            let bridge_fee = Coin {
+                amount: 1u64.into(),
+                denom: gravity_denom.clone(),
+            };
+
+            let eth_dest = self.args.get(3).expect("ethereum destination is required");
+            let eth_dest: EthAddress = eth_dest.parse().expect("cannot parse ethereum address");
+            result.eth_destination = format_eth_address(eth_dest);
+            check_for_fee_denom(&gravity_denom, cosmos_address, &contact).await;
+
+            let balances = contact
+                .get_balances(cosmos_address)
+                .await
+                .expect("Failed to get balances!");
+            let mut found = None;
+            for coin in balances.iter() {
+                if coin.denom == gravity_denom {
+                    found = Some(coin);
+                }
             }
-        }
+            result.balances = balances.iter().map(CoinOutput::from).collect();
 
-        println!("Cosmos balances {balances:?}");
+            output::progress(format!("Cosmos balances {balances:?}"));
 
-        let times = self.args.get(4).expect("times is required");
-        let times = times.parse::<usize>().expect("cannot parse times");
+            let times = self.args.get(4).expect("times is required");
+            let times = times.parse::<usize>().expect("cannot parse times");
+            result.times = times;
 
-        match found {
-            None => panic!("You don't have any {gravity_denom} tokens!"),
-            Some(found) => {
-                if amount.amount.clone() * times.into() >= found.amount && times == 1 {
-                    if is_cosmos_originated {
-                        panic!("Your transfer of {} {} tokens is greater than your balance of {} tokens. Remember you need some to pay for fees!", print_atom(amount.amount), gravity_denom, print_atom(found.amount.clone()));
-                    } else {
-                        panic!("Your transfer of {} {} tokens is greater than your balance of {} tokens. Remember you need some to pay for fees!", print_eth(amount.amount), gravity_denom, print_eth(found.amount.clone()));
-                    }
-                } else if amount.amount.clone() * times.into() >= found.amount {
-                    if is_cosmos_originated {
-                        panic!("Your transfer of {} * {} {} tokens is greater than your balance of {} tokens. Try to reduce the amount or the --times parameter", print_atom(amount.amount), times, gravity_denom, print_atom(found.amount.clone()));
-                    } else {
-                        panic!("Your transfer of {} * {} {} tokens is greater than your balance of {} tokens. Try to reduce the amount or the --times parameter", print_eth(amount.amount), times, gravity_denom, print_eth(found.amount.clone()));
+            let print_amount = |amount: Uint256| {
+                if is_cosmos_originated {
+                    print_atom(amount)
+                } else {
+                    print_eth(amount)
+                }
+            };
+            match found {
+                None => output::fail(
+                    "insufficient_balance",
+                    format!("You don't have any {gravity_denom} tokens!"),
+                ),
+                Some(found) => {
+                    if amount.amount.clone() * times.into() >= found.amount && times == 1 {
+                        output::fail("insufficient_balance", format!("Your transfer of {} {} tokens is greater than your balance of {} tokens. Remember you need some to pay for fees!", print_amount(amount.amount.clone()), gravity_denom, print_amount(found.amount.clone())));
+                    } else if amount.amount.clone() * times.into() >= found.amount {
+                        output::fail("insufficient_balance", format!("Your transfer of {} * {} {} tokens is greater than your balance of {} tokens. Try to reduce the amount or the --times parameter", print_amount(amount.amount.clone()), times, gravity_denom, print_amount(found.amount.clone())));
                     }
                 }
             }
-        }
-
-        let mut successful_sends = 0;
-        for _ in 0..times {
-            println!(
-                "Locking {} / {} into the batch pool",
-                amount.clone(),
-                gravity_denom
-            );
-            let res = send_to_eth(
-                cosmos_key.clone(),
-                cosmos_granter.clone(),
-                eth_dest,
-                amount.clone(),
-                bridge_fee.clone(),
-                config.cosmos.gas_price.as_tuple(),
-                config.cosmos.gas_limit,
-                &contact,
-                1.0
-            )
-            .await;
-            match res {
-                Ok(tx_id) => {
-                    successful_sends += 1;
-                    println!("Send to Eth txid {}", tx_id.txhash);
+
+            for _ in 0..times {
+                output::progress(format!(
+                    "Locking {} / {} into the batch pool",
+                    amount.clone(),
+                    gravity_denom
+                ));
+                let res = send_to_eth(
+                    cosmos_key.clone(),
+                    cosmos_granter.clone(),
+                    eth_dest,
+                    amount.clone(),
+                    bridge_fee.clone(),
+                    config.cosmos.gas_price.as_tuple(),
+                    config.cosmos.gas_limit,
+                    &contact,
+                    1.0,
+                )
+                .await;
+                match res {
+                    Ok(tx_id) => {
+                        output::progress(format!("Send to Eth txid {}", tx_id.txhash));
+                        result.sends.push(TxOutput::sent(tx_id.txhash));
+                    }
+                    Err(e) => {
+                        output::progress(format!("Failed to send tokens! {e:?}"));
+                        result.sends.push(TxOutput::failed(e));
+                    }
                 }
-                Err(e) => println!("Failed to send tokens! {e:?}"),
             }
-        }
 
-        if successful_sends > 0 {
-            if !self.flag_no_batch {
-                println!("Requesting a batch to push transaction along immediately");
+            if result.sends.iter().all(|send| send.tx_hash.is_none()) {
+                output::progress("No successful sends, no batch will be sent");
+            } else if !self.flag_no_batch {
+                output::progress("Requesting a batch to push transaction along immediately");
                 send_request_batch_tx(
                     cosmos_key,
                     cosmos_granter.clone(),
                     gravity_denom,
                     config.cosmos.gas_price.as_tuple(),
                     config.cosmos.gas_limit,
-                    &contact,config.cosmos.gas_adjustment
+                    &contact,
+                    config.cosmos.gas_adjustment,
                 )
                 .await
                 .expect("Failed to request batch");
+                result.batch_requested = true;
             } else {
-                println!("--no-batch specified, your transfer will wait until someone requests a batch for this token type")
+                output::progress("--no-batch specified, your transfer will wait until someone requests a batch for this token type")
             }
-        } else {
-            println!("No successful sends, no batch will be sent")
-        }
+
+            output::print(&result, |_| {});
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
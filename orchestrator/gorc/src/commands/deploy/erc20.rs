@@ -1,4 +1,4 @@
-use crate::{application::APP, prelude::*};
+use crate::{application::APP, output, prelude::*};
 use abscissa_core::{clap::Parser, Command, Runnable};
 use ethereum_gravity::deploy_erc20::deploy_erc20;
 use ethers::prelude::*;
@@ -7,8 +7,8 @@ use gravity_utils::{
     connection_prep::{check_for_eth, create_rpc_connections},
     ethereum::{downcast_to_u64, format_eth_hash},
 };
+use serde::Serialize;
 use std::convert::TryFrom;
-use std::process::exit;
 use std::{sync::Arc, time::Duration};
 use tokio::time::sleep as delay_for;
 
@@ -24,14 +24,20 @@ pub struct Erc20 {
     gas_multiplier: f64,
 }
 
+#[derive(Debug, Serialize)]
+struct DeployErc20Output {
+    denom: String,
+    tx_hash: String,
+    erc20: String,
+}
+
 impl Runnable for Erc20 {
     fn run(&self) {
         abscissa_tokio::run_with_actix(&APP, async {
             self.deploy().await;
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
@@ -82,7 +88,7 @@ impl Erc20 {
             .expect("Couldn't get erc-20 params")
             .into_inner();
 
-        println!("Starting deploy of ERC20");
+        output::progress("Starting deploy of ERC20");
 
         let res = deploy_erc20(
             res.base_denom,
@@ -95,10 +101,10 @@ impl Erc20 {
             eth_client.clone(),
         )
         .await
-        .expect("Could not deploy ERC20");
+        .unwrap_or_else(|e| output::fail("deploy_failed", format!("Could not deploy ERC20 {e:?}")));
+        let tx_hash = format_eth_hash(res);
 
-        println!("We have deployed ERC20 contract at tx hash {}, waiting to see if the Cosmos chain choses to adopt it",
-            format_eth_hash(res));
+        output::progress(format!("We have deployed ERC20 contract at tx hash {tx_hash}, waiting to see if the Cosmos chain choses to adopt it"));
 
         match tokio::time::timeout(Duration::from_secs(300), async {
             loop {
@@ -117,19 +123,23 @@ impl Erc20 {
         .await
         {
             Ok(val) => {
-                println!(
-                    "Asset {} has accepted new ERC20 representation {}",
-                    denom,
-                    val.into_inner().erc20
-                );
-                exit(0);
-            }
-            Err(_) => {
-                println!(
-                    "Your ERC20 contract was not adopted, double check the metadata and try again"
-                );
-                exit(1);
+                let result = DeployErc20Output {
+                    denom: denom.clone(),
+                    tx_hash,
+                    erc20: val.into_inner().erc20,
+                };
+                output::print(&result, |result| {
+                    println!(
+                        "Asset {} has accepted new ERC20 representation {}",
+                        result.denom, result.erc20
+                    )
+                });
+                std::process::exit(0);
             }
+            Err(_) => output::fail(
+                "erc20_not_adopted",
+                format!("Your ERC20 contract at tx hash {tx_hash} was not adopted, double check the metadata and try again"),
+            ),
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::application::APP;
-use abscissa_core::{clap::Parser, status_err, Application, Command, Runnable};
+use crate::output::{self, TxOutput};
+use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use deep_space::address::Address as CosmosAddress;
 use ethereum_gravity::erc20_utils::get_erc20_balance;
 use ethereum_gravity::send_to_cosmos::send_to_cosmos;
@@ -7,8 +8,9 @@ use ethers::prelude::*;
 use ethers::types::Address as EthAddress;
 use gravity_utils::{
     connection_prep::{check_for_eth, create_rpc_connections},
-    ethereum::downcast_to_u64,
+    ethereum::{downcast_to_u64, format_eth_address},
 };
+use serde::Serialize;
 use std::{sync::Arc, time::Duration};
 
 const TIMEOUT: Duration = Duration::from_secs(60);
@@ -19,6 +21,17 @@ pub struct EthToCosmosCmd {
     pub args: Vec<String>,
 }
 
+#[derive(Debug, Default, Serialize)]
+struct EthToCosmosOutput {
+    sender: String,
+    erc20: String,
+    cosmos_destination: String,
+    amount: String,
+    times: usize,
+    erc20_balance: String,
+    sends: Vec<TxOutput>,
+}
+
 impl Runnable for EthToCosmosCmd {
     fn run(&self) {
         let config = APP.config();
@@ -74,25 +87,34 @@ impl Runnable for EthToCosmosCmd {
             let times_u256 = U256::from_dec_str(times).expect("cannot parse times as U256");
 
             if erc20_balance == 0u8.into() {
-                panic!(
-                    "You have zero {contract_address} tokens, please double check your sender and erc20 addresses!"
+                output::fail(
+                    "insufficient_balance",
+                    format!("You have zero {contract_address} tokens, please double check your sender and erc20 addresses!"),
                 );
             } else if amount * times_u256 > erc20_balance {
-                panic!(
-                    "Insufficient balance {} > {}",
-                    amount * times_u256,
-                    erc20_balance
+                output::fail(
+                    "insufficient_balance",
+                    format!("Insufficient balance {} > {}", amount * times_u256, erc20_balance),
                 );
             }
 
+            let mut result = EthToCosmosOutput {
+                sender: format_eth_address(ethereum_address),
+                erc20: format_eth_address(erc20_address),
+                cosmos_destination: cosmos_dest.to_string(),
+                amount: amount.to_string(),
+                times: times_usize,
+                erc20_balance: erc20_balance.to_string(),
+                sends: Vec::new(),
+            };
             for _ in 0..times_usize {
-                println!(
+                output::progress(format!(
                     "Sending {} / {} to Cosmos from {} to {}",
                     init_amount.parse::<f64>().unwrap(),
                     erc20_address,
                     ethereum_address,
                     cosmos_dest
-                );
+                ));
                 // we send some erc20 tokens to the gravity contract to register a deposit
                 let res = send_to_cosmos(
                     erc20_address,
@@ -104,13 +126,21 @@ impl Runnable for EthToCosmosCmd {
                 )
                 .await;
                 match res {
-                    Ok(tx_id) => println!("Send to Cosmos txid: {tx_id}"),
-                    Err(e) => println!("Failed to send tokens! {e:?}"),
+                    Ok(tx_id) => {
+                        output::progress(format!("Send to Cosmos txid: {tx_id}"));
+                        result.sends.push(TxOutput::sent(format!("{tx_id:#066x}")));
+                    }
+                    Err(e) => {
+                        output::progress(format!("Failed to send tokens! {e:?}"));
+                        result.sends.push(TxOutput::failed(e));
+                    }
                 }
             }
+
+            output::print(&result, |_| {});
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
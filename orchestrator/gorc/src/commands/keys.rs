@@ -2,6 +2,7 @@ mod cosmos;
 mod eth;
 
 use abscissa_core::{clap::Parser, Command, Runnable};
+use serde::Serialize;
 
 use crate::commands::keys::cosmos::CosmosKeysCmd;
 use crate::commands::keys::eth::EthKeysCmd;
@@ -16,3 +17,42 @@ pub enum KeysCmd {
     #[clap(subcommand)]
     Eth(EthKeysCmd),
 }
+
+/// A key as printed by the keys commands
+#[derive(Debug, Serialize)]
+pub struct KeyOutput {
+    pub name: String,
+    pub address: String,
+    /// only set when a key is generated, so the mnemonic can be recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+}
+
+impl KeyOutput {
+    pub fn print_text(&self, show_name: bool) {
+        if show_name {
+            print!("{}\t", self.name);
+        }
+        println!("{}", self.address);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletedKeyOutput {
+    pub name: String,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenamedKeyOutput {
+    pub name: String,
+    pub previous_name: String,
+}
+
+/// Reports that a key already exists, the command then exits cleanly as it always has
+pub fn key_exists(name: &str) {
+    crate::output::error(
+        "key_exists",
+        format!("Key {name} already exists, exiting. Use --overwrite to replace it"),
+    )
+}
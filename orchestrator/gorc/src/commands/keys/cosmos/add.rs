@@ -1,6 +1,7 @@
-use super::show::ShowCosmosKeyCmd;
-use crate::application::APP;
+use super::show::cosmos_key_output;
+use crate::commands::keys::key_exists;
 use crate::config::Keystore;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use k256::pkcs8::EncodePrivateKey;
 use rand_core::OsRng;
@@ -23,21 +24,17 @@ impl Runnable for AddCosmosKeyCmd {
         let name = name.parse().expect("Could not parse name");
         if let Ok(_info) = keystore.info(&name) {
             if !self.overwrite {
-                eprintln!("Key already exists, exiting.");
+                key_exists(&name.to_string());
                 return;
             }
         }
 
         let mnemonic = bip32::Mnemonic::random(OsRng, Default::default());
-        match &config.keystore {
-            Keystore::File(_path) => {
-                eprintln!("**Important** record this bip39-mnemonic in a safe place:");
-                println!("{}", mnemonic.phrase());
-            }
-            Keystore::Aws => {}
-        }
-
         let seed = mnemonic.to_seed("");
+        let mnemonic = match &config.keystore {
+            Keystore::File(_path) => Some(mnemonic.phrase().to_string()),
+            Keystore::Aws => None,
+        };
 
         let path = config.cosmos.key_derivation_path.clone();
         let path = path
@@ -52,8 +49,14 @@ impl Runnable for AddCosmosKeyCmd {
 
         keystore.store(&name, &key).expect("Could not store key");
 
-        let args = vec![name.to_string()];
-        let show_cmd = ShowCosmosKeyCmd { args };
-        show_cmd.run();
+        let mut key = cosmos_key_output(&name.to_string());
+        key.mnemonic = mnemonic;
+        output::print(&key, |key| {
+            if let Some(mnemonic) = &key.mnemonic {
+                eprintln!("**Important** record this bip39-mnemonic in a safe place:");
+                println!("{mnemonic}");
+            }
+            key.print_text(true)
+        });
     }
 }
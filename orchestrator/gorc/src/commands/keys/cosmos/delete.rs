@@ -1,4 +1,5 @@
-use crate::application::APP;
+use crate::commands::keys::DeletedKeyOutput;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 
 /// Delete a Cosmos Key
@@ -17,5 +18,11 @@ impl Runnable for DeleteCosmosKeyCmd {
         let name = name.parse().expect("Could not parse name");
         // Delete keyname after locating file from path and key name.
         keystore.delete(&name).unwrap();
+
+        let result = DeletedKeyOutput {
+            name: name.to_string(),
+            deleted: true,
+        };
+        output::print(&result, |_| {});
     }
 }
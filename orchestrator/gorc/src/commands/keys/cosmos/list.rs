@@ -1,5 +1,6 @@
-use super::show::ShowCosmosKeyCmd;
-use crate::{application::APP, config::Keystore};
+use super::show::cosmos_key_output;
+use crate::commands::keys::KeyOutput;
+use crate::{application::APP, config::Keystore, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use std::path::Path;
 
@@ -11,6 +12,7 @@ pub struct ListCosmosKeyCmd {}
 impl Runnable for ListCosmosKeyCmd {
     fn run(&self) {
         let config = APP.config();
+        let mut keys: Vec<KeyOutput> = Vec::new();
         if let Keystore::File(path) = &config.keystore {
             let keystore = Path::new(&path);
 
@@ -21,13 +23,17 @@ impl Runnable for ListCosmosKeyCmd {
                         if extension == "pem" {
                             let name = path.file_stem().unwrap();
                             let name = name.to_str().unwrap();
-                            let args = vec![name.to_string()];
-                            let show_cmd = ShowCosmosKeyCmd { args };
-                            show_cmd.run();
+                            keys.push(cosmos_key_output(name));
                         }
                     }
                 }
             }
         }
+
+        output::print(&keys, |keys| {
+            for key in keys {
+                key.print_text(true);
+            }
+        });
     }
 }
@@ -1,5 +1,6 @@
-use super::show::ShowCosmosKeyCmd;
-use crate::application::APP;
+use super::show::cosmos_key_output;
+use crate::commands::keys::key_exists;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use k256::pkcs8::EncodePrivateKey;
 
@@ -24,7 +25,7 @@ impl Runnable for RecoverCosmosKeyCmd {
         let name = name.parse().expect("Could not parse name");
         if let Ok(_info) = keystore.info(&name) {
             if !self.overwrite {
-                eprintln!("Key already exists, exiting.");
+                key_exists(&name.to_string());
                 return;
             }
         }
@@ -53,8 +54,7 @@ impl Runnable for RecoverCosmosKeyCmd {
 
         keystore.store(&name, &key).expect("Could not store key");
 
-        let args = vec![name.to_string()];
-        let show_cmd = ShowCosmosKeyCmd { args };
-        show_cmd.run();
+        let key = cosmos_key_output(&name.to_string());
+        output::print(&key, |key| key.print_text(true));
     }
 }
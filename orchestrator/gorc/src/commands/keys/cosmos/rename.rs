@@ -1,4 +1,5 @@
-use crate::application::APP;
+use crate::commands::keys::{key_exists, RenamedKeyOutput};
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 
 /// Rename a Cosmos Key
@@ -23,7 +24,7 @@ impl Runnable for RenameCosmosKeyCmd {
         let new_name = new_name.parse().expect("Could not parse new_name");
         if let Ok(_info) = keystore.info(&new_name) {
             if !self.overwrite {
-                println!("Key already exists, exiting.");
+                key_exists(&new_name.to_string());
                 return;
             }
         }
@@ -31,5 +32,11 @@ impl Runnable for RenameCosmosKeyCmd {
         let key = keystore.load(&name).expect("Could not load key");
         keystore.store(&new_name, &key).unwrap();
         keystore.delete(&name).unwrap();
+
+        let result = RenamedKeyOutput {
+            name: new_name.to_string(),
+            previous_name: name.to_string(),
+        };
+        output::print(&result, |_| {});
     }
 }
@@ -1,4 +1,5 @@
-use crate::application::APP;
+use crate::commands::keys::KeyOutput;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use cosmos_gravity::crypto::CosmosSigner;
 
@@ -11,14 +12,23 @@ pub struct ShowCosmosKeyCmd {
 // Entry point for `gorc keys cosmos show [name]`
 impl Runnable for ShowCosmosKeyCmd {
     fn run(&self) {
-        let config = APP.config();
         let name = self.args.get(0).expect("name is required");
-        let key = config.load_deep_space_key(name.clone());
+        let key = cosmos_key_output(name);
+        output::print(&key, |key| key.print_text(true));
+    }
+}
+
+pub fn cosmos_key_output(name: &str) -> KeyOutput {
+    let config = APP.config();
+    let key = config.load_deep_space_key(name.to_string());
 
-        let address = key
-            .to_address(config.cosmos.prefix.trim())
-            .expect("Could not generate public key");
+    let address = key
+        .to_address(config.cosmos.prefix.trim())
+        .expect("Could not generate public key");
 
-        println!("{name}\t{address}")
+    KeyOutput {
+        name: name.to_string(),
+        address: address.to_string(),
+        mnemonic: None,
     }
 }
@@ -1,6 +1,7 @@
-use super::show::ShowEthKeyCmd;
-use crate::application::APP;
+use super::show::eth_key_output;
+use crate::commands::keys::key_exists;
 use crate::config::Keystore;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use k256::pkcs8::EncodePrivateKey;
 use rand_core::OsRng;
@@ -25,21 +26,17 @@ impl Runnable for AddEthKeyCmd {
         let name = name.parse().expect("Could not parse name");
         if let Ok(_info) = keystore.info(&name) {
             if !self.overwrite {
-                eprintln!("Key already exists, exiting.");
+                key_exists(&name.to_string());
                 return;
             }
         }
 
         let mnemonic = bip32::Mnemonic::random(OsRng, Default::default());
-        match &config.keystore {
-            Keystore::File(_path) => {
-                eprintln!("**Important** record this bip39-mnemonic in a safe place:");
-                println!("{}", mnemonic.phrase());
-            }
-            Keystore::Aws => {}
-        }
-
         let seed = mnemonic.to_seed("");
+        let mnemonic = match &config.keystore {
+            Keystore::File(_path) => Some(mnemonic.phrase().to_string()),
+            Keystore::Aws => None,
+        };
 
         let path = config.ethereum.key_derivation_path.trim();
         let path = path
@@ -54,10 +51,14 @@ impl Runnable for AddEthKeyCmd {
 
         keystore.store(&name, &key).expect("Could not store key");
 
-        let show_cmd = ShowEthKeyCmd {
-            args: vec![name.to_string()],
-            show_name: false,
-        };
-        show_cmd.run();
+        let mut key = eth_key_output(&name.to_string());
+        key.mnemonic = mnemonic;
+        output::print(&key, |key| {
+            if let Some(mnemonic) = &key.mnemonic {
+                eprintln!("**Important** record this bip39-mnemonic in a safe place:");
+                println!("{mnemonic}");
+            }
+            key.print_text(false)
+        });
     }
 }
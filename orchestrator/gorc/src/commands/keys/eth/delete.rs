@@ -1,4 +1,5 @@
-use crate::application::APP;
+use crate::commands::keys::DeletedKeyOutput;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 
 /// Delete an Eth Key
@@ -17,5 +18,11 @@ impl Runnable for DeleteEthKeyCmd {
         let name = self.args.get(0).expect("name is required");
         let name = name.parse().expect("Could not parse name");
         keystore.delete(&name).expect("Could not delete key");
+
+        let result = DeletedKeyOutput {
+            name: name.to_string(),
+            deleted: true,
+        };
+        output::print(&result, |_| {});
     }
 }
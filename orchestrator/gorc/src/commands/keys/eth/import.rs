@@ -1,5 +1,6 @@
-use super::show::ShowEthKeyCmd;
-use crate::application::APP;
+use super::show::eth_key_output;
+use crate::commands::keys::key_exists;
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use bip32::PrivateKey;
 use k256::{pkcs8::EncodePrivateKey, SecretKey};
@@ -25,7 +26,7 @@ impl Runnable for ImportEthKeyCmd {
         let name = name.parse().expect("Could not parse name");
         if let Ok(_info) = keystore.info(&name) {
             if !self.overwrite {
-                eprintln!("Key already exists, exiting.");
+                key_exists(&name.to_string());
                 return;
             }
         }
@@ -48,10 +49,7 @@ impl Runnable for ImportEthKeyCmd {
 
         keystore.store(&name, &key).expect("Could not store key");
 
-        let show_cmd = ShowEthKeyCmd {
-            args: vec![name.to_string()],
-            show_name: false,
-        };
-        show_cmd.run();
+        let key = eth_key_output(&name.to_string());
+        output::print(&key, |key| key.print_text(false));
     }
 }
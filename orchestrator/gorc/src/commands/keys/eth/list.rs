@@ -1,5 +1,6 @@
-use super::show::ShowEthKeyCmd;
-use crate::{application::APP, config::Keystore};
+use super::show::eth_key_output;
+use crate::commands::keys::KeyOutput;
+use crate::{application::APP, config::Keystore, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use std::path;
 
@@ -11,6 +12,7 @@ pub struct ListEthKeyCmd {}
 impl Runnable for ListEthKeyCmd {
     fn run(&self) {
         let config = APP.config();
+        let mut keys: Vec<KeyOutput> = Vec::new();
         if let Keystore::File(path) = &config.keystore {
             let keystore = path::Path::new(&path);
 
@@ -21,15 +23,17 @@ impl Runnable for ListEthKeyCmd {
                         if extension == "pem" {
                             let name = path.file_stem().unwrap();
                             let name = name.to_str().unwrap();
-                            let show_cmd = ShowEthKeyCmd {
-                                args: vec![name.to_string()],
-                                show_name: true,
-                            };
-                            show_cmd.run();
+                            keys.push(eth_key_output(name));
                         }
                     }
                 }
             }
         }
+
+        output::print(&keys, |keys| {
+            for key in keys {
+                key.print_text(true);
+            }
+        });
     }
 }
@@ -1,4 +1,5 @@
-use crate::application::APP;
+use crate::commands::keys::{key_exists, RenamedKeyOutput};
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 
 /// Rename an Eth Key
@@ -23,7 +24,7 @@ impl Runnable for RenameEthKeyCmd {
         let new_name = new_name.parse().expect("Could not parse new-name");
         if let Ok(_info) = keystore.info(&new_name) {
             if !self.overwrite {
-                eprintln!("Key already exists, exiting.");
+                key_exists(&new_name.to_string());
                 return;
             }
         }
@@ -33,5 +34,11 @@ impl Runnable for RenameEthKeyCmd {
             .store(&new_name, &key)
             .expect("Could not store key");
         keystore.delete(&name).expect("Could not delete key");
+
+        let result = RenamedKeyOutput {
+            name: new_name.to_string(),
+            previous_name: name.to_string(),
+        };
+        output::print(&result, |_| {});
     }
 }
@@ -1,4 +1,6 @@
 use crate::application::APP;
+use crate::commands::keys::KeyOutput;
+use crate::output;
 use crate::utils::aws::verifying_key_to_address;
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use cosmos_gravity::crypto::EthPubkey;
@@ -15,18 +17,22 @@ pub struct ShowEthKeyCmd {
 // Entry point for `gorc keys eth show [name]`
 impl Runnable for ShowEthKeyCmd {
     fn run(&self) {
-        let config = APP.config();
         let name = self.args.get(0).expect("name is required");
+        let key = eth_key_output(name);
+        output::print(&key, |key| key.print_text(self.show_name));
+    }
+}
 
-        let key = config.load_ethers_wallet(name.clone());
-
-        let pub_key = key.to_public_key();
-        let address = verifying_key_to_address(&pub_key);
+pub fn eth_key_output(name: &str) -> KeyOutput {
+    let config = APP.config();
+    let key = config.load_ethers_wallet(name.to_string());
 
-        if self.show_name {
-            print!("{name}\t");
-        }
+    let pub_key = key.to_public_key();
+    let address = verifying_key_to_address(&pub_key);
 
-        println!("{address:?}");
+    KeyOutput {
+        name: name.to_string(),
+        address: format!("{address:?}"),
+        mnemonic: None,
     }
 }
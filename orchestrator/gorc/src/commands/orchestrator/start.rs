@@ -1,6 +1,7 @@
-use crate::{application::APP, prelude::*};
+use crate::{application::APP, output, prelude::*};
 use abscissa_core::{clap::Parser, Command, Runnable};
 use cosmos_gravity::crypto::CosmosSigner;
+use ethereum_gravity::utils::multiplier_to_bps;
 use ethers::{prelude::*, types::Address as EthAddress};
 use gravity_utils::types::config::RelayerMode;
 use gravity_utils::{
@@ -61,6 +62,10 @@ impl Runnable for StartCommand {
 
         let fees_denom = config.gravity.fees_denom.clone();
 
+        if !self.orchestrator_only {
+            config.relayer.init_relay_history();
+        }
+
         let timeout = min(
             min(ETH_SIGNER_LOOP_SPEED, ETH_ORACLE_LOOP_SPEED),
             RELAYER_LOOP_SPEED,
@@ -143,6 +148,7 @@ impl Runnable for StartCommand {
                 .expect("Incorrect mode, possible value are: AlwaysRelay, Api or File");
             info!("Relayer using mode {mode:?}");
 
+            orchestrator::health::set_ready_loop_periods(config.metrics.ready_loop_periods);
             orchestrator_main_loop(
                 cosmos_key,
                 cosmos_granter,
@@ -154,20 +160,27 @@ impl Runnable for StartCommand {
                 gas_price,
                 config.cosmos.gas_limit,
                 &config.metrics.listen_addr,
-                config.ethereum.gas_price_multiplier,
-                config.ethereum.gas_multiplier,
+                multiplier_to_bps(config.ethereum.gas_price_multiplier),
+                multiplier_to_bps(config.ethereum.gas_multiplier),
                 config.ethereum.blocks_to_search,
                 config.cosmos.gas_adjustment,
                 self.orchestrator_only,
                 config.cosmos.msg_batch_size,
                 mode,
                 supported_contract,
+                config.relayer.logic_call_filter(),
+                config.batch_request_config(),
+                config.relayer.spend_limits(),
+                config.relayer.watch_pending_relays,
+                config.relayer.gas_schedule(),
+                config.participation_monitor_config(),
+                config.bridge_lag_config(),
+                config.claim_verifier_config(),
             )
             .await;
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            std::process::exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
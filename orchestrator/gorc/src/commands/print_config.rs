@@ -1,5 +1,6 @@
 use crate::application::APP;
 use crate::config::GorcConfig;
+use crate::output;
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 
 /// Command for printing configurations
@@ -14,17 +15,11 @@ impl Runnable for PrintConfigCmd {
         let config = if self.show_default {
             GorcConfig::default()
         } else {
-            let config = APP.config();
-            GorcConfig {
-                keystore: config.keystore.to_owned(),
-                gravity: config.gravity.to_owned(),
-                ethereum: config.ethereum.to_owned(),
-                cosmos: config.cosmos.to_owned(),
-                metrics: config.metrics.to_owned(),
-                relayer: config.relayer.to_owned(),
-            }
+            GorcConfig::clone(&APP.config())
         };
 
-        print!("{}", toml::to_string(&config).unwrap());
+        output::print(&config, |config| {
+            print!("{}", toml::to_string(config).unwrap())
+        });
     }
 }
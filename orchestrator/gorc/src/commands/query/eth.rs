@@ -1,6 +1,6 @@
 //! `eth subcommands` subcommand
 
-use crate::{application::APP, prelude::*};
+use crate::{application::APP, output};
 use abscissa_core::{clap::Parser, Command, Runnable};
 
 /// Query Eth chain
@@ -32,8 +32,7 @@ impl Runnable for Balance {
         let _key_name = self.free[0].clone();
 
         abscissa_tokio::run(&APP, async { unimplemented!() }).unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            std::process::exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
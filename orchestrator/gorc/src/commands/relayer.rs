@@ -1,3 +1,5 @@
+mod plan;
+mod report;
 mod start;
 
 use abscissa_core::{clap::Parser, Command, Runnable};
@@ -6,4 +8,8 @@ use abscissa_core::{clap::Parser, Command, Runnable};
 #[derive(Command, Debug, Parser, Runnable)]
 pub enum RelayerCmd {
     Start(start::StartCommand),
+
+    Plan(plan::PlanCommand),
+
+    Report(report::ReportCommand),
 }
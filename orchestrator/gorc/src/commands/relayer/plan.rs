@@ -0,0 +1,219 @@
+use crate::config::{BridgeSection, GorcConfig};
+use crate::{application::APP, output, prelude::*};
+use abscissa_core::{clap::Parser, Command, Runnable};
+use ethereum_gravity::utils::multiplier_to_bps;
+use ethers::{prelude::*, types::Address as EthAddress};
+use gravity_utils::types::config::RelayerMode;
+use gravity_utils::{
+    connection_prep::create_rpc_connections,
+    ethereum::{downcast_to_u64, format_eth_address},
+};
+use relayer::fee_manager::FeeManager;
+use relayer::main_loop::LOOP_SPEED as RELAYER_LOOP_SPEED;
+use relayer::relay_plan::{plan_relays, PlannedRelay};
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Show what the relayer would relay right now and at what cost, without sending anything
+#[derive(Command, Debug, Parser)]
+pub struct PlanCommand {
+    /// Ethereum key relays would be signed with, required unless every planned bridge has its own key
+    #[clap(short, long)]
+    ethereum_key: Option<String>,
+
+    #[clap(short, long)]
+    mode: Option<String>,
+
+    /// Only plan the bridge with this name, every configured bridge is planned by default
+    #[clap(short, long)]
+    bridge: Option<String>,
+}
+
+/// The relays planned for one bridge
+#[derive(Debug, Serialize)]
+struct BridgePlan {
+    bridge: String,
+    relays: Vec<PlannedRelay>,
+}
+
+impl Runnable for PlanCommand {
+    fn run(&self) {
+        openssl_probe::init_ssl_cert_env_vars();
+        let config = APP.config();
+
+        let bridges: Vec<BridgeSection> = config
+            .relayer_bridges()
+            .into_iter()
+            .filter(|bridge| {
+                self.bridge
+                    .as_ref()
+                    .map_or(true, |name| *name == bridge.name)
+            })
+            .collect();
+        if bridges.is_empty() {
+            output::fail(
+                "config",
+                format!(
+                    "No relayer bridge named {}",
+                    self.bridge.as_deref().unwrap_or_default()
+                ),
+            );
+        }
+
+        abscissa_tokio::run_with_actix(&APP, async {
+            let mut plans = Vec::new();
+            for bridge in bridges {
+                let relays = self.plan_bridge(&config, &bridge).await;
+                plans.push(BridgePlan {
+                    bridge: bridge.name,
+                    relays,
+                });
+            }
+            output::print(&plans, |plans| print_tables(plans));
+        })
+        .unwrap_or_else(|e| {
+            output::fail("executor", format!("executor exited with error: {e}"));
+        });
+    }
+}
+
+impl PlanCommand {
+    async fn plan_bridge(&self, config: &GorcConfig, bridge: &BridgeSection) -> Vec<PlannedRelay> {
+        let mode_str = self.mode.as_deref().unwrap_or(&bridge.mode);
+        let mode = RelayerMode::from_str(mode_str)
+            .expect("Incorrect mode, possible value are: AlwaysRelay, Api or File");
+
+        let key = if bridge.ethereum_key.is_empty() {
+            self.ethereum_key.clone().unwrap_or_else(|| {
+                output::fail(
+                    "config",
+                    format!(
+                        "An Ethereum key is required for bridge {}, pass --ethereum-key or set one in its config",
+                        bridge.name
+                    ),
+                )
+            })
+        } else {
+            bridge.ethereum_key.clone()
+        };
+        let ethereum_wallet = config.load_ethers_wallet(key);
+
+        let contract_address: EthAddress = bridge
+            .contract
+            .parse()
+            .expect("Could not parse gravity contract address");
+        let mut payment_address: EthAddress = bridge
+            .payment_address
+            .parse()
+            .expect("Could not parse relayer payment address");
+
+        let mut supported_contracts: Vec<EthAddress> = Vec::new();
+        for contract in &bridge.ethereum_contracts {
+            match H160::from_str(contract) {
+                Ok(c) => supported_contracts.push(c),
+                Err(_) => error!("error parsing contract in config {contract}"),
+            }
+        }
+
+        let connections = create_rpc_connections(
+            bridge.cosmos_prefix.clone(),
+            Some(bridge.cosmos_grpc.clone()),
+            Some(bridge.ethereum_rpc.clone()),
+            RELAYER_LOOP_SPEED,
+        )
+        .await;
+
+        let mut grpc = connections.grpc.clone().unwrap();
+        let provider = connections.eth_provider.clone().unwrap();
+        let chain_id = provider
+            .get_chainid()
+            .await
+            .expect("Could not retrieve chain ID");
+        let chain_id =
+            downcast_to_u64(chain_id).expect("Chain ID overflowed when downcasting to u64");
+        let eth_client = SignerMiddleware::new(provider, ethereum_wallet.with_chain_id(chain_id));
+        let eth_client = Arc::new(eth_client);
+
+        if payment_address == EthAddress::zero() {
+            payment_address = eth_client.address()
+        }
+
+        let mut fee_manager = FeeManager::new_fee_manager_with_sources(
+            mode,
+            bridge.token_prices_path(),
+            bridge.relayer_api_url(),
+        )
+        .await
+        .expect("Could not start the fee manager");
+        plan_relays(
+            eth_client,
+            &mut grpc,
+            contract_address,
+            payment_address,
+            &mut fee_manager,
+            bridge.blocks_to_search.unwrap_or_default(),
+            supported_contracts,
+            &bridge.logic_call_filter(),
+            &bridge.gas_schedule(),
+            multiplier_to_bps(bridge.gas_price_multiplier.unwrap_or(1.0)),
+            multiplier_to_bps(bridge.gas_multiplier.unwrap_or(1.0)),
+            config.relayer.spend_limits_for(bridge),
+            &bridge.name,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            output::fail(
+                "plan_failed",
+                format!("Could not plan relays for bridge {}: {e}", bridge.name),
+            )
+        })
+    }
+}
+
+fn print_tables(plans: &[BridgePlan]) {
+    for plan in plans {
+        if plans.len() > 1 {
+            println!("Bridge {}", plan.bridge);
+        }
+        print_table(&plan.relays);
+    }
+}
+
+fn print_table(plan: &[PlannedRelay]) {
+    if plan.is_empty() {
+        println!("Nothing to relay");
+        return;
+    }
+
+    let optional = |value: Option<U256>| value.map(|v| v.to_string()).unwrap_or_default();
+    println!(
+        "{:<10} {:>8} {:<70} {:>24} {:>10} {:>14} {:>24} {:<8} REASON",
+        "TYPE", "NONCE", "FEES", "FEE VALUE", "GAS", "GAS PRICE", "COST", "DECISION"
+    );
+    for relay in plan {
+        let fees: Vec<String> = relay
+            .fees
+            .iter()
+            .map(|fee| {
+                format!(
+                    "{} {}",
+                    fee.amount,
+                    format_eth_address(fee.token_contract_address)
+                )
+            })
+            .collect();
+        println!(
+            "{:<10} {:>8} {:<70} {:>24} {:>10} {:>14} {:>24} {:<8} {}",
+            relay.relay_type.to_string(),
+            relay.nonce,
+            fees.join(", "),
+            optional(relay.fee_value),
+            optional(relay.gas),
+            optional(relay.gas_price),
+            optional(relay.cost),
+            format!("{:?}", relay.decision).to_lowercase(),
+            relay.reason
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{application::APP, output, prelude::*};
+use abscissa_core::{clap::Parser, Command, Runnable};
+use ethers::types::U256;
+use gravity_utils::ethereum::format_eth_address;
+use gravity_utils::types::config::RelayerMode;
+use relayer::fee_manager::FeeManager;
+use relayer::relay_history::{build_report, parse_since, GroupBy, RelayHistory, ReportRow};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Show what relaying earned and cost, from the relay history database
+#[derive(Command, Debug, Parser)]
+pub struct ReportCommand {
+    /// Start of the report, a UTC date such as 2024-01-31 or a duration such as 12h, 7d or 2w
+    #[clap(short, long, default_value = "7d")]
+    since: String,
+
+    /// Group relays by token or by UTC day
+    #[clap(short, long, default_value = "token")]
+    group_by: GroupBy,
+
+    /// Only report the relays of this bridge, fees are valued with its token prices
+    #[clap(short, long)]
+    bridge: Option<String>,
+
+    #[clap(short, long)]
+    mode: Option<String>,
+}
+
+impl Runnable for ReportCommand {
+    fn run(&self) {
+        let config = APP.config();
+
+        let path = config.relayer.history_path().unwrap_or_else(|| {
+            output::fail(
+                "config",
+                "No relay history is recorded, set history_path in the [relayer] section",
+            )
+        });
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let since =
+            parse_since(&self.since, now).unwrap_or_else(|e| output::fail("invalid_argument", e));
+
+        let bridges = config.relayer_bridges();
+        let bridge = match &self.bridge {
+            Some(name) => bridges
+                .iter()
+                .find(|bridge| &bridge.name == name)
+                .unwrap_or_else(|| output::fail("config", format!("Unknown bridge {name}"))),
+            None => &bridges[0],
+        };
+        let mode_str = self.mode.as_deref().unwrap_or(&bridge.mode);
+        let mode = RelayerMode::from_str(mode_str)
+            .expect("Incorrect mode, possible value are: AlwaysRelay, Api or File");
+
+        let records = RelayHistory::open(&path)
+            .and_then(|history| history.records_since(since, self.bridge.as_deref()))
+            .unwrap_or_else(|e| output::fail("history", e));
+
+        abscissa_tokio::run_with_actix(&APP, async {
+            let mut fee_manager = FeeManager::new_fee_manager_with_sources(
+                mode,
+                bridge.token_prices_path(),
+                bridge.relayer_api_url(),
+            )
+            .await
+            .expect("Could not start the fee manager");
+            let report = build_report(&records, self.group_by, &mut fee_manager).await;
+
+            output::print(&report, |report| {
+                if report.rows.is_empty() {
+                    println!("No relays since {}", self.since);
+                    return;
+                }
+                print_header();
+                for row in &report.rows {
+                    print_row(row);
+                }
+                print_row(&report.total);
+            });
+        })
+        .unwrap_or_else(|e| {
+            output::fail("executor", format!("executor exited with error: {e}"));
+        });
+    }
+}
+
+fn print_header() {
+    println!(
+        "{:<42} {:>7} {:>9} {:>8} {:>6} {:>12} {:>24} {:<70} {:>24} {:>25}",
+        "GROUP",
+        "RELAYS",
+        "SUCCEEDED",
+        "REVERTED",
+        "OTHER",
+        "GAS USED",
+        "ETH SPENT",
+        "FEES EARNED",
+        "FEES VALUE",
+        "PROFIT"
+    );
+}
+
+fn print_row(row: &ReportRow) {
+    let optional = |value: Option<U256>| value.map(|v| v.to_string()).unwrap_or_default();
+    let fees: Vec<String> = row
+        .fees_earned
+        .iter()
+        .map(|fee| {
+            format!(
+                "{} {}",
+                fee.amount,
+                format_eth_address(fee.token_contract_address)
+            )
+        })
+        .collect();
+    println!(
+        "{:<42} {:>7} {:>9} {:>8} {:>6} {:>12} {:>24} {:<70} {:>24} {:>25}",
+        row.group,
+        row.relays,
+        row.succeeded,
+        row.reverted,
+        row.other,
+        row.gas_used,
+        row.eth_spent,
+        fees.join(", "),
+        optional(row.fees_value),
+        row.profit.clone().unwrap_or_default()
+    );
+}
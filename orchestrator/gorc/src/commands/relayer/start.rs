@@ -1,12 +1,21 @@
-use crate::{application::APP, prelude::*};
+use crate::config::BridgeSection;
+use crate::{application::APP, output, prelude::*};
 use abscissa_core::{clap::Parser, Command, Runnable};
+use cosmos_gravity::crypto::CosmosSigner;
+use ethereum_gravity::nonce_manager::NonceManager;
+use ethereum_gravity::utils::multiplier_to_bps;
 use ethers::{prelude::*, types::Address as EthAddress};
+use gravity_utils::metrics_server::{metrics_main_loop, HealthChecks};
 use gravity_utils::types::config::RelayerMode;
 use gravity_utils::{
     connection_prep::{check_for_eth, create_rpc_connections, wait_for_cosmos_node_ready},
     ethereum::{downcast_to_u64, format_eth_address},
 };
+use relayer::batch_requesting::{batch_request_main_loop, BatchRequestConfig};
+use relayer::budget::SpendLimits;
 use relayer::fee_manager::FeeManager;
+use relayer::gas_scheduling::GasSchedule;
+use relayer::logic_call_relaying::LogicCallFilter;
 use relayer::main_loop::{relayer_main_loop, LOOP_SPEED as RELAYER_LOOP_SPEED};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -14,8 +23,13 @@ use std::sync::Arc;
 /// Start the relayer
 #[derive(Command, Debug, Parser)]
 pub struct StartCommand {
+    /// Ethereum key used to sign relays, required unless every configured bridge has its own key
     #[clap(short, long)]
-    ethereum_key: String,
+    ethereum_key: Option<String>,
+
+    /// Cosmos key used to request batches, required if batch requests are enabled
+    #[clap(short, long)]
+    cosmos_key: Option<String>,
 
     #[clap(short, long)]
     mode: Option<String>,
@@ -26,103 +40,199 @@ impl Runnable for StartCommand {
         openssl_probe::init_ssl_cert_env_vars();
         let config = APP.config();
 
-        let mode_config: String = config
-            .relayer
-            .mode
-            .parse()
-            .expect("Could not parse mode in relayer config");
-        let mode_str = self.mode.as_deref().unwrap_or(&*mode_config);
-        let mode = RelayerMode::from_str(mode_str)
-            .expect("Incorrect mode, possible value are: AlwaysRelay, Api or File");
-        info!("Relayer using mode {mode:?}");
-
-        let cosmos_prefix = config.cosmos.prefix.clone();
-
-        let ethereum_wallet = config.load_ethers_wallet(self.ethereum_key.clone());
-        let ethereum_address = ethereum_wallet.address();
-
-        let contract_address: EthAddress = config
-            .gravity
-            .contract
-            .parse()
-            .expect("Could not parse gravity contract address");
-
-        let mut payment_address: EthAddress = config
-            .relayer
-            .payment_address
-            .parse()
-            .expect("Could not parse gravity contract address");
-
-        let mut supported_contract: Vec<EthAddress> = Vec::new();
-        for contract in &config.relayer.ethereum_contracts {
-            if let Ok(c) = H160::from_str(contract) {
-                supported_contract.push(c);
-            } else {
-                error!("error parsing contract in config {contract}")
-            }
-        }
-        if supported_contract.is_empty() {
-            info!("no contracts found in config, relayer will relay all contracts");
-        } else {
-            info!("supported contracts by the relayer {supported_contract:?}");
+        let bridges: Vec<_> = config
+            .relayer_bridges()
+            .into_iter()
+            .map(|bridge| {
+                let key = if bridge.ethereum_key.is_empty() {
+                    self.ethereum_key.clone().unwrap_or_else(|| {
+                        panic!(
+                            "An Ethereum key is required for bridge {}, pass --ethereum-key or set one in its config",
+                            bridge.name
+                        )
+                    })
+                } else {
+                    bridge.ethereum_key.clone()
+                };
+                let ethereum_wallet = config.load_ethers_wallet(key);
+                let batch_request_config = config.batch_request_config_for(&bridge);
+                let batch_requests = batch_request_config.map(|batch_request_config| {
+                    let name = self
+                        .cosmos_key
+                        .clone()
+                        .expect("A cosmos key is required when batch requests are enabled");
+                    (config.load_deep_space_key(name), batch_request_config)
+                });
+                (bridge, ethereum_wallet, batch_requests)
+            })
+            .collect();
+
+        let mut names: Vec<&str> = bridges
+            .iter()
+            .map(|(bridge, _, _)| bridge.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        if names.len() != bridges.len() {
+            output::fail("config", "Relayer bridge names must be unique");
         }
 
-        let timeout = RELAYER_LOOP_SPEED;
+        config.relayer.init_relay_history();
 
         abscissa_tokio::run_with_actix(&APP, async {
-            let connections = create_rpc_connections(
-                cosmos_prefix,
-                Some(config.cosmos.grpc.clone()),
-                Some(config.ethereum.rpc.clone()),
-                timeout,
-            )
-            .await;
-
-            let grpc = connections.grpc.clone().unwrap();
-            let contact = connections.contact.clone().unwrap();
-            let provider = connections.eth_provider.clone().unwrap();
-            let chain_id = provider
-                .get_chainid()
-                .await
-                .expect("Could not retrieve chain ID during relayer start");
-            let chain_id =
-                downcast_to_u64(chain_id).expect("Chain ID overflowed when downcasting to u64");
-            let eth_client =
-                SignerMiddleware::new(provider, ethereum_wallet.clone().with_chain_id(chain_id));
-            let eth_client = Arc::new(eth_client);
-
-            // if payment address is zero, then use the ethereum key address used for signing tx
-            if payment_address == EthAddress::zero() {
-                info!("relayer payment address is zero, use signing ethereum address instead");
-                payment_address = eth_client.address()
-            }
-
-            info!("Starting Relayer");
-            info!("Ethereum Address: {}", format_eth_address(ethereum_address));
-
-            // check if the cosmos node is syncing, if so wait for it
-            // we can't move any steps above this because they may fail on an incorrect
-            // historic chain state while syncing occurs
-            wait_for_cosmos_node_ready(&contact).await;
-            check_for_eth(ethereum_address, eth_client.clone()).await;
-
-            let mut fee_manager = FeeManager::new_fee_manager(mode).await.unwrap();
-            relayer_main_loop(
-                eth_client,
-                grpc,
-                contract_address,
-                payment_address,
-                config.ethereum.gas_price_multiplier,
-                &mut fee_manager,
-                config.ethereum.gas_multiplier,
-                config.ethereum.blocks_to_search,
-                supported_contract,
-            )
-            .await;
+            let nonce_manager = NonceManager::default();
+            let relayers = bridges
+                .into_iter()
+                .map(|(bridge, ethereum_wallet, batch_requests)| {
+                    let logic_call_filter = bridge.logic_call_filter();
+                    let spend_limits = config.relayer.spend_limits_for(&bridge);
+                    let gas_schedule = bridge.gas_schedule();
+                    start_bridge(
+                        bridge,
+                        ethereum_wallet,
+                        self.mode.clone(),
+                        logic_call_filter,
+                        config.relayer.watch_pending_relays,
+                        spend_limits,
+                        gas_schedule,
+                        batch_requests,
+                        nonce_manager.clone(),
+                    )
+                });
+            tokio::join!(
+                futures::future::join_all(relayers),
+                metrics_main_loop(&config.metrics.listen_addr, HealthChecks::default())
+            );
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            std::process::exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
+
+/// Connects to one bridge and runs its relayer, along with batch requests if they are given
+#[allow(clippy::too_many_arguments)]
+async fn start_bridge<S: Signer + 'static, CS: CosmosSigner>(
+    bridge: BridgeSection,
+    ethereum_wallet: S,
+    mode_override: Option<String>,
+    logic_call_filter: LogicCallFilter,
+    watch_pending_relays: bool,
+    spend_limits: SpendLimits,
+    gas_schedule: GasSchedule,
+    batch_requests: Option<(CS, BatchRequestConfig)>,
+    nonce_manager: NonceManager,
+) {
+    let mode_str = mode_override.as_deref().unwrap_or(&bridge.mode);
+    let mode = RelayerMode::from_str(mode_str)
+        .expect("Incorrect mode, possible value are: AlwaysRelay, Api or File");
+    info!("Relayer for bridge {} using mode {mode:?}", bridge.name);
+
+    let ethereum_address = ethereum_wallet.address();
+
+    let contract_address: EthAddress = bridge
+        .contract
+        .parse()
+        .expect("Could not parse gravity contract address");
+
+    let mut payment_address: EthAddress = bridge
+        .payment_address
+        .parse()
+        .expect("Could not parse gravity contract address");
+
+    let mut supported_contract: Vec<EthAddress> = Vec::new();
+    for contract in &bridge.ethereum_contracts {
+        if let Ok(c) = H160::from_str(contract) {
+            supported_contract.push(c);
+        } else {
+            error!("error parsing contract in config {contract}")
+        }
+    }
+    if supported_contract.is_empty() {
+        info!(
+            "no contracts found in config, relayer will relay all contracts of bridge {}",
+            bridge.name
+        );
+    } else {
+        info!(
+            "supported contracts by the relayer for bridge {} {supported_contract:?}",
+            bridge.name
+        );
+    }
+
+    let connections = create_rpc_connections(
+        bridge.cosmos_prefix.clone(),
+        Some(bridge.cosmos_grpc.clone()),
+        Some(bridge.ethereum_rpc.clone()),
+        RELAYER_LOOP_SPEED,
+    )
+    .await;
+
+    let grpc = connections.grpc.clone().unwrap();
+    let contact = connections.contact.clone().unwrap();
+    let provider = connections.eth_provider.clone().unwrap();
+    let chain_id = provider
+        .get_chainid()
+        .await
+        .expect("Could not retrieve chain ID during relayer start");
+    let account_nonce = nonce_manager.account_nonce(chain_id, ethereum_address);
+    let chain_id = downcast_to_u64(chain_id).expect("Chain ID overflowed when downcasting to u64");
+    let eth_client = SignerMiddleware::new(provider, ethereum_wallet.with_chain_id(chain_id));
+    let eth_client = Arc::new(eth_client);
+
+    // if payment address is zero, then use the ethereum key address used for signing tx
+    if payment_address == EthAddress::zero() {
+        info!("relayer payment address is zero, use signing ethereum address instead");
+        payment_address = eth_client.address()
+    }
+
+    info!("Starting Relayer for bridge {}", bridge.name);
+    info!("Ethereum Address: {}", format_eth_address(ethereum_address));
+
+    // check if the cosmos node is syncing, if so wait for it
+    // we can't move any steps above this because they may fail on an incorrect
+    // historic chain state while syncing occurs
+    wait_for_cosmos_node_ready(&contact).await;
+    check_for_eth(ethereum_address, eth_client.clone()).await;
+
+    let batch_requests = async {
+        if let Some((cosmos_key, batch_request_config)) = batch_requests {
+            info!("Requesting batches when unbatched fees are profitable");
+            batch_request_main_loop(
+                cosmos_key,
+                contact.clone(),
+                eth_client.clone(),
+                grpc.clone(),
+                mode,
+                batch_request_config,
+            )
+            .await;
+        }
+    };
+
+    let mut fee_manager = FeeManager::new_fee_manager_with_sources(
+        mode,
+        bridge.token_prices_path(),
+        bridge.relayer_api_url(),
+    )
+    .await
+    .unwrap();
+    let relayer = relayer_main_loop(
+        eth_client.clone(),
+        grpc.clone(),
+        contract_address,
+        payment_address,
+        multiplier_to_bps(bridge.gas_price_multiplier.unwrap_or(1.0)),
+        &mut fee_manager,
+        multiplier_to_bps(bridge.gas_multiplier.unwrap_or(1.0)),
+        bridge.blocks_to_search.unwrap_or_default(),
+        supported_contract,
+        logic_call_filter,
+        spend_limits,
+        watch_pending_relays,
+        bridge.name.clone(),
+        account_nonce,
+        gas_schedule,
+    );
+    tokio::join!(relayer, batch_requests);
+}
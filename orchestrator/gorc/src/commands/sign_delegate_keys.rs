@@ -1,7 +1,8 @@
-use crate::{application::APP, prelude::*};
+use crate::{application::APP, output, prelude::*};
 use abscissa_core::{clap::Parser, Application, Command, Runnable};
 use ethers::{prelude::Signer, utils::keccak256};
 use gravity_proto::gravity as proto;
+use serde::Serialize;
 use std::time::Duration;
 
 /// Sign delegate keys command
@@ -10,6 +11,13 @@ pub struct SignDelegateKeysCmd {
     pub args: Vec<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct SignDelegateKeysOutput {
+    validator_address: String,
+    nonce: u64,
+    signature: String,
+}
+
 impl Runnable for SignDelegateKeysCmd {
     fn run(&self) {
         let config = APP.config();
@@ -50,11 +58,15 @@ impl Runnable for SignDelegateKeysCmd {
                 .await
                 .expect("Could not sign message");
 
-            println!("0x{signature}");
+            let result = SignDelegateKeysOutput {
+                validator_address: val.clone(),
+                nonce,
+                signature: format!("0x{signature}"),
+            };
+            output::print(&result, |result| println!("{}", result.signature));
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            std::process::exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
@@ -1,5 +1,6 @@
 //! `cosmos subcommands` subcommand
 
+use crate::output::{self, CoinOutput, TxOutput};
 use crate::{application::APP, prelude::*, utils::*};
 use abscissa_core::{clap::Parser, Command, Runnable};
 use clarity::Uint256;
@@ -10,7 +11,7 @@ use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::DenomToErc20Request;
 use gravity_utils::connection_prep::{check_for_fee_denom, create_rpc_connections};
 use regex::Regex;
-use std::process::exit;
+use serde::Serialize;
 
 /// Create transactions in Cosmos chain
 #[derive(Command, Debug, Parser)]
@@ -34,6 +35,14 @@ pub struct SendToEth {
     help: bool,
 }
 
+#[derive(Debug, Serialize)]
+struct SendToEthOutput {
+    sender: String,
+    erc20: String,
+    balances: Vec<CoinOutput>,
+    send: TxOutput,
+}
+
 fn parse_denom(s: &str) -> (String, String) {
     let re_dec_amt = r#"[[:digit:]]+(?:\.[[:digit:]]+)?|\.[[:digit:]]+"#;
     let re_dnm_string = r#"[a-zA-Z][a-zA-Z0-9/]{2,127}"#;
@@ -68,7 +77,7 @@ impl Runnable for SendToEth {
         // call it
         let cosmos_address = cosmos_key.to_address("//TODO add to config file").unwrap();
 
-        println!("Sending from Cosmos address {cosmos_address}");
+        output::progress(format!("Sending from Cosmos address {cosmos_address}"));
         let config = APP.config();
         let cosmos_prefix = config.cosmos.prefix.clone();
         let cosmso_grpc = config.cosmos.grpc.clone();
@@ -85,20 +94,14 @@ impl Runnable for SendToEth {
                     denom: denom.clone(),
                 })
                 .await;
-            match res {
-                Ok(val) => println!(
-                    "Asset {} has ERC20 representation {}",
-                    denom,
-                    val.into_inner().erc20
+            let erc20 = match res {
+                Ok(val) => val.into_inner().erc20,
+                Err(_e) => output::fail(
+                    "no_erc20_representation",
+                    format!("Asset {denom} has no ERC20 representation, you may need to deploy an ERC20 for it!"),
                 ),
-                Err(_e) => {
-                    println!(
-                        "Asset {} has no ERC20 representation, you may need to deploy an ERC20 for it!",
-                        denom.clone()
-                    );
-                    exit(1);
-                }
-            }
+            };
+            output::progress(format!("Asset {denom} has ERC20 representation {erc20}"));
             let amount = Coin {
                 amount: amount.clone(),
                 denom: denom.clone(),
@@ -121,14 +124,15 @@ impl Runnable for SendToEth {
                     found = Some(coin);
                 }
             }
-            println!("Cosmos balances {balances:?}");
+            output::progress(format!("Cosmos balances {balances:?}"));
 
             if found.is_none() {
-                panic!("You don't have any {denom} tokens!");
+                output::fail(
+                    "insufficient_balance",
+                    format!("You don't have any {denom} tokens!"),
+                );
             }
-            println!(
-                "Locking {amount:?} / {denom} into the batch pool"
-            );
+            output::progress(format!("Locking {amount:?} / {denom} into the batch pool"));
             let res = send_to_eth(
                 cosmos_key,
                 cosmos_granter,
@@ -141,14 +145,20 @@ impl Runnable for SendToEth {
                 1.0
             )
             .await;
-            match res {
-                Ok(tx_id) => println!("Send to Eth txid {}", tx_id.txhash),
-                Err(e) => println!("Failed to send tokens! {e:?}"),
-            }
+            let send = match res {
+                Ok(tx_id) => TxOutput::sent(tx_id.txhash),
+                Err(e) => TxOutput::failed(e),
+            };
+            let result = SendToEthOutput {
+                sender: cosmos_address.to_string(),
+                erc20,
+                balances: balances.iter().map(CoinOutput::from).collect(),
+                send,
+            };
+            output::print(&result, |result| result.send.print_text("Send to Eth txid"));
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
@@ -170,8 +180,7 @@ impl Runnable for Send {
         let _coin_amount = self.free[2].clone();
 
         abscissa_tokio::run_with_actix(&APP, async { unimplemented!() }).unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
@@ -1,5 +1,6 @@
 //! `eth subcommands` subcommand
 
+use crate::output::{self, TxOutput};
 use crate::{application::APP, prelude::*, utils::*};
 use abscissa_core::{clap::Parser, Command, Runnable};
 use deep_space::address::Address as CosmosAddress;
@@ -11,6 +12,7 @@ use gravity_utils::{
     connection_prep::{check_for_eth, create_rpc_connections},
     ethereum::{downcast_to_u64, format_eth_address},
 };
+use serde::Serialize;
 use std::sync::Arc;
 
 /// Create transactions in Eth chain
@@ -33,6 +35,15 @@ pub struct SendToCosmos {
     help: bool,
 }
 
+#[derive(Debug, Serialize)]
+struct SendToCosmosOutput {
+    sender: String,
+    erc20: String,
+    cosmos_destination: String,
+    amount: String,
+    send: TxOutput,
+}
+
 // TODO(bolten): I guess this command is also non-functional?
 // are the commands under tx dead code?
 fn lookup_eth_key(_key: String) -> LocalWallet {
@@ -54,10 +65,10 @@ impl Runnable for SendToCosmos {
         let erc20_amount = self.free[3].clone();
         let ethereum_wallet = lookup_eth_key(from_eth_key);
 
-        println!(
+        output::progress(format!(
             "Sending from Eth address {}",
             format_eth_address(ethereum_wallet.address())
-        );
+        ));
         let config = APP.config();
         let cosmos_prefix = config.cosmos.prefix.clone();
         let cosmso_grpc = config.cosmos.grpc.clone();
@@ -94,17 +105,18 @@ impl Runnable for SendToCosmos {
                     .expect("Failed to get balance, check ERC20 contract address");
 
             if erc20_balance == 0u8.into() {
-                panic!(
-                    "You have zero {erc20_contract} tokens, please double check your sender and erc20 addresses!"
+                output::fail(
+                    "insufficient_balance",
+                    format!("You have zero {erc20_contract} tokens, please double check your sender and erc20 addresses!"),
                 );
             }
-            println!(
+            output::progress(format!(
                 "Sending {} / {} to Cosmos from {} to {}",
                 amount,
                 erc20_contract,
                 eth_client.address(),
                 to_cosmos_addr
-            );
+            ));
             // we send some erc20 tokens to the gravity contract to register a deposit
             let res = send_to_cosmos(
                 erc20_contract,
@@ -115,14 +127,21 @@ impl Runnable for SendToCosmos {
                 eth_client.clone(),
             )
             .await;
-            match res {
-                Ok(tx_id) => println!("Send to Cosmos txid: {tx_id:#066x}"),
-                Err(e) => println!("Failed to send tokens! {e:?}"),
-            }
+            let send = match res {
+                Ok(tx_id) => TxOutput::sent(format!("{tx_id:#066x}")),
+                Err(e) => TxOutput::failed(e),
+            };
+            let result = SendToCosmosOutput {
+                sender: format_eth_address(eth_client.address()),
+                erc20: format_eth_address(erc20_contract),
+                cosmos_destination: to_cosmos_addr.to_string(),
+                amount: amount.to_string(),
+                send,
+            };
+            output::print(&result, |result| result.send.print_text("Send to Cosmos txid:"));
         })
         .unwrap_or_else(|e| {
-            status_err!("executor exited with error: {}", e);
-            std::process::exit(1);
+            output::fail("executor", format!("executor exited with error: {e}"));
         });
     }
 }
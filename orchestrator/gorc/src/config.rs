@@ -1,20 +1,36 @@
+use crate::error::{Error, ErrorKind};
 use crate::{application::APP, prelude::*};
 use aws_sdk_kms::Client;
 use bip32::PrivateKey;
 use cosmos_gravity::crypto::{CosmosSigner, EthPubkey, DEFAULT_HD_PATH};
+use ethereum_gravity::gas_oracle::{GasOracle, GasPriceStrategy};
 use ethers::providers::Middleware;
+use ethers::types::Address as EthAddress;
 use ethers::{
     signers::{LocalWallet as EthWallet, Signer},
-    types::Chain,
+    types::{Chain, U256},
 };
-use gravity_utils::{connection_prep::create_rpc_connections, ethereum::downcast_to_u64};
+use gravity_utils::{
+    connection_prep::create_rpc_connections,
+    ethereum::{downcast_to_u64, hex_str_to_bytes},
+    notifier::{NotificationKind, NotifierConfig, Severity, WebhookConfig, WebhookFormat},
+};
+use orchestrator::bridge_lag::BridgeLagConfig;
+use orchestrator::claim_verifier::ClaimVerifierConfig;
+use orchestrator::participation::ParticipationMonitorConfig;
 use pkcs8::LineEnding;
+use relayer::batch_requesting::BatchRequestConfig;
+use relayer::budget::SpendLimits;
+use relayer::gas_scheduling::GasSchedule;
+use relayer::logic_call_relaying::LogicCallFilter;
+use relayer::main_loop::DEFAULT_BRIDGE_NAME;
 use serde::{Deserialize, Serialize};
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 use signatory::FsKeyStore;
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::path::Path;
-use std::{io, time::Duration};
+use std::path::{Path, PathBuf};
+use std::{fmt, io, time::Duration};
 
 use crate::utils::aws::{AwsSigner, AwsSignerError, WrapperSigner};
 
@@ -158,7 +174,11 @@ pub struct GorcConfig {
     pub ethereum: EthereumSection,
     pub cosmos: CosmosSection,
     pub metrics: MetricsSection,
+    pub participation: ParticipationSection,
+    pub claim_verification: ClaimVerificationSection,
+    pub notifications: NotificationsSection,
     pub relayer: RelayerSection,
+    pub telemetry: TelemetrySection,
 }
 
 impl GorcConfig {
@@ -196,6 +216,139 @@ impl GorcConfig {
             WrapperSigner::LocalCosmos(pk)
         }
     }
+
+    /// Returns the bridges the relayer should relay, with unset bridge settings taken from the top
+    /// level sections. Without any `[[relayer.bridges]]` this is the single bridge those sections describe.
+    pub fn relayer_bridges(&self) -> Vec<BridgeSection> {
+        if self.relayer.bridges.is_empty() {
+            return vec![BridgeSection {
+                name: DEFAULT_BRIDGE_NAME.to_owned(),
+                ethereum_contracts: self.relayer.ethereum_contracts.clone(),
+                ..BridgeSection::default()
+            }
+            .with_defaults(self)];
+        }
+
+        self.relayer
+            .bridges
+            .iter()
+            .map(|bridge| bridge.clone().with_defaults(self))
+            .collect()
+    }
+
+    /// Returns the bridge lag metrics settings if they are enabled
+    pub fn bridge_lag_config(&self) -> Option<BridgeLagConfig> {
+        if !self.metrics.bridge_lag.enabled {
+            return None;
+        }
+
+        Some(BridgeLagConfig {
+            blocks_to_search: self.ethereum.blocks_to_search,
+            lookback_blocks: self.metrics.bridge_lag.lookback_blocks,
+            senders: self.metrics.bridge_lag.senders.clone(),
+        })
+    }
+
+    /// Returns the participation monitor settings if the monitor is enabled
+    pub fn participation_monitor_config(&self) -> Option<ParticipationMonitorConfig> {
+        if !self.participation.enabled {
+            return None;
+        }
+
+        Some(ParticipationMonitorConfig {
+            alert_blocks_to_slash: self.participation.alert_blocks_to_slash,
+            webhook_url: self.participation.webhook_url.clone(),
+        })
+    }
+
+    /// Returns the claim verifier settings if oracle claims should be checked against other validators
+    pub fn claim_verifier_config(&self) -> Option<ClaimVerifierConfig> {
+        if !self.claim_verification.enabled {
+            return None;
+        }
+
+        Some(ClaimVerifierConfig {
+            cosmos_grpc: self.cosmos.grpc.clone(),
+            transactions_to_search: self.claim_verification.transactions_to_search,
+        })
+    }
+
+    /// Returns the notifier settings if any webhooks are configured
+    pub fn notifier_config(&self) -> Option<NotifierConfig> {
+        if self.notifications.webhooks.is_empty() {
+            return None;
+        }
+
+        Some(NotifierConfig {
+            webhooks: self
+                .notifications
+                .webhooks
+                .iter()
+                .map(|webhook| WebhookConfig {
+                    url: webhook.url.clone(),
+                    format: webhook.format,
+                    min_severity: webhook.min_severity,
+                    routing_key: webhook.routing_key.clone(),
+                })
+                .collect(),
+            source: self.notifications.source.clone(),
+            default_rate_limit: Duration::from_secs(self.notifications.rate_limit_seconds),
+            rate_limits: self
+                .notifications
+                .rate_limits
+                .iter()
+                .map(|(kind, seconds)| (*kind, Duration::from_secs(*seconds)))
+                .collect(),
+            low_eth_balance: self.notifications.low_eth_balance_wei.into(),
+        })
+    }
+
+    /// Returns the batch requesting settings if the role is enabled
+    pub fn batch_request_config(&self) -> Option<BatchRequestConfig> {
+        self.batch_requests(
+            &self.relayer.batch_requests,
+            DEFAULT_BRIDGE_NAME.to_owned(),
+            None,
+            None,
+        )
+    }
+
+    /// Returns the batch request settings of one bridge of the relayer if they are enabled
+    pub fn batch_request_config_for(&self, bridge: &BridgeSection) -> Option<BatchRequestConfig> {
+        self.batch_requests(
+            bridge
+                .batch_requests
+                .as_ref()
+                .unwrap_or(&self.relayer.batch_requests),
+            bridge.name.clone(),
+            bridge.token_prices_path(),
+            bridge.relayer_api_url(),
+        )
+    }
+
+    fn batch_requests(
+        &self,
+        batch_requests: &BatchRequestSection,
+        bridge: String,
+        token_prices_path: Option<String>,
+        relayer_api_url: Option<String>,
+    ) -> Option<BatchRequestConfig> {
+        if !batch_requests.enabled {
+            return None;
+        }
+
+        Some(BatchRequestConfig {
+            bridge,
+            cosmos_granter: self.cosmos.granter.clone(),
+            gas_price: self.cosmos.gas_price.as_tuple(),
+            gas_limit: self.cosmos.gas_limit,
+            gas_adjustment: self.cosmos.gas_adjustment,
+            estimated_batch_gas: batch_requests.estimated_batch_gas.into(),
+            min_request_interval: Duration::from_secs(batch_requests.min_request_interval_secs),
+            token_prices_path,
+            relayer_api_url,
+        })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -222,6 +375,7 @@ pub struct EthereumSection {
     pub gas_price_multiplier: f32,
     pub gas_multiplier: f32,
     pub blocks_to_search: u64,
+    pub gas_oracle: GasOracleSection,
 }
 
 impl Default for EthereumSection {
@@ -232,10 +386,107 @@ impl Default for EthereumSection {
             gas_price_multiplier: 1.0f32,
             gas_multiplier: 1.0f32,
             blocks_to_search: 5000,
+            gas_oracle: GasOracleSection::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GasPriceStrategyName {
+    Node,
+    FeeHistory,
+    Fixed,
+    Etherscan,
+}
+
+/// Where gas prices come from. With several strategies the highest price of those that answer is used,
+/// then clamped to the min and max. Etherscan is only queried when listed and given an API key.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasOracleSection {
+    pub strategies: Vec<GasPriceStrategyName>,
+    /// how many recent blocks the fee_history strategy looks at
+    pub fee_history_blocks: u64,
+    /// the percentile of priority fees paid in those blocks, from 0 to 100
+    pub fee_history_percentile: f64,
+    pub fixed_gas_price_gwei: Option<u64>,
+    /// never serialized, so `gorc print-config` doesn't print it
+    #[serde(skip_serializing)]
+    pub etherscan_api_key: String,
+    pub min_gas_price_gwei: Option<u64>,
+    pub max_gas_price_gwei: Option<u64>,
+}
+
+// written by hand so the Etherscan API key never ends up in the logs
+impl fmt::Debug for GasOracleSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GasOracleSection")
+            .field("strategies", &self.strategies)
+            .field("fee_history_blocks", &self.fee_history_blocks)
+            .field("fee_history_percentile", &self.fee_history_percentile)
+            .field("fixed_gas_price_gwei", &self.fixed_gas_price_gwei)
+            .field("etherscan_api_key", &"<redacted>")
+            .field("min_gas_price_gwei", &self.min_gas_price_gwei)
+            .field("max_gas_price_gwei", &self.max_gas_price_gwei)
+            .finish()
+    }
+}
+
+impl Default for GasOracleSection {
+    fn default() -> Self {
+        Self {
+            strategies: vec![GasPriceStrategyName::Node],
+            fee_history_blocks: 20,
+            fee_history_percentile: 50.0,
+            fixed_gas_price_gwei: None,
+            etherscan_api_key: String::new(),
+            min_gas_price_gwei: None,
+            max_gas_price_gwei: None,
         }
     }
 }
 
+impl GasOracleSection {
+    pub fn gas_oracle(&self) -> Result<GasOracle, Error> {
+        let to_wei = |gwei: u64| U256::from(gwei) * U256::exp10(9);
+        let config_error = |message: &str| ErrorKind::Config.context(message.to_string());
+
+        if !(0.0..=100.0).contains(&self.fee_history_percentile) {
+            return Err(config_error("fee_history_percentile must be between 0 and 100").into());
+        }
+        let strategies = self
+            .strategies
+            .iter()
+            .map(|strategy| match strategy {
+                GasPriceStrategyName::Node => Ok(GasPriceStrategy::Node),
+                GasPriceStrategyName::FeeHistory => Ok(GasPriceStrategy::FeeHistory {
+                    blocks: self.fee_history_blocks,
+                    percentile: self.fee_history_percentile,
+                }),
+                GasPriceStrategyName::Fixed => match self.fixed_gas_price_gwei {
+                    Some(gwei) => Ok(GasPriceStrategy::Fixed(to_wei(gwei))),
+                    None => Err(config_error(
+                        "The fixed gas price strategy needs fixed_gas_price_gwei",
+                    )),
+                },
+                GasPriceStrategyName::Etherscan if self.etherscan_api_key.is_empty() => Err(
+                    config_error("The etherscan gas price strategy needs etherscan_api_key"),
+                ),
+                GasPriceStrategyName::Etherscan => Ok(GasPriceStrategy::Etherscan {
+                    api_key: self.etherscan_api_key.clone(),
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(GasOracle {
+            strategies,
+            min: self.min_gas_price_gwei.map(to_wei),
+            max: self.max_gas_price_gwei.map(to_wei),
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct CosmosSection {
@@ -290,12 +541,157 @@ impl GasPrice {
 #[serde(default, deny_unknown_fields)]
 pub struct MetricsSection {
     pub listen_addr: SocketAddr,
+    /// how many loop periods an orchestrator loop may go without ticking before /readyz and /healthz fail
+    pub ready_loop_periods: u32,
+    pub bridge_lag: BridgeLagSection,
 }
 
 impl Default for MetricsSection {
     fn default() -> Self {
         Self {
             listen_addr: "127.0.0.1:3000".parse().unwrap(),
+            ready_loop_periods: orchestrator::health::DEFAULT_READY_LOOP_PERIODS,
+            bridge_lag: BridgeLagSection::default(),
+        }
+    }
+}
+
+/// Metrics comparing the Gravity contract to the gravity module, exported by the orchestrator
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeLagSection {
+    pub enabled: bool,
+    /// Cosmos addresses whose unbatched transactions count as pending, batched ones always do
+    pub senders: Vec<String>,
+    /// how many blocks back to look for the last batch relayed for each token when starting,
+    /// searched in chunks of `ethereum.blocks_to_search`
+    pub lookback_blocks: u64,
+}
+
+impl Default for BridgeLagSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            senders: vec![],
+            lookback_blocks: 50000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// How gorc writes logs and exports traces
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetrySection {
+    pub log_format: LogFormat,
+    /// OTLP gRPC endpoint of an OpenTelemetry collector such as "http://localhost:4317", traces are only
+    /// exported when this is set
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySection {
+    fn default() -> Self {
+        Self {
+            log_format: LogFormat::Text,
+            otlp_endpoint: None,
+            service_name: "gorc".to_owned(),
+        }
+    }
+}
+
+/// Watches for valsets, batches and logic calls the orchestrator hasn't signed and alerts before they
+/// become slashable
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParticipationSection {
+    pub enabled: bool,
+    /// alert once an unsigned object is this many Cosmos blocks or fewer from being slashable
+    pub alert_blocks_to_slash: u64,
+    /// alerts are posted here as JSON, and only logged if it isn't set
+    pub webhook_url: Option<String>,
+}
+
+impl Default for ParticipationSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            alert_blocks_to_slash: 1000,
+            webhook_url: None,
+        }
+    }
+}
+
+/// Compares the Ethereum events the oracle claims with the claims other validators submitted
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaimVerificationSection {
+    pub enabled: bool,
+    /// how many of the latest Cosmos transactions submitting Ethereum events are searched each loop
+    pub transactions_to_search: u64,
+}
+
+impl Default for ClaimVerificationSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            transactions_to_search: 500,
+        }
+    }
+}
+
+/// Posts bridge events such as resyncs, failed relays and slashing risks to webhooks
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsSection {
+    /// names this orchestrator or relayer in every event
+    pub source: String,
+    /// the least time between two events of the same kind
+    pub rate_limit_seconds: u64,
+    /// rate limits for particular kinds of events, such as `relay_succeeded = 3600`
+    pub rate_limits: HashMap<NotificationKind, u64>,
+    /// raise a low_eth_balance event when the Ethereum key holds less than this many wei, 0 disables it
+    pub low_eth_balance_wei: u64,
+    pub webhooks: Vec<WebhookSection>,
+}
+
+impl Default for NotificationsSection {
+    fn default() -> Self {
+        Self {
+            source: "gorc".to_owned(),
+            rate_limit_seconds: 300,
+            rate_limits: HashMap::new(),
+            low_eth_balance_wei: 50_000_000_000_000_000,
+            webhooks: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookSection {
+    pub url: String,
+    /// "json", "slack" or "pager_duty"
+    pub format: WebhookFormat,
+    /// "info", "warning" or "critical", less severe events are not sent
+    pub min_severity: Severity,
+    /// the PagerDuty integration key, only used by the pager_duty format
+    pub routing_key: Option<String>,
+}
+
+impl Default for WebhookSection {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            format: WebhookFormat::Json,
+            min_severity: Severity::Warning,
+            routing_key: None,
         }
     }
 }
@@ -306,6 +702,19 @@ pub struct RelayerSection {
     pub mode: String,
     pub payment_address: String,
     pub ethereum_contracts: Vec<String>,
+    pub logic_call_allowed_contracts: Vec<String>,
+    pub logic_call_denied_contracts: Vec<String>,
+    pub logic_call_allowed_invalidation_ids: Vec<String>,
+    pub logic_call_denied_invalidation_ids: Vec<String>,
+    /// back off from relays other relayers already have pending in the mempool
+    pub watch_pending_relays: bool,
+    pub batch_requests: BatchRequestSection,
+    pub spend_limits: SpendLimitsSection,
+    pub gas_scheduling: GasSchedulingSection,
+    /// SQLite database every relay attempt is recorded in for `gorc relayer report`, empty to disable
+    pub history_path: String,
+    /// relay several bridges from one process, if empty the bridge in the top level sections is relayed
+    pub bridges: Vec<BridgeSection>,
 }
 
 impl Default for RelayerSection {
@@ -314,6 +723,298 @@ impl Default for RelayerSection {
             mode: "AlwaysRelay".to_owned(),
             payment_address: "0x0000000000000000000000000000000000000000".to_owned(),
             ethereum_contracts: vec![],
+            logic_call_allowed_contracts: vec![],
+            logic_call_denied_contracts: vec![],
+            logic_call_allowed_invalidation_ids: vec![],
+            logic_call_denied_invalidation_ids: vec![],
+            watch_pending_relays: false,
+            batch_requests: BatchRequestSection::default(),
+            spend_limits: SpendLimitsSection::default(),
+            gas_scheduling: GasSchedulingSection::default(),
+            history_path: "".to_owned(),
+            bridges: vec![],
+        }
+    }
+}
+
+/// Deferring batches while gas is expensive, disabled unless a target gas price is set
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasSchedulingSection {
+    pub target_gas_price_gwei: Option<u64>,
+    /// batches further than this from their timeout wait for the target gas price
+    pub patience_blocks: u64,
+    /// batches this close to their timeout are submitted at any gas price
+    pub urgent_blocks: u64,
+    /// the most a batch is willing to pay before the urgent window, as a percentage of the target
+    pub max_escalation_percent: u64,
+}
+
+impl Default for GasSchedulingSection {
+    fn default() -> Self {
+        let schedule = GasSchedule::default();
+        Self {
+            target_gas_price_gwei: None,
+            patience_blocks: schedule.patience_blocks,
+            urgent_blocks: schedule.urgent_blocks,
+            max_escalation_percent: schedule.max_escalation_percent,
         }
     }
 }
+
+impl GasSchedulingSection {
+    pub fn gas_schedule(&self) -> GasSchedule {
+        GasSchedule {
+            target_gas_price: self
+                .target_gas_price_gwei
+                .map(|gwei| U256::from(gwei) * U256::exp10(9)),
+            patience_blocks: self.patience_blocks,
+            urgent_blocks: self.urgent_blocks,
+            max_escalation_percent: self.max_escalation_percent,
+        }
+    }
+}
+
+/// One Gravity bridge relayed by a multi-bridge relayer. Empty or unset settings are taken from the
+/// top level sections, except `ethereum_contracts` where empty means relaying every token.
+/// Batch requests sign with the --cosmos-key argument and pay their Cosmos fees with the `[cosmos]`
+/// gas settings, so bridges on Cosmos chains with other fee denoms should leave them disabled.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeSection {
+    /// used to label metrics and logs, must be unique
+    pub name: String,
+    pub contract: String,
+    pub ethereum_rpc: String,
+    pub cosmos_grpc: String,
+    pub cosmos_prefix: String,
+    /// keystore key used to sign Ethereum transactions, defaults to the --ethereum-key argument
+    pub ethereum_key: String,
+    pub payment_address: String,
+    pub mode: String,
+    pub ethereum_contracts: Vec<String>,
+    /// token prices used in File mode, defaults to the TOKEN_PRICES_JSON environment variable
+    pub token_prices_path: String,
+    /// API queried in Api mode, defaults to the RELAYER_API_URL environment variable
+    pub relayer_api_url: String,
+    pub gas_price_multiplier: Option<f32>,
+    pub gas_multiplier: Option<f32>,
+    pub blocks_to_search: Option<u64>,
+    pub logic_call_allowed_contracts: Option<Vec<String>>,
+    pub logic_call_denied_contracts: Option<Vec<String>>,
+    pub logic_call_allowed_invalidation_ids: Option<Vec<String>>,
+    pub logic_call_denied_invalidation_ids: Option<Vec<String>>,
+    pub spend_limits: Option<SpendLimitsSection>,
+    pub gas_scheduling: Option<GasSchedulingSection>,
+    pub batch_requests: Option<BatchRequestSection>,
+}
+
+impl BridgeSection {
+    fn with_defaults(mut self, config: &GorcConfig) -> Self {
+        let inherit = |value: &mut String, default: &String| {
+            if value.is_empty() {
+                *value = default.clone();
+            }
+        };
+        inherit(&mut self.contract, &config.gravity.contract);
+        inherit(&mut self.ethereum_rpc, &config.ethereum.rpc);
+        inherit(&mut self.cosmos_grpc, &config.cosmos.grpc);
+        inherit(&mut self.cosmos_prefix, &config.cosmos.prefix);
+        inherit(&mut self.payment_address, &config.relayer.payment_address);
+        inherit(&mut self.mode, &config.relayer.mode);
+        self.gas_price_multiplier = self
+            .gas_price_multiplier
+            .or(Some(config.ethereum.gas_price_multiplier));
+        self.gas_multiplier = self.gas_multiplier.or(Some(config.ethereum.gas_multiplier));
+        self.blocks_to_search = self
+            .blocks_to_search
+            .or(Some(config.ethereum.blocks_to_search));
+
+        let relayer = &config.relayer;
+        self.logic_call_allowed_contracts
+            .get_or_insert_with(|| relayer.logic_call_allowed_contracts.clone());
+        self.logic_call_denied_contracts
+            .get_or_insert_with(|| relayer.logic_call_denied_contracts.clone());
+        self.logic_call_allowed_invalidation_ids
+            .get_or_insert_with(|| relayer.logic_call_allowed_invalidation_ids.clone());
+        self.logic_call_denied_invalidation_ids
+            .get_or_insert_with(|| relayer.logic_call_denied_invalidation_ids.clone());
+        self.spend_limits
+            .get_or_insert_with(|| relayer.spend_limits.clone());
+        self.gas_scheduling
+            .get_or_insert_with(|| relayer.gas_scheduling.clone());
+        self.batch_requests
+            .get_or_insert_with(|| relayer.batch_requests.clone());
+        self
+    }
+
+    pub fn logic_call_filter(&self) -> LogicCallFilter {
+        let list = |list: &Option<Vec<String>>| list.clone().unwrap_or_default();
+        parse_logic_call_filter(
+            &list(&self.logic_call_allowed_contracts),
+            &list(&self.logic_call_denied_contracts),
+            &list(&self.logic_call_allowed_invalidation_ids),
+            &list(&self.logic_call_denied_invalidation_ids),
+        )
+    }
+
+    pub fn gas_schedule(&self) -> GasSchedule {
+        self.gas_scheduling
+            .clone()
+            .unwrap_or_default()
+            .gas_schedule()
+    }
+
+    pub fn token_prices_path(&self) -> Option<String> {
+        Some(self.token_prices_path.clone()).filter(|path| !path.is_empty())
+    }
+
+    pub fn relayer_api_url(&self) -> Option<String> {
+        Some(self.relayer_api_url.clone()).filter(|url| !url.is_empty())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchRequestSection {
+    pub enabled: bool,
+    pub estimated_batch_gas: u64,
+    pub min_request_interval_secs: u64,
+}
+
+impl Default for BatchRequestSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            estimated_batch_gas: 500000,
+            min_request_interval_secs: 3600,
+        }
+    }
+}
+
+/// Caps on the relayer's Ethereum spending, denominated in gwei. Caps that are not set are not enforced.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpendLimitsSection {
+    pub max_gas_price_gwei: Option<u64>,
+    pub max_tx_cost_gwei: Option<u64>,
+    pub max_hourly_spend_gwei: Option<u64>,
+    pub max_daily_spend_gwei: Option<u64>,
+    /// file the spending history is kept in across restarts, empty to keep it in memory only
+    pub state_path: String,
+}
+
+impl Default for SpendLimitsSection {
+    fn default() -> Self {
+        Self {
+            max_gas_price_gwei: None,
+            max_tx_cost_gwei: None,
+            max_hourly_spend_gwei: None,
+            max_daily_spend_gwei: None,
+            state_path: "relayer_budget.json".to_owned(),
+        }
+    }
+}
+
+impl SpendLimitsSection {
+    pub fn spend_limits(&self) -> SpendLimits {
+        let to_wei = |gwei: Option<u64>| gwei.map(|gwei| U256::from(gwei) * U256::exp10(9));
+
+        SpendLimits {
+            max_gas_price: to_wei(self.max_gas_price_gwei),
+            max_tx_cost: to_wei(self.max_tx_cost_gwei),
+            max_hourly_spend: to_wei(self.max_hourly_spend_gwei),
+            max_daily_spend: to_wei(self.max_daily_spend_gwei),
+            state_path: if self.state_path.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(&self.state_path))
+            },
+        }
+    }
+}
+
+impl RelayerSection {
+    /// The spend limits of one bridge, each bridge of a multi-bridge relayer keeps its own spending history
+    pub fn spend_limits_for(&self, bridge: &BridgeSection) -> SpendLimits {
+        let mut limits = bridge
+            .spend_limits
+            .as_ref()
+            .unwrap_or(&self.spend_limits)
+            .spend_limits();
+        if !self.bridges.is_empty() {
+            limits.state_path = limits.state_path.map(|path| {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let file_name = match path.extension() {
+                    Some(ext) => format!("{}.{}.{}", stem, bridge.name, ext.to_string_lossy()),
+                    None => format!("{}.{}", stem, bridge.name),
+                };
+                path.with_file_name(file_name)
+            });
+        }
+        limits
+    }
+
+    pub fn spend_limits(&self) -> SpendLimits {
+        self.spend_limits.spend_limits()
+    }
+
+    pub fn history_path(&self) -> Option<PathBuf> {
+        Some(PathBuf::from(&self.history_path)).filter(|_| !self.history_path.is_empty())
+    }
+
+    /// Starts recording relay attempts if a history database is configured
+    pub fn init_relay_history(&self) {
+        if let Some(path) = self.history_path() {
+            if let Err(e) = relayer::relay_history::init(&path) {
+                panic!("Could not open relay history {}: {}", path.display(), e);
+            }
+            info!("Recording relay history to {}", path.display());
+        }
+    }
+
+    pub fn gas_schedule(&self) -> GasSchedule {
+        self.gas_scheduling.gas_schedule()
+    }
+
+    pub fn logic_call_filter(&self) -> LogicCallFilter {
+        parse_logic_call_filter(
+            &self.logic_call_allowed_contracts,
+            &self.logic_call_denied_contracts,
+            &self.logic_call_allowed_invalidation_ids,
+            &self.logic_call_denied_invalidation_ids,
+        )
+    }
+}
+
+fn parse_logic_call_filter(
+    allowed_contracts: &[String],
+    denied_contracts: &[String],
+    allowed_invalidation_ids: &[String],
+    denied_invalidation_ids: &[String],
+) -> LogicCallFilter {
+    let parse_contracts = |contracts: &[String]| -> Vec<EthAddress> {
+        contracts
+            .iter()
+            .map(|contract| {
+                contract
+                    .parse()
+                    .expect("Could not parse logic call contract address in config")
+            })
+            .collect()
+    };
+    let parse_invalidation_ids = |ids: &[String]| -> Vec<Vec<u8>> {
+        ids.iter()
+            .map(|id| {
+                hex_str_to_bytes(id).expect("Could not parse logic call invalidation id in config")
+            })
+            .collect()
+    };
+
+    LogicCallFilter {
+        allowed_contracts: parse_contracts(allowed_contracts),
+        denied_contracts: parse_contracts(denied_contracts),
+        allowed_invalidation_ids: parse_invalidation_ids(allowed_invalidation_ids),
+        denied_invalidation_ids: parse_invalidation_ids(denied_invalidation_ids),
+    }
+}
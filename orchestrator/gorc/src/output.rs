@@ -0,0 +1,154 @@
+//! How gorc commands print their results. Commands print text for people by default, with the global
+//! `--output json` flag every command prints exactly one JSON document to stdout instead so scripts don't
+//! have to scrape the text. A failed command prints `{"error": {"kind": ..., "message": ...}}` and exits
+//! with a nonzero code, and logs are written to stderr so they never mix with the document. Exit codes
+//! are the same in both modes, a key that already exists or a send that failed still exits with zero.
+
+use abscissa_core::{clap::ArgEnum, status_err};
+use deep_space::coin::Coin;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::fmt::Display;
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+static FORMAT: OnceCell<OutputFormat> = OnceCell::new();
+
+/// Sets the output format of the process, in JSON mode panics are reported as JSON errors too
+pub fn init(format: OutputFormat) {
+    if FORMAT.set(format).is_ok() && format == OutputFormat::Json {
+        std::panic::set_hook(Box::new(|info| {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            print_error("panic", &message);
+        }));
+    }
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&OutputFormat::Json)
+}
+
+/// Prints the result of a command, as JSON or for people with `text`
+pub fn print<T: Serialize>(result: &T, text: impl FnOnce(&T)) {
+    if is_json() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(result).expect("Could not serialize command output")
+        );
+    } else {
+        text(result);
+    }
+}
+
+/// Prints a progress message for people, JSON output only has the final document
+pub fn progress(message: impl Display) {
+    if !is_json() {
+        println!("{message}");
+    }
+}
+
+/// Reports a failed command and exits, `kind` is a stable snake_case name scripts can match on
+pub fn fail(kind: &str, message: impl Display) -> ! {
+    error(kind, message);
+    std::process::exit(1);
+}
+
+/// Reports a failure without exiting, for the cases gorc has always exited cleanly on
+pub fn error(kind: &str, message: impl Display) {
+    let message = message.to_string();
+    if is_json() {
+        print_error(kind, &message);
+    } else {
+        status_err!("{}", message);
+    }
+}
+
+/// A coin amount, the amount is a decimal string in the denom's base unit
+#[derive(Debug, Clone, Serialize)]
+pub struct CoinOutput {
+    pub denom: String,
+    pub amount: String,
+}
+
+impl From<&Coin> for CoinOutput {
+    fn from(coin: &Coin) -> Self {
+        CoinOutput {
+            denom: coin.denom.clone(),
+            amount: coin.amount.to_string(),
+        }
+    }
+}
+
+/// The outcome of sending one transaction, exactly one of the fields is set
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TxOutput {
+    pub tx_hash: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TxOutput {
+    pub fn sent(tx_hash: impl Display) -> Self {
+        TxOutput {
+            tx_hash: Some(tx_hash.to_string()),
+            error: None,
+        }
+    }
+
+    pub fn failed(error: impl std::fmt::Debug) -> Self {
+        TxOutput {
+            tx_hash: None,
+            error: Some(format!("{error:?}")),
+        }
+    }
+
+    /// Prints the hash after `label`, or the error if the transaction wasn't sent
+    pub fn print_text(&self, label: &str) {
+        match (&self.tx_hash, &self.error) {
+            (Some(tx_hash), _) => println!("{label} {tx_hash}"),
+            (None, error) => println!(
+                "Failed to send tokens! {}",
+                error.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorOutput<'a> {
+    error: ErrorBody<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    kind: &'a str,
+    message: &'a str,
+}
+
+fn print_error(kind: &str, message: &str) {
+    let error = ErrorOutput {
+        error: ErrorBody { kind, message },
+    };
+    println!("{}", serde_json::to_string_pretty(&error).unwrap());
+}
+
+#[test]
+fn test_error_output() {
+    let error = ErrorOutput {
+        error: ErrorBody {
+            kind: "insufficient_balance",
+            message: "Insufficient balance 10 > 5",
+        },
+    };
+    let json: serde_json::Value = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["error"]["kind"], "insufficient_balance");
+    assert_eq!(json["error"]["message"], "Insufficient balance 10 > 5");
+}
@@ -0,0 +1,77 @@
+//! Sets up logging and tracing for gorc. Log records from the `log` macros used by the orchestrator and
+//! relayer crates are forwarded to `tracing`, so they carry the fields of the loop iteration or object
+//! span they are emitted in. Logs are written as text or JSON, and spans can also be exported to an
+//! OpenTelemetry collector over OTLP. Logs go to stderr when commands print JSON, so stdout only has
+//! the command's document.
+
+use crate::config::{LogFormat, TelemetrySection};
+use crate::output;
+use once_cell::sync::OnceCell;
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::{
+    fmt::{self, writer::BoxMakeWriter},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter,
+};
+
+/// The runtime the OTLP exporter runs on, the config is loaded before the command's own runtime starts
+static EXPORTER_RUNTIME: OnceCell<tokio::runtime::Runtime> = OnceCell::new();
+
+/// Installs the global subscriber, `filter` is an `EnvFilter` directive such as "info" or the value of RUST_LOG
+pub fn init(filter: &str, telemetry: &TelemetrySection) {
+    let text =
+        (telemetry.log_format == LogFormat::Text).then(|| fmt::layer().with_writer(log_writer()));
+    let json = (telemetry.log_format == LogFormat::Json).then(|| {
+        fmt::layer()
+            .with_writer(log_writer())
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+    });
+
+    let otlp = telemetry.otlp_endpoint.as_ref().map(|endpoint| {
+        let runtime = EXPORTER_RUNTIME.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("otlp-exporter")
+                .enable_all()
+                .build()
+                .expect("Could not start the OTLP exporter runtime")
+        });
+        let _guard = runtime.enter();
+
+        let tracer =
+            opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", telemetry.service_name.clone()),
+                ])))
+                .install_batch(opentelemetry::runtime::Tokio)
+                .expect("Could not set up the OTLP trace exporter");
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    });
+
+    // also forwards records from the `log` macros into tracing
+    tracing_subscriber::registry()
+        .with(EnvFilter::new(filter))
+        .with(text)
+        .with(json)
+        .with(otlp)
+        .init();
+}
+
+fn log_writer() -> BoxMakeWriter {
+    if output::is_json() {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    }
+}
@@ -2,7 +2,9 @@
 //! It's a common problem to have conflicts between ipv4 and ipv6 localhost and this module is first and foremost supposed to resolve that problem
 //! by trying more than one thing to handle potentially misconfigured inputs.
 
+use crate::eth_transport::{is_http_url, EthTransport};
 use crate::ethereum::format_eth_address;
+use crate::notifier::{check_eth_balance, notify, notify_and_wait, NotificationKind};
 use deep_space::client::ChainStatus;
 use deep_space::Address as CosmosAddress;
 use deep_space::Contact;
@@ -12,7 +14,6 @@ use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_proto::gravity::DelegateKeysByEthereumSignerRequest;
 use gravity_proto::gravity::DelegateKeysByOrchestratorRequest;
-use std::convert::TryFrom;
 use std::process::exit;
 use std::sync::Arc;
 use std::time::Duration;
@@ -21,7 +22,7 @@ use tonic::transport::Channel;
 use url::Url;
 
 pub struct Connections {
-    pub eth_provider: Option<Provider<Http>>,
+    pub eth_provider: Option<Provider<EthTransport>>,
     pub grpc: Option<GravityQueryClient<Channel>>,
     pub contact: Option<Contact>,
 }
@@ -113,16 +114,21 @@ pub async fn create_rpc_connections(
             }
         }
     }
-    if let Some(eth_rpc_url) = eth_rpc_url {
+    if let Some(eth_rpc_url) = eth_rpc_url.as_ref().filter(|url| !is_http_url(url)) {
+        // WebSocket and IPC connections have no fallbacks, they are used as given
+        let transport = EthTransport::connect(eth_rpc_url)
+            .await
+            .unwrap_or_else(|e| panic!("Could not connect to Ethereum rpc {}: {}", eth_rpc_url, e));
+        info!("Connected to Ethereum rpc over {}", transport.name());
+        eth_provider = Some(Provider::new(transport));
+    } else if let Some(eth_rpc_url) = eth_rpc_url {
         let url = Url::parse(&eth_rpc_url)
             .unwrap_or_else(|_| panic!("Invalid Ethereum RPC url {}", eth_rpc_url));
         check_scheme(&url, &eth_rpc_url);
         let eth_url = eth_rpc_url.trim_end_matches('/');
         // TODO(bolten): should probably set a non-default interval, but what is the appropriate
         // value?
-        let base_eth_provider = Provider::<Http>::try_from(eth_url).unwrap_or_else(|_| {
-            panic!("Could not instantiate Ethereum HTTP provider: {}", eth_url)
-        });
+        let base_eth_provider = http_provider(eth_url);
         let try_base = base_eth_provider.get_block_number().await;
         match try_base {
             // it worked, lets go!
@@ -139,20 +145,8 @@ pub async fn create_rpc_connections(
                     let prefix = url.scheme();
                     let ipv6_url = format!("{prefix}://::1:{port}");
                     let ipv4_url = format!("{prefix}://127.0.0.1:{port}");
-                    let ipv6_eth_provider = Provider::<Http>::try_from(ipv6_url.as_str())
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "Could not instantiate Ethereum HTTP provider: {}",
-                                &ipv6_url
-                            )
-                        });
-                    let ipv4_eth_provider = Provider::<Http>::try_from(ipv4_url.as_str())
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "Could not instantiate Ethereum HTTP provider: {}",
-                                &ipv4_url
-                            )
-                        });
+                    let ipv6_eth_provider = http_provider(ipv6_url.as_str());
+                    let ipv4_eth_provider = http_provider(ipv4_url.as_str());
                     let ipv6_test = ipv6_eth_provider.get_block_number().await;
                     let ipv4_test = ipv4_eth_provider.get_block_number().await;
                     warn!("Trying fallback urls {} {}", ipv6_url, ipv4_url);
@@ -175,22 +169,8 @@ pub async fn create_rpc_connections(
                     // transparently upgrade to https if available, we can't transparently downgrade for obvious security reasons
                     let https_on_80_url = format!("https://{body}:80");
                     let https_on_443_url = format!("https://{body}:443");
-                    let https_on_80_eth_provider =
-                        Provider::<Http>::try_from(https_on_80_url.as_str()).unwrap_or_else(|_| {
-                            panic!(
-                                "Could not instantiate Ethereum HTTP provider: {}",
-                                &https_on_80_url
-                            )
-                        });
-                    let https_on_443_eth_provider = Provider::<Http>::try_from(
-                        https_on_443_url.as_str(),
-                    )
-                    .unwrap_or_else(|_| {
-                        panic!(
-                            "Could not instantiate Ethereum HTTP provider: {}",
-                            &https_on_443_url
-                        )
-                    });
+                    let https_on_80_eth_provider = http_provider(https_on_80_url.as_str());
+                    let https_on_443_eth_provider = http_provider(https_on_443_url.as_str());
                     let https_on_80_test = https_on_80_eth_provider.get_block_number().await;
                     let https_on_443_test = https_on_443_eth_provider.get_block_number().await;
                     warn!(
@@ -223,6 +203,14 @@ pub async fn create_rpc_connections(
     }
 }
 
+/// Creates a provider for an HTTP Ethereum rpc url
+fn http_provider(url: &str) -> Provider<EthTransport> {
+    let http = url
+        .parse::<Http>()
+        .unwrap_or_else(|_| panic!("Could not instantiate Ethereum HTTP provider: {}", url));
+    Provider::new(EthTransport::Http(http))
+}
+
 /// Verify that a url has an http or https prefix
 fn check_scheme(input: &Url, original_string: &str) {
     if !(input.scheme() == "http" || input.scheme() == "https") {
@@ -298,7 +286,10 @@ pub async fn check_delegate_addresses(
                     delegate_orchestrator_address, req_delegate_orchestrator_address
                 );
                 error!("In order to resolve this issue you should double check your input value or re-register your delegate keys");
-                exit(1);
+                exit_on_delegate_key_mismatch(
+                    "the delegate Ethereum and Orchestrator addresses are both incorrect",
+                )
+                .await;
             } else if req_delegate_eth_address != delegate_eth_address {
                 error!("Your Delegate Ethereum address is incorrect!");
                 error!(
@@ -306,7 +297,7 @@ pub async fn check_delegate_addresses(
                     delegate_eth_address, req_delegate_eth_address
                 );
                 error!("In order to resolve this issue you should double check how you input your eth private key");
-                exit(1);
+                exit_on_delegate_key_mismatch("the delegate Ethereum address is incorrect").await;
             } else if req_delegate_orchestrator_address != delegate_orchestrator_address {
                 error!("Your Delegate Orchestrator address is incorrect!");
                 error!(
@@ -314,30 +305,44 @@ pub async fn check_delegate_addresses(
                     delegate_eth_address, req_delegate_eth_address
                 );
                 error!("In order to resolve this issue you should double check how you input your Orchestrator address phrase, make sure you didn't use your Validator phrase!");
-                exit(1);
+                exit_on_delegate_key_mismatch("the delegate Orchestrator address is incorrect")
+                    .await;
             }
 
             if e.validator_address != o.validator_address {
                 error!("You are using delegate keys from two different validator addresses!");
                 error!("If you get this error message I would just blow everything away and start again");
-                exit(1);
+                exit_on_delegate_key_mismatch(
+                    "the delegate keys belong to two different validators",
+                )
+                .await;
             }
         }
         (Err(e), Ok(_)) => {
             error!("Your delegate Ethereum address is incorrect, please double check you private key. If you can't locate the correct private key register your delegate keys again and use the new value {:?}", e);
-            exit(1);
+            exit_on_delegate_key_mismatch("the delegate Ethereum address is not registered").await;
         }
         (Ok(_), Err(e)) => {
             error!("Your delegate Cosmos address is incorrect, please double check your phrase. If you can't locate the correct phrase register your delegate keys again and use the new value {:?}", e);
-            exit(1);
+            exit_on_delegate_key_mismatch("the delegate Cosmos address is not registered").await;
         }
         (Err(_), Err(_)) => {
             error!("Delegate keys are not set! Please Register your delegate keys");
-            exit(1);
+            exit_on_delegate_key_mismatch("the delegate keys are not registered").await;
         }
     }
 }
 
+/// Reports that the delegate keys don't match the chain and exits, nothing can work with the wrong keys
+async fn exit_on_delegate_key_mismatch(reason: &str) {
+    notify_and_wait(
+        NotificationKind::DelegateKeyMismatch,
+        format!("Exiting because {}", reason),
+    )
+    .await;
+    exit(1);
+}
+
 /// Checks if a given denom, used for fees is in the provided address
 pub async fn check_for_fee_denom(fee_denom: &str, address: CosmosAddress, contact: &Contact) {
     let mut found = false;
@@ -349,16 +354,21 @@ pub async fn check_for_fee_denom(fee_denom: &str, address: CosmosAddress, contac
     }
     if !found {
         warn!("You have specified that fees should be paid in {} but account {} has no balance of that token!", fee_denom, address);
+        notify(
+            NotificationKind::LowFeeBalance,
+            format!("Account {} has no {} to pay fees with", address, fee_denom),
+        );
     }
 }
 
 /// Checks the user has some Ethereum in their address to pay for things
 pub async fn check_for_eth<S: Signer>(
     address: EthAddress,
-    eth_client: Arc<SignerMiddleware<Provider<Http>, S>>,
+    eth_client: Arc<SignerMiddleware<Provider<EthTransport>, S>>,
 ) {
     let balance = eth_client.get_balance(address, None).await.unwrap();
     if balance == 0u8.into() {
         warn!("You don't have any Ethereum! You will need to send some to {} for this program to work. Dust will do for basic operations, more info about average relaying costs will be presented as the program runs", address);
     }
+    check_eth_balance(address, balance);
 }
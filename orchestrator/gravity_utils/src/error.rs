@@ -1,5 +1,7 @@
 //! for things that don't belong in the cosmos or ethereum libraries but also don't belong
 //! in a function specific library
+use crate::eth_transport::EthTransport;
+use crate::types::GravityContractError;
 use clarity::Error as ClarityError;
 use deep_space::error::AddressError as CosmosAddressError;
 use deep_space::error::CosmosGrpcError;
@@ -57,6 +59,8 @@ pub enum GravityError {
     ParseIntError(ParseIntError),
     FromUtf8Error(FromUtf8Error),
     OverflowError(String),
+    DatabaseError(String),
+    TransactionReverted(Box<RevertedTransaction>),
 }
 
 impl fmt::Display for GravityError {
@@ -117,12 +121,39 @@ impl fmt::Display for GravityError {
                 write!(f, "Failed to parse bytes to UTF-8: {val}")
             }
             GravityError::OverflowError(val) => write!(f, "Overflow error: {val}"),
+            GravityError::DatabaseError(val) => write!(f, "Database error: {val}"),
+            GravityError::TransactionReverted(val) => match &val.contract_error {
+                Some(e) => write!(
+                    f,
+                    "Transaction {:?} reverted: {}",
+                    val.receipt.transaction_hash,
+                    e.message()
+                ),
+                None => write!(f, "Transaction {:?} reverted", val.receipt.transaction_hash),
+            },
         }
     }
 }
 
 impl std::error::Error for GravityError {}
 
+/// A transaction that was mined but reverted, along with the Gravity contract error that caused the
+/// revert if it could be recovered by replaying the transaction
+#[derive(Debug)]
+pub struct RevertedTransaction {
+    pub receipt: TransactionReceipt,
+    pub contract_error: Option<GravityContractError>,
+}
+
+impl RevertedTransaction {
+    /// If the transaction reverted because another relayer got there first
+    pub fn already_relayed(&self) -> bool {
+        self.contract_error
+            .as_ref()
+            .map_or(false, GravityContractError::is_already_relayed)
+    }
+}
+
 impl From<CosmosGrpcError> for GravityError {
     fn from(error: CosmosGrpcError) -> Self {
         GravityError::CosmosGrpcError(error)
@@ -153,8 +184,8 @@ impl From<ClarityError> for GravityError {
     }
 }
 
-impl<S: Signer + 'static> From<SignerMiddlewareError<Provider<Http>, S>> for GravityError {
-    fn from(error: SignerMiddlewareError<Provider<Http>, S>) -> Self {
+impl<S: Signer + 'static> From<SignerMiddlewareError<Provider<EthTransport>, S>> for GravityError {
+    fn from(error: SignerMiddlewareError<Provider<EthTransport>, S>) -> Self {
         GravityError::EthereumRestError(Box::new(error))
     }
 }
@@ -171,10 +202,10 @@ impl From<EthersContractAbiError> for GravityError {
     }
 }
 
-impl<S: Signer + 'static> From<ContractError<SignerMiddleware<Provider<Http>, S>>>
+impl<S: Signer + 'static> From<ContractError<SignerMiddleware<Provider<EthTransport>, S>>>
     for GravityError
 {
-    fn from(error: ContractError<SignerMiddleware<Provider<Http>, S>>) -> Self {
+    fn from(error: ContractError<SignerMiddleware<Provider<EthTransport>, S>>) -> Self {
         GravityError::EthersContractError(Box::new(error))
     }
 }
@@ -238,3 +269,31 @@ impl From<FromUtf8Error> for GravityError {
         GravityError::FromUtf8Error(error)
     }
 }
+
+#[test]
+fn test_reverted_transaction() {
+    use crate::types::decode_gravity_error;
+    use ethers::utils::keccak256;
+
+    // InvalidBatchNonce(5, 6), what a relayer sees when another relayer submitted the batch first
+    let mut data = keccak256("InvalidBatchNonce(uint256,uint256)")[0..4].to_vec();
+    data.extend_from_slice(&H256::from_low_u64_be(5).0);
+    data.extend_from_slice(&H256::from_low_u64_be(6).0);
+    let reverted = RevertedTransaction {
+        receipt: TransactionReceipt {
+            status: Some(0u64.into()),
+            ..Default::default()
+        },
+        contract_error: decode_gravity_error(data),
+    };
+    assert!(reverted.already_relayed());
+    assert!(GravityError::TransactionReverted(Box::new(reverted))
+        .to_string()
+        .ends_with("reverted: Invalid batch nonce, new nonce 5, current nonce 6"));
+
+    let reverted = RevertedTransaction {
+        receipt: TransactionReceipt::default(),
+        contract_error: decode_gravity_error(keccak256("InvalidSignature()")[0..4].to_vec()),
+    };
+    assert!(!reverted.already_relayed());
+}
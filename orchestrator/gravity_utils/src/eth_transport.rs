@@ -0,0 +1,166 @@
+//! The JSON-RPC transports used to talk to Ethereum. HTTP, WebSocket and IPC are wrapped in a single transport
+//! type so that `EthClient` stays one concrete type and everything built on it works over any of the three.
+//! Nodes running on the same host can be reached over IPC, WebSocket and IPC connections also support subscriptions.
+
+use async_trait::async_trait;
+use ethers::providers::{
+    Http, HttpClientError, Ipc, IpcError, JsonRpcClient, JsonRpcError, ProviderError, PubsubClient,
+    Ws, WsClientError,
+};
+use ethers::types::U256;
+use futures::Stream;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::pin::Pin;
+
+#[derive(Debug, Clone)]
+pub enum EthTransport {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+impl EthTransport {
+    /// Connects to an Ethereum node, the transport is picked from the url: http:// and https:// urls
+    /// use HTTP, ws:// and wss:// urls use WebSocket, ipc:// urls and filesystem paths use IPC
+    pub async fn connect(url: &str) -> Result<Self, EthTransportError> {
+        if is_http_url(url) {
+            return url
+                .parse::<Http>()
+                .map(EthTransport::Http)
+                .map_err(|e| EthTransportError::InvalidUrl(format!("{url}: {e}")));
+        }
+
+        let lowercase = url.to_lowercase();
+        if lowercase.starts_with("ws://") || lowercase.starts_with("wss://") {
+            Ok(EthTransport::Ws(
+                Ws::connect(url).await.map_err(EthTransportError::Ws)?,
+            ))
+        } else {
+            let path = url.strip_prefix("ipc://").unwrap_or(url);
+            Ok(EthTransport::Ipc(
+                Ipc::connect(path).await.map_err(EthTransportError::Ipc)?,
+            ))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EthTransport::Http(_) => "http",
+            EthTransport::Ws(_) => "ws",
+            EthTransport::Ipc(_) => "ipc",
+        }
+    }
+}
+
+/// If the url is for the HTTP transport, which supports the fallbacks in `create_rpc_connections`
+pub fn is_http_url(url: &str) -> bool {
+    let lowercase = url.to_lowercase();
+    lowercase.starts_with("http://") || lowercase.starts_with("https://")
+}
+
+#[derive(Debug)]
+pub enum EthTransportError {
+    Http(HttpClientError),
+    Ws(WsClientError),
+    Ipc(IpcError),
+    InvalidUrl(String),
+    Unsupported(&'static str),
+}
+
+impl EthTransportError {
+    /// The JSON-RPC error returned by the node, for reverted calls it carries the revert data
+    pub fn as_json_rpc_error(&self) -> Option<&JsonRpcError> {
+        match self {
+            EthTransportError::Http(HttpClientError::JsonRpcError(e)) => Some(e),
+            EthTransportError::Ws(WsClientError::JsonRpcError(e)) => Some(e),
+            EthTransportError::Ipc(IpcError::JsonRpcError(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EthTransportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EthTransportError::Http(val) => write!(f, "Ethereum HTTP transport error: {val}"),
+            EthTransportError::Ws(val) => write!(f, "Ethereum WebSocket transport error: {val}"),
+            EthTransportError::Ipc(val) => write!(f, "Ethereum IPC transport error: {val}"),
+            EthTransportError::InvalidUrl(val) => write!(f, "Invalid Ethereum RPC url {val}"),
+            EthTransportError::Unsupported(val) => write!(f, "Unsupported by transport: {val}"),
+        }
+    }
+}
+
+impl Error for EthTransportError {}
+
+impl From<EthTransportError> for ProviderError {
+    fn from(error: EthTransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(error))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for EthTransport {
+    type Error = EthTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        match self {
+            EthTransport::Http(client) => client
+                .request(method, params)
+                .await
+                .map_err(EthTransportError::Http),
+            EthTransport::Ws(client) => client
+                .request(method, params)
+                .await
+                .map_err(EthTransportError::Ws),
+            EthTransport::Ipc(client) => client
+                .request(method, params)
+                .await
+                .map_err(EthTransportError::Ipc),
+        }
+    }
+}
+
+impl PubsubClient for EthTransport {
+    type NotificationStream = Pin<Box<dyn Stream<Item = Box<RawValue>> + Send>>;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            EthTransport::Http(_) => Err(EthTransportError::Unsupported(
+                "subscriptions need a WebSocket or IPC connection",
+            )),
+            EthTransport::Ws(client) => Ok(Box::pin(
+                client.subscribe(id).map_err(EthTransportError::Ws)?,
+            )),
+            EthTransport::Ipc(client) => Ok(Box::pin(
+                client.subscribe(id).map_err(EthTransportError::Ipc)?,
+            )),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match self {
+            EthTransport::Http(_) => Err(EthTransportError::Unsupported(
+                "subscriptions need a WebSocket or IPC connection",
+            )),
+            EthTransport::Ws(client) => client.unsubscribe(id).map_err(EthTransportError::Ws),
+            EthTransport::Ipc(client) => client.unsubscribe(id).map_err(EthTransportError::Ipc),
+        }
+    }
+}
+
+#[test]
+fn test_is_http_url() {
+    assert!(is_http_url("http://localhost:8545"));
+    assert!(is_http_url("HTTPS://mainnet.example.com"));
+    assert!(!is_http_url("ws://localhost:8546"));
+    assert!(!is_http_url("/var/run/geth.ipc"));
+    assert!(!is_http_url("ipc:///var/run/geth.ipc"));
+}
@@ -0,0 +1,133 @@
+//! Tracks when the long running loops last ticked so that a health check can tell when one has
+//! stopped. Loops register themselves wherever they live, the orchestrator and relayer loops as
+//! well as the Cosmos send loop, and the metrics server reports on all of them.
+
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// How many loop periods may pass without a tick before a loop is considered stuck
+pub const DEFAULT_READY_LOOP_PERIODS: u32 = 3;
+
+struct LoopState {
+    period: Duration,
+    last_tick: Option<Instant>,
+}
+
+struct LoopRegistry {
+    ready_loop_periods: u32,
+    loops: BTreeMap<String, LoopState>,
+}
+
+impl LoopRegistry {
+    /// Loops that have ticked before but not within the allowed time, loops still starting up
+    /// such as the oracle during its resync are not counted as stuck
+    fn stuck(&self, now: Instant) -> Vec<String> {
+        self.loops
+            .iter()
+            .filter_map(|(name, state)| {
+                let last_tick = state.last_tick?;
+                let allowed = state.period * self.ready_loop_periods;
+                if now.duration_since(last_tick) > allowed {
+                    Some(format!("{} loop has not ticked in {:?}", name, allowed))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn not_started(&self) -> Vec<String> {
+        self.loops
+            .iter()
+            .filter(|(_, state)| state.last_tick.is_none())
+            .map(|(name, _)| format!("{} loop has not ticked yet", name))
+            .collect()
+    }
+
+    fn ages(&self, now: Instant) -> BTreeMap<String, Option<u64>> {
+        self.loops
+            .iter()
+            .map(|(name, state)| {
+                let since = state
+                    .last_tick
+                    .map(|tick| now.duration_since(tick).as_secs());
+                (name.clone(), since)
+            })
+            .collect()
+    }
+}
+
+lazy_static! {
+    static ref LOOPS: RwLock<LoopRegistry> = RwLock::new(LoopRegistry {
+        ready_loop_periods: DEFAULT_READY_LOOP_PERIODS,
+        loops: BTreeMap::new(),
+    });
+}
+
+pub fn set_ready_loop_periods(periods: u32) {
+    LOOPS.write().unwrap().ready_loop_periods = periods.max(1);
+}
+
+/// Starts tracking a loop, it has to tick at least every `ready_loop_periods` times `period`
+pub fn register_loop(name: impl Into<String>, period: Duration) {
+    LOOPS.write().unwrap().loops.insert(
+        name.into(),
+        LoopState {
+            period,
+            last_tick: None,
+        },
+    );
+}
+
+pub fn loop_ticked(name: &str) {
+    if let Some(state) = LOOPS.write().unwrap().loops.get_mut(name) {
+        state.last_tick = Some(Instant::now());
+    }
+}
+
+/// The loops that have stopped ticking
+pub fn stuck_loops() -> Vec<String> {
+    LOOPS.read().unwrap().stuck(Instant::now())
+}
+
+/// The loops that are stuck or have not ticked yet
+pub fn loops_not_ready() -> Vec<String> {
+    let loops = LOOPS.read().unwrap();
+    let mut reasons = loops.stuck(Instant::now());
+    reasons.extend(loops.not_started());
+    reasons
+}
+
+/// Seconds since each loop last ticked, None if it hasn't yet
+pub fn loop_ages() -> BTreeMap<String, Option<u64>> {
+    LOOPS.read().unwrap().ages(Instant::now())
+}
+
+#[test]
+fn test_stuck_loops() {
+    let now = Instant::now();
+    let period = Duration::from_secs(10);
+    let mut registry = LoopRegistry {
+        ready_loop_periods: 3,
+        loops: BTreeMap::new(),
+    };
+    registry.loops.insert(
+        "eth_signer".to_string(),
+        LoopState {
+            period,
+            last_tick: None,
+        },
+    );
+    // still starting up, alive but not ready
+    assert!(registry.stuck(now).is_empty());
+    assert_eq!(registry.not_started().len(), 1);
+    assert_eq!(registry.ages(now)["eth_signer"], None);
+
+    registry.loops.get_mut("eth_signer").unwrap().last_tick = Some(now);
+    assert!(registry.not_started().is_empty());
+    assert!(registry.stuck(now + period * 3).is_empty());
+    assert_eq!(registry.stuck(now + period * 4).len(), 1);
+    assert_eq!(registry.ages(now + period)["eth_signer"], Some(10));
+}
@@ -7,6 +7,10 @@ extern crate log;
 
 pub mod connection_prep;
 pub mod error;
+pub mod eth_transport;
 pub mod ethereum;
+pub mod health;
 pub mod message_signatures;
+pub mod metrics_server;
+pub mod notifier;
 pub mod types;
@@ -26,8 +26,8 @@ pub fn encode_valset_confirm(gravity_id: String, valset: Valset) -> Vec<u8> {
         Token::Uint(valset.nonce.into()),
         Token::Array(eth_addresses),
         Token::Array(powers),
-        Token::Uint(U256::zero()),
-        Token::Address(H160::zero()),
+        Token::Uint(valset.reward_amount),
+        Token::Address(valset.reward_token),
     ])
 }
 
@@ -74,6 +74,7 @@ fn test_valset_signature() {
                 power: 3333,
             },
         ],
+        ..Default::default()
     };
     let checkpoint = encode_valset_confirm("foo".to_string(), valset);
     let checkpoint_hash = keccak256(&checkpoint);
@@ -108,6 +109,7 @@ fn test_valset_signature() {
                 power: 3333,
             },
         ],
+        ..Default::default()
     };
     let checkpoint = encode_valset_confirm("foo".to_string(), valset);
     let checkpoint_hash = keccak256(&checkpoint);
@@ -0,0 +1,79 @@
+//! The HTTP server for the Prometheus metrics, shared by the orchestrator and the standalone relayer.
+//! Every crate registers its metrics in the default registry, so serving it here serves them all.
+
+use crate::health;
+use axum::prelude::*;
+use hyper::{Server, StatusCode};
+use prometheus::{Encoder, TextEncoder};
+use std::net;
+
+/// The health checks served next to the metrics. `/healthz` always fails once a registered loop
+/// stops ticking, the orchestrator also waits on its chains and oracle resync to be ready.
+#[derive(Clone, Copy)]
+pub struct HealthChecks {
+    pub readiness: fn() -> Result<(), Vec<String>>,
+    /// the body of `/status`, which is only served when set
+    pub status: Option<fn() -> serde_json::Value>,
+}
+
+impl Default for HealthChecks {
+    fn default() -> Self {
+        HealthChecks {
+            readiness: loops_ready,
+            status: None,
+        }
+    }
+}
+
+fn loops_ready() -> Result<(), Vec<String>> {
+    let reasons = health::loops_not_ready();
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons)
+    }
+}
+
+fn check_response(check: Result<(), Vec<String>>) -> (StatusCode, String) {
+    match check {
+        Ok(()) => (StatusCode::OK, "OK".to_string()),
+        Err(reasons) => (StatusCode::SERVICE_UNAVAILABLE, reasons.join("\n")),
+    }
+}
+
+pub async fn metrics_main_loop(addr: &net::SocketAddr, health_checks: HealthChecks) {
+    let get_metrics = || async {
+        let mut buffer = Vec::new();
+        let encoder = TextEncoder::new();
+        let metric_families = prometheus::gather();
+        encoder.encode(&metric_families, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+
+    // liveness, fails once a loop stops ticking
+    let get_healthz = || async {
+        let stuck = health::stuck_loops();
+        check_response(if stuck.is_empty() { Ok(()) } else { Err(stuck) })
+    };
+    let get_readyz = move || async move { check_response((health_checks.readiness)()) };
+    let get_status = move || async move {
+        match health_checks.status {
+            Some(status) => (StatusCode::OK, response::Json(status())),
+            None => (
+                StatusCode::NOT_FOUND,
+                response::Json(serde_json::Value::Null),
+            ),
+        }
+    };
+
+    let app = route("/", get(get_metrics))
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .route("/status", get(get_status));
+
+    info!("metrics listening on {}", addr);
+    Server::bind(addr)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
@@ -0,0 +1,415 @@
+//! Posts bridge operational events, such as a resync finishing or a relay failing, as JSON to webhooks so
+//! operators don't have to watch the logs. Each webhook gets the payload in the shape it expects, plain
+//! JSON, a Slack incoming webhook message or a PagerDuty Events API v2 event. Every kind of event is rate
+//! limited on its own, events sent while a kind is limited are counted and reported with the next one.
+//!
+//! The notifier is global so that any loop can raise an event, nothing is sent until `init` is called.
+
+use crate::ethereum::format_eth_address;
+use ethers::types::{Address as EthAddress, U256};
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    ResyncStarted,
+    ResyncFinished,
+    LowEthBalance,
+    LowFeeBalance,
+    RelaySucceeded,
+    RelayFailed,
+    SlashingRisk,
+    DelegateKeyMismatch,
+    ReorgDetected,
+    ClaimDivergence,
+}
+
+impl NotificationKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            NotificationKind::ResyncStarted
+            | NotificationKind::ResyncFinished
+            | NotificationKind::RelaySucceeded => Severity::Info,
+            NotificationKind::LowEthBalance
+            | NotificationKind::LowFeeBalance
+            | NotificationKind::RelayFailed
+            | NotificationKind::ReorgDetected => Severity::Warning,
+            NotificationKind::SlashingRisk
+            | NotificationKind::DelegateKeyMismatch
+            | NotificationKind::ClaimDivergence => Severity::Critical,
+        }
+    }
+}
+
+/// The payload shape a webhook expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    Json,
+    Slack,
+    PagerDuty,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub format: WebhookFormat,
+    /// events less severe than this are not sent to the webhook
+    pub min_severity: Severity,
+    /// the integration key of the PagerDuty service, only used by the pager_duty format
+    pub routing_key: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NotifierConfig {
+    pub webhooks: Vec<WebhookConfig>,
+    /// names this process in every event, such as the validator moniker
+    pub source: String,
+    /// the least time between two events of the same kind
+    pub default_rate_limit: Duration,
+    pub rate_limits: HashMap<NotificationKind, Duration>,
+    /// ETH balances below this many wei raise a LowEthBalance event, zero disables it
+    pub low_eth_balance: U256,
+}
+
+/// One bridge event, this is also the body of json webhooks
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub severity: Severity,
+    pub source: String,
+    pub message: String,
+    /// events of this kind that were not sent because of the rate limit since the last one
+    pub suppressed: u64,
+    /// unix time in seconds
+    pub timestamp: u64,
+}
+
+struct Notifier {
+    config: NotifierConfig,
+    client: reqwest::Client,
+    /// when each kind was last sent and how many have been suppressed since
+    last_sent: HashMap<NotificationKind, (Instant, u64)>,
+    /// addresses already reported below `low_eth_balance`, until they are topped back up
+    low_eth_balances: HashSet<EthAddress>,
+}
+
+lazy_static! {
+    static ref NOTIFIER: RwLock<Option<Notifier>> = RwLock::new(None);
+}
+
+/// Starts sending events to the configured webhooks
+pub fn init(config: NotifierConfig) {
+    *NOTIFIER.write().unwrap() = Some(Notifier {
+        config,
+        client: reqwest::Client::new(),
+        last_sent: HashMap::new(),
+        low_eth_balances: HashSet::new(),
+    });
+}
+
+/// Sends an event in the background if it's not rate limited, does nothing before `init`
+pub fn notify(kind: NotificationKind, message: impl Into<String>) {
+    if let Some(send) = prepare(kind, message.into(), Instant::now()) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(send);
+            }
+            Err(_) => warn!(
+                "Could not send {:?} notification outside of a runtime",
+                kind
+            ),
+        }
+    }
+}
+
+/// Sends an event and waits for the webhooks to respond, for events raised just before exiting
+pub async fn notify_and_wait(kind: NotificationKind, message: impl Into<String>) {
+    if let Some(send) = prepare(kind, message.into(), Instant::now()) {
+        send.await;
+    }
+}
+
+/// Raises a LowEthBalance event when the balance of `address` drops below the configured threshold.
+/// It is raised once per drop, checking again while the balance stays low does nothing.
+pub fn check_eth_balance(address: EthAddress, balance: U256) {
+    let threshold = {
+        let mut notifier = NOTIFIER.write().unwrap();
+        let notifier = match notifier.as_mut() {
+            Some(notifier) => notifier,
+            None => return,
+        };
+        let threshold = notifier.config.low_eth_balance;
+        if !crossed_below(&mut notifier.low_eth_balances, address, balance, threshold) {
+            return;
+        }
+        threshold
+    };
+    notify(
+        NotificationKind::LowEthBalance,
+        format!(
+            "Ethereum address {} holds {} wei, below the {} wei threshold",
+            format_eth_address(address),
+            balance,
+            threshold
+        ),
+    );
+}
+
+/// Whether `address` just dropped below the threshold, `below` holds the addresses already under it
+fn crossed_below(
+    below: &mut HashSet<EthAddress>,
+    address: EthAddress,
+    balance: U256,
+    threshold: U256,
+) -> bool {
+    if balance >= threshold {
+        below.remove(&address);
+        return false;
+    }
+    below.insert(address)
+}
+
+/// Applies the rate limit and builds the future posting the event to every interested webhook
+fn prepare(
+    kind: NotificationKind,
+    message: String,
+    now: Instant,
+) -> Option<impl std::future::Future<Output = ()>> {
+    let mut notifier = NOTIFIER.write().unwrap();
+    let notifier = notifier.as_mut()?;
+
+    let suppressed = notifier.rate_limit(kind, now)?;
+    let notification = Notification {
+        kind,
+        severity: kind.severity(),
+        source: notifier.config.source.clone(),
+        message,
+        suppressed,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+
+    let requests: Vec<(String, String)> = notifier
+        .config
+        .webhooks
+        .iter()
+        .filter(|webhook| notification.severity >= webhook.min_severity)
+        .map(|webhook| (webhook.url.clone(), payload(webhook, &notification)))
+        .collect();
+    let client = notifier.client.clone();
+
+    Some(async move {
+        for (url, body) in requests {
+            let response = client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = response {
+                warn!("Could not send {:?} notification to webhook {:?}", kind, e);
+            }
+        }
+    })
+}
+
+impl Notifier {
+    /// The number of suppressed events to report if this one can be sent, None if it's rate limited
+    fn rate_limit(&mut self, kind: NotificationKind, now: Instant) -> Option<u64> {
+        let limit = self
+            .config
+            .rate_limits
+            .get(&kind)
+            .copied()
+            .unwrap_or(self.config.default_rate_limit);
+        if let Some((last, suppressed)) = self.last_sent.get_mut(&kind) {
+            if now.duration_since(*last) < limit {
+                *suppressed += 1;
+                return None;
+            }
+        }
+        let suppressed = self
+            .last_sent
+            .insert(kind, (now, 0))
+            .map_or(0, |(_, suppressed)| suppressed);
+        Some(suppressed)
+    }
+}
+
+/// Builds the body for a webhook in the format it expects
+pub fn payload(webhook: &WebhookConfig, notification: &Notification) -> String {
+    let summary = if notification.suppressed > 0 {
+        format!(
+            "{} ({} similar events suppressed)",
+            notification.message, notification.suppressed
+        )
+    } else {
+        notification.message.clone()
+    };
+
+    let body = match webhook.format {
+        WebhookFormat::Json => serde_json::to_value(notification).unwrap(),
+        WebhookFormat::Slack => serde_json::json!({
+            "text": format!(
+                "*[{:?}] {:?}* from {}\n{}",
+                notification.severity, notification.kind, notification.source, summary
+            ),
+        }),
+        WebhookFormat::PagerDuty => serde_json::json!({
+            "routing_key": webhook.routing_key.clone().unwrap_or_default(),
+            "event_action": "trigger",
+            "dedup_key": format!("{}/{:?}", notification.source, notification.kind),
+            "payload": {
+                "summary": summary,
+                "source": notification.source,
+                "severity": match notification.severity {
+                    Severity::Info => "info",
+                    Severity::Warning => "warning",
+                    Severity::Critical => "critical",
+                },
+                "custom_details": notification,
+            },
+        }),
+    };
+    body.to_string()
+}
+
+#[test]
+fn test_rate_limit() {
+    let mut notifier = Notifier {
+        config: NotifierConfig {
+            webhooks: vec![],
+            source: "test".to_string(),
+            default_rate_limit: Duration::from_secs(60),
+            rate_limits: vec![(NotificationKind::RelaySucceeded, Duration::from_secs(0))]
+                .into_iter()
+                .collect(),
+            low_eth_balance: U256::zero(),
+        },
+        client: reqwest::Client::new(),
+        last_sent: HashMap::new(),
+        low_eth_balances: HashSet::new(),
+    };
+    let now = Instant::now();
+
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::RelayFailed, now),
+        Some(0)
+    );
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::RelayFailed, now),
+        None
+    );
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::RelayFailed, now),
+        None
+    );
+    // kinds are limited separately
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::ReorgDetected, now),
+        Some(0)
+    );
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::RelayFailed, now + Duration::from_secs(61)),
+        Some(2)
+    );
+
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::RelaySucceeded, now),
+        Some(0)
+    );
+    assert_eq!(
+        notifier.rate_limit(NotificationKind::RelaySucceeded, now),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_crossed_below() {
+    let mut below = HashSet::new();
+    let address = EthAddress::repeat_byte(1);
+    let threshold = U256::from(100u64);
+
+    assert!(!crossed_below(
+        &mut below,
+        address,
+        150u64.into(),
+        threshold
+    ));
+    assert!(crossed_below(&mut below, address, 99u64.into(), threshold));
+    // still low, already reported
+    assert!(!crossed_below(&mut below, address, 10u64.into(), threshold));
+    // topped back up, the next drop is reported again
+    assert!(!crossed_below(
+        &mut below,
+        address,
+        100u64.into(),
+        threshold
+    ));
+    assert!(crossed_below(&mut below, address, 0u64.into(), threshold));
+    // addresses are tracked separately
+    assert!(crossed_below(
+        &mut below,
+        EthAddress::repeat_byte(2),
+        0u64.into(),
+        threshold
+    ));
+
+    // a zero threshold never alerts
+    assert!(!crossed_below(
+        &mut HashSet::new(),
+        address,
+        0u64.into(),
+        U256::zero()
+    ));
+}
+
+#[test]
+fn test_payload() {
+    let notification = Notification {
+        kind: NotificationKind::ReorgDetected,
+        severity: Severity::Warning,
+        source: "validator".to_string(),
+        message: "Block 10 changed".to_string(),
+        suppressed: 1,
+        timestamp: 0,
+    };
+    let webhook = |format| WebhookConfig {
+        url: String::new(),
+        format,
+        min_severity: Severity::Info,
+        routing_key: Some("key".to_string()),
+    };
+
+    let json: serde_json::Value =
+        serde_json::from_str(&payload(&webhook(WebhookFormat::Json), &notification)).unwrap();
+    assert_eq!(json["kind"], "reorg_detected");
+    assert_eq!(json["severity"], "warning");
+
+    let slack: serde_json::Value =
+        serde_json::from_str(&payload(&webhook(WebhookFormat::Slack), &notification)).unwrap();
+    assert!(slack["text"]
+        .as_str()
+        .unwrap()
+        .contains("Block 10 changed (1 similar events suppressed)"));
+
+    let pager_duty: serde_json::Value =
+        serde_json::from_str(&payload(&webhook(WebhookFormat::PagerDuty), &notification)).unwrap();
+    assert_eq!(pager_duty["routing_key"], "key");
+    assert_eq!(pager_duty["payload"]["severity"], "warning");
+}
@@ -90,10 +90,14 @@ pub trait EventNonceFilter: Sized {
 /// out.
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
 pub struct ValsetUpdatedEvent {
+    #[serde(with = "crate::types::serialization::u256")]
     pub valset_nonce: U256,
+    #[serde(with = "crate::types::serialization::u256")]
     pub event_nonce: U256,
+    #[serde(with = "crate::types::serialization::u256")]
     pub reward_amount: U256,
     pub reward_token: EthAddress,
+    #[serde(with = "crate::types::serialization::u256")]
     pub block_height: U256,
     pub members: Vec<ValsetMember>,
 }
@@ -164,8 +168,10 @@ impl EventNonceFilter for ValsetUpdatedEvent {}
 pub struct TransactionBatchExecutedEvent {
     /// the nonce attached to the transaction batch that follows
     /// it throughout it's lifecycle
+    #[serde(with = "crate::types::serialization::u256")]
     pub batch_nonce: U256,
     /// The block height this event occurred at
+    #[serde(with = "crate::types::serialization::u256")]
     pub block_height: U256,
     /// The ERC20 token contract address for the batch executed, since batches are uniform
     /// in token type there is only one
@@ -173,6 +179,7 @@ pub struct TransactionBatchExecutedEvent {
     /// the event nonce representing a unique ordering of events coming out
     /// of the Gravity solidity contract. Ensuring that these events can only be played
     /// back in order
+    #[serde(with = "crate::types::serialization::u256")]
     pub event_nonce: U256,
 }
 
@@ -208,10 +215,13 @@ pub struct SendToCosmosEvent {
     /// The Cosmos destination
     pub destination: CosmosAddress,
     /// The amount of the erc20 token that is being sent
+    #[serde(with = "crate::types::serialization::u256")]
     pub amount: U256,
     /// The transaction's nonce, used to make sure there can be no accidental duplication
+    #[serde(with = "crate::types::serialization::u256")]
     pub event_nonce: U256,
     /// The block height this event occurred at
+    #[serde(with = "crate::types::serialization::u256")]
     pub block_height: U256,
 }
 
@@ -254,7 +264,9 @@ pub struct Erc20DeployedEvent {
     pub symbol: String,
     /// The number of decimals required to represent the smallest unit of this token
     pub decimals: u8,
+    #[serde(with = "crate::types::serialization::u256")]
     pub event_nonce: U256,
+    #[serde(with = "crate::types::serialization::u256")]
     pub block_height: U256,
 }
 
@@ -286,10 +298,15 @@ impl EventNonceFilter for Erc20DeployedEvent {}
 /// contract to send an arbitrary logic call.
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
 pub struct LogicCallExecutedEvent {
+    #[serde(with = "crate::types::serialization::hex_bytes")]
     pub invalidation_id: Vec<u8>,
+    #[serde(with = "crate::types::serialization::u256")]
     pub invalidation_nonce: U256,
+    #[serde(with = "crate::types::serialization::hex_bytes")]
     pub return_data: Vec<u8>,
+    #[serde(with = "crate::types::serialization::u256")]
     pub event_nonce: U256,
+    #[serde(with = "crate::types::serialization::u256")]
     pub block_height: U256,
 }
 
@@ -30,6 +30,7 @@ lazy_static! {
     static ref LOGIC_CALL_TIMED_OUT: [u8; 4] = err_to_selector("LogicCallTimedOut()");
 }
 
+#[derive(Debug, Clone)]
 pub enum GravityContractError {
     InvalidSignature(InvalidSignature),
     InvalidValsetNonce(InvalidValsetNonce),
@@ -47,6 +48,58 @@ pub enum GravityContractError {
     LogicCallTimedOut(LogicCallTimedOut),
 }
 
+impl GravityContractError {
+    /// If the contract is already past the relayed nonce, which happens when another relayer got
+    /// there first
+    pub fn is_already_relayed(&self) -> bool {
+        matches!(
+            self,
+            GravityContractError::InvalidValsetNonce(_)
+                | GravityContractError::InvalidBatchNonce(_)
+                | GravityContractError::InvalidLogicCallNonce(_)
+        )
+    }
+
+    /// The Solidity name of the error, used to label metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityContractError::InvalidSignature(_) => "InvalidSignature",
+            GravityContractError::InvalidValsetNonce(_) => "InvalidValsetNonce",
+            GravityContractError::InvalidBatchNonce(_) => "InvalidBatchNonce",
+            GravityContractError::InvalidLogicCallNonce(_) => "InvalidLogicCallNonce",
+            GravityContractError::InvalidLogicCallTransfers(_) => "InvalidLogicCallTransfers",
+            GravityContractError::InvalidLogicCallFees(_) => "InvalidLogicCallFees",
+            GravityContractError::InvalidSendToCosmos(_) => "InvalidSendToCosmos",
+            GravityContractError::IncorrectCheckpoint(_) => "IncorrectCheckpoint",
+            GravityContractError::MalformedNewValidatorSet(_) => "MalformedNewValidatorSet",
+            GravityContractError::MalformedCurrentValidatorSet(_) => "MalformedCurrentValidatorSet",
+            GravityContractError::MalformedBatch(_) => "MalformedBatch",
+            GravityContractError::InsufficientPower(_) => "InsufficientPower",
+            GravityContractError::BatchTimedOut(_) => "BatchTimedOut",
+            GravityContractError::LogicCallTimedOut(_) => "LogicCallTimedOut",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            GravityContractError::InvalidSignature(e) => e.message(),
+            GravityContractError::InvalidValsetNonce(e) => e.message(),
+            GravityContractError::InvalidBatchNonce(e) => e.message(),
+            GravityContractError::InvalidLogicCallNonce(e) => e.message(),
+            GravityContractError::InvalidLogicCallTransfers(e) => e.message(),
+            GravityContractError::InvalidLogicCallFees(e) => e.message(),
+            GravityContractError::InvalidSendToCosmos(e) => e.message(),
+            GravityContractError::IncorrectCheckpoint(e) => e.message(),
+            GravityContractError::MalformedNewValidatorSet(e) => e.message(),
+            GravityContractError::MalformedCurrentValidatorSet(e) => e.message(),
+            GravityContractError::MalformedBatch(e) => e.message(),
+            GravityContractError::InsufficientPower(e) => e.message(),
+            GravityContractError::BatchTimedOut(e) => e.message(),
+            GravityContractError::LogicCallTimedOut(e) => e.message(),
+        }
+    }
+}
+
 pub fn decode_gravity_error(data: Vec<u8>) -> Option<GravityContractError> {
     if data.len() < 4 {
         return None;
@@ -116,8 +169,8 @@ pub fn decode_gravity_error(data: Vec<u8>) -> Option<GravityContractError> {
     }
 
     if selector == INCORRECT_CHECKPOINT.as_slice() {
-        return Some(GravityContractError::InvalidLogicCallTransfers(
-            InvalidLogicCallTransfers {},
+        return Some(GravityContractError::IncorrectCheckpoint(
+            IncorrectCheckpoint {},
         ));
     }
 
@@ -163,6 +216,7 @@ pub fn decode_gravity_error(data: Vec<u8>) -> Option<GravityContractError> {
     None
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidSignature {}
 
 impl InvalidSignature {
@@ -171,6 +225,7 @@ impl InvalidSignature {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidValsetNonce {
     new_nonce: U256,
     current_nonce: U256,
@@ -185,6 +240,7 @@ impl InvalidValsetNonce {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidBatchNonce {
     new_nonce: U256,
     current_nonce: U256,
@@ -199,6 +255,7 @@ impl InvalidBatchNonce {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidLogicCallNonce {
     new_nonce: U256,
     current_nonce: U256,
@@ -213,6 +270,7 @@ impl InvalidLogicCallNonce {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidLogicCallTransfers {}
 
 impl InvalidLogicCallTransfers {
@@ -221,6 +279,7 @@ impl InvalidLogicCallTransfers {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidLogicCallFees {}
 
 impl InvalidLogicCallFees {
@@ -229,6 +288,7 @@ impl InvalidLogicCallFees {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InvalidSendToCosmos {}
 
 impl InvalidSendToCosmos {
@@ -237,6 +297,7 @@ impl InvalidSendToCosmos {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct IncorrectCheckpoint {}
 
 impl IncorrectCheckpoint {
@@ -245,6 +306,7 @@ impl IncorrectCheckpoint {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct MalformedNewValidatorSet {}
 
 impl MalformedNewValidatorSet {
@@ -253,6 +315,7 @@ impl MalformedNewValidatorSet {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct MalformedCurrentValidatorSet {}
 
 impl MalformedCurrentValidatorSet {
@@ -261,6 +324,7 @@ impl MalformedCurrentValidatorSet {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct MalformedBatch {}
 
 impl MalformedBatch {
@@ -269,6 +333,7 @@ impl MalformedBatch {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct InsufficientPower {
     cumulative_power: U256,
     power_threshold: U256,
@@ -283,6 +348,7 @@ impl InsufficientPower {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct BatchTimedOut {}
 
 impl BatchTimedOut {
@@ -291,6 +357,7 @@ impl BatchTimedOut {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct LogicCallTimedOut {}
 
 impl LogicCallTimedOut {
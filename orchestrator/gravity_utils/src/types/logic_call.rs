@@ -9,8 +9,10 @@ pub struct LogicCall {
     pub transfers: Vec<Erc20Token>,
     pub fees: Vec<Erc20Token>,
     pub logic_contract_address: EthAddress,
+    #[serde(with = "crate::types::serialization::hex_bytes")]
     pub payload: Vec<u8>,
     pub timeout: u64,
+    #[serde(with = "crate::types::serialization::hex_bytes")]
     pub invalidation_id: Vec<u8>,
     pub invalidation_nonce: u64,
 }
@@ -41,6 +43,7 @@ impl LogicCall {
 /// the response we get when querying for a logic call confirmation
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LogicCallConfirmResponse {
+    #[serde(with = "crate::types::serialization::hex_bytes")]
     pub invalidation_id: Vec<u8>,
     pub invalidation_nonce: u64,
     pub ethereum_signer: EthAddress,
@@ -3,6 +3,7 @@ pub mod config;
 mod ethereum_events;
 mod gravity_contract_errors;
 mod logic_call;
+pub mod serialization;
 mod signatures;
 mod valsets;
 
@@ -20,6 +21,7 @@ pub use valsets::*;
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
 pub struct Erc20Token {
+    #[serde(with = "crate::types::serialization::u256")]
     pub amount: U256,
     #[serde(rename = "contract")]
     pub token_contract_address: EthAddress,
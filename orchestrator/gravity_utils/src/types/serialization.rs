@@ -0,0 +1,113 @@
+//! Stable serde representations for the bridge types, used through `#[serde(with = ...)]`. ethers writes
+//! U256 values as 0x prefixed hex and byte vectors serialize as arrays of numbers, neither of which is
+//! convenient for tooling reading gorc's JSON output. Amounts and nonces are written as decimal strings
+//! and bytes as 0x prefixed hex. Reading still accepts hex strings and plain numbers for U256 so state
+//! written with the old representation can be loaded.
+
+use crate::ethereum::{bytes_to_hex_str, hex_str_to_bytes};
+use ethers::types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// U256 as a decimal string
+pub mod u256 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        deserializer.deserialize_any(U256Visitor)
+    }
+
+    struct U256Visitor;
+
+    impl<'de> de::Visitor<'de> for U256Visitor {
+        type Value = U256;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a decimal or 0x prefixed hex string, or an unsigned integer")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<U256, E> {
+            Ok(value.into())
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<U256, E> {
+            match value.strip_prefix("0x") {
+                Some(hex) => U256::from_str_radix(hex, 16).map_err(E::custom),
+                None => U256::from_dec_str(value).map_err(E::custom),
+            }
+        }
+    }
+}
+
+/// Optional U256 as a decimal string or null
+pub mod option_u256 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "super::u256")] U256);
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<U256>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.map(Wrapper).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<U256>, D::Error> {
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(value)| value))
+    }
+}
+
+/// Bytes as a 0x prefixed hex string
+pub mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", bytes_to_hex_str(value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        hex_str_to_bytes(&value).map_err(de::Error::custom)
+    }
+}
+
+#[test]
+fn test_u256_representation() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Amounts {
+        #[serde(with = "u256")]
+        amount: U256,
+        #[serde(with = "option_u256")]
+        fee: Option<U256>,
+        #[serde(with = "hex_bytes")]
+        payload: Vec<u8>,
+    }
+
+    let amounts = Amounts {
+        amount: U256::from(1_000_000_000_000_000_000u64) * 1000u64,
+        fee: Some(255u64.into()),
+        payload: vec![0xde, 0xad],
+    };
+    let json = serde_json::to_string(&amounts).unwrap();
+    assert_eq!(
+        json,
+        r#"{"amount":"1000000000000000000000","fee":"255","payload":"0xdead"}"#
+    );
+    assert_eq!(serde_json::from_str::<Amounts>(&json).unwrap(), amounts);
+
+    // the hex representation ethers writes and plain numbers are still read
+    let old: Amounts =
+        serde_json::from_str(r#"{"amount":"0x3635c9adc5dea00000","fee":255,"payload":"dead"}"#)
+            .unwrap();
+    assert_eq!(old, amounts);
+    let none: Amounts =
+        serde_json::from_str(r#"{"amount":"1","fee":null,"payload":"0x"}"#).unwrap();
+    assert_eq!(none.fee, None);
+}
@@ -87,6 +87,12 @@ impl Confirm for ValsetConfirmResponse {
 pub struct Valset {
     pub nonce: u64,
     pub members: Vec<ValsetMember>,
+    /// the reward paid by the Gravity contract to whoever relays this validator set
+    #[serde(default, with = "crate::types::serialization::u256")]
+    pub reward_amount: U256,
+    /// the token the reward is paid in, the zero address if there is no reward
+    #[serde(default)]
+    pub reward_token: EthAddress,
 }
 
 impl Valset {
@@ -281,41 +287,27 @@ impl Valset {
 
 impl From<gravity_proto::gravity::SignerSetTxResponse> for Valset {
     fn from(input: gravity_proto::gravity::SignerSetTxResponse) -> Self {
-        Valset {
-            nonce: input.signer_set.clone().unwrap().nonce,
-            members: input
-                .signer_set
-                .unwrap()
-                .signers
-                .iter()
-                .map(|i| i.into())
-                .collect(),
-        }
+        input.signer_set.unwrap().into()
     }
 }
 
+// The Cosmos module does not attach a relaying reward to its SignerSetTx, validators sign the
+// checkpoint with a zero reward so that is what we must submit to the contract. Only the valset
+// currently in the contract, read from its ValsetUpdated event, can carry a reward.
 impl From<gravity_proto::gravity::SignerSetTx> for Valset {
     fn from(input: gravity_proto::gravity::SignerSetTx) -> Self {
         Valset {
             nonce: input.nonce,
             members: input.signers.iter().map(|i| i.into()).collect(),
+            reward_amount: U256::zero(),
+            reward_token: EthAddress::zero(),
         }
     }
 }
 
 impl From<&gravity_proto::gravity::SignerSetTxResponse> for Valset {
     fn from(input: &gravity_proto::gravity::SignerSetTxResponse) -> Self {
-        Valset {
-            nonce: input.signer_set.clone().unwrap().nonce,
-            members: input
-                .signer_set
-                .clone()
-                .unwrap()
-                .signers
-                .iter()
-                .map(|i| i.into())
-                .collect(),
-        }
+        input.signer_set.clone().unwrap().into()
     }
 }
 
@@ -0,0 +1,280 @@
+//! Compares the state of the Gravity contract on Ethereum with the state of the gravity module on Cosmos
+//! so that one dashboard can show if the bridge is keeping up in both directions. Nonce gaps going from
+//! Ethereum to Cosmos mean the oracle is behind, gaps going from Cosmos to Ethereum mean relayers are.
+
+use crate::health;
+use crate::metrics;
+use cosmos_gravity::query::{
+    get_last_event_nonce, get_last_observed_ethereum_height, get_latest_batch, get_latest_valset,
+    get_unbatched_send_to_ethereums,
+};
+use deep_space::address::Address as CosmosAddress;
+use ethereum_gravity::types::EthClient;
+use ethereum_gravity::utils::{get_gravity_nonces, NonceRequest};
+use ethers::prelude::*;
+use ethers::types::Address as EthAddress;
+use gravity_abi::gravity::TransactionBatchExecutedEventFilter;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_proto::gravity::BatchTxsRequest;
+use gravity_utils::error::GravityError;
+use gravity_utils::types::{FromLogs, TransactionBatchExecutedEvent};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep as delay_for;
+use tonic::transport::Channel;
+
+pub const BRIDGE_LAG_LOOP_SPEED: Duration = Duration::from_secs(60);
+
+/// Settings for the bridge lag metrics
+#[derive(Debug, Clone)]
+pub struct BridgeLagConfig {
+    /// the most blocks searched for events in one request
+    pub blocks_to_search: u64,
+    /// how far back to look for the last batch relayed for each token when starting
+    pub lookback_blocks: u64,
+    /// Cosmos addresses whose unbatched transactions are tracked, the chain only allows
+    /// querying the unbatched pool per sender so without any only batched ones are seen
+    pub senders: Vec<String>,
+}
+
+/// What the collector remembers between loops
+#[derive(Debug, Default)]
+struct BridgeLagState {
+    /// every token a batch has been seen for
+    tokens: HashSet<EthAddress>,
+    /// the batch nonce in the Gravity contract for each token on the last loop
+    batch_nonces: HashMap<EthAddress, u64>,
+    /// unix time each token was last relayed
+    last_relays: HashMap<EthAddress, u64>,
+    /// unix time each pending SendToEthereum was first seen, the chain doesn't record when it was sent
+    pending_first_seen: HashMap<u64, u64>,
+    last_relays_searched: bool,
+}
+
+/// Periodically exports the gaps between the Ethereum and Cosmos sides of the bridge
+pub async fn bridge_lag_main_loop<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+    grpc_client: GravityQueryClient<Channel>,
+    gravity_contract_address: EthAddress,
+    our_cosmos_address: CosmosAddress,
+    config: BridgeLagConfig,
+) {
+    let mut grpc_client = grpc_client;
+    let mut state = BridgeLagState::default();
+
+    health::register_loop("bridge_lag", BRIDGE_LAG_LOOP_SPEED);
+    loop {
+        tokio::join!(
+            async {
+                if let Err(e) = collect_bridge_lag(
+                    eth_client.clone(),
+                    &mut grpc_client,
+                    gravity_contract_address,
+                    our_cosmos_address,
+                    &config,
+                    &mut state,
+                )
+                .await
+                {
+                    warn!("Could not collect bridge lag metrics {:?}", e);
+                }
+            },
+            delay_for(BRIDGE_LAG_LOOP_SPEED)
+        );
+        health::loop_ticked("bridge_lag");
+    }
+}
+
+async fn collect_bridge_lag<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity_contract_address: EthAddress,
+    our_cosmos_address: CosmosAddress,
+    config: &BridgeLagConfig,
+    state: &mut BridgeLagState,
+) -> Result<(), GravityError> {
+    let now = unix_time();
+
+    // batches waiting to be relayed, and so the SendToEthereums in them
+    let batches = grpc_client
+        .batch_txs(BatchTxsRequest { pagination: None })
+        .await?
+        .into_inner()
+        .batches;
+    let mut pending: HashSet<u64> = HashSet::new();
+    for batch in &batches {
+        if let Ok(token) = batch.token_contract.parse() {
+            state.tokens.insert(token);
+        }
+        pending.extend(batch.transactions.iter().map(|tx| tx.id));
+    }
+    for sender in &config.senders {
+        let unbatched = get_unbatched_send_to_ethereums(grpc_client, sender.clone()).await?;
+        pending.extend(unbatched.iter().map(|tx| tx.id));
+    }
+    state
+        .pending_first_seen
+        .retain(|id, _| pending.contains(id));
+    for id in pending {
+        state.pending_first_seen.entry(id).or_insert(now);
+    }
+    let oldest_pending = state
+        .pending_first_seen
+        .values()
+        .min()
+        .map_or(0, |first_seen| now.saturating_sub(*first_seen));
+    metrics::set_bridge_oldest_pending_send_to_ethereum_seconds(oldest_pending);
+
+    let latest_eth_block = eth_client.get_block_number().await?.as_u64();
+    let last_observed = get_last_observed_ethereum_height(grpc_client).await?;
+    metrics::set_bridge_ethereum_height_lag(signed_gap(
+        latest_eth_block,
+        last_observed.ethereum_height,
+    ));
+
+    let tokens: Vec<EthAddress> = state.tokens.iter().copied().collect();
+    let nonces = get_gravity_nonces(
+        gravity_contract_address,
+        &NonceRequest {
+            valset_nonce: true,
+            event_nonce: true,
+            batch_tokens: tokens.clone(),
+            ..NonceRequest::default()
+        },
+        eth_client.clone(),
+    )
+    .await?;
+
+    // Ethereum -> Cosmos, how far behind the events on Ethereum our oracle is
+    let cosmos_event_nonce = get_last_event_nonce(grpc_client, our_cosmos_address).await?;
+    if let Some(event_nonce) = nonces.event_nonce {
+        metrics::set_bridge_event_nonce_gap(signed_gap(event_nonce, cosmos_event_nonce));
+    }
+
+    // Cosmos -> Ethereum, how far behind the valsets and batches on Cosmos the contract is
+    if let (Some(valset), Some(valset_nonce)) =
+        (get_latest_valset(grpc_client).await?, nonces.valset_nonce)
+    {
+        metrics::set_bridge_valset_nonce_gap(signed_gap(valset.nonce, valset_nonce));
+    }
+
+    if !state.last_relays_searched {
+        let last_relays = find_last_relays(
+            eth_client.clone(),
+            gravity_contract_address,
+            latest_eth_block,
+            config.blocks_to_search,
+            config.lookback_blocks,
+            &state.tokens,
+        )
+        .await;
+        match last_relays {
+            Ok(last_relays) => {
+                state.last_relays_searched = true;
+                for (token, relayed_at) in last_relays {
+                    state.tokens.insert(token);
+                    state.last_relays.entry(token).or_insert(relayed_at);
+                }
+            }
+            Err(e) => warn!("Could not search for the last batch relays {:?}", e),
+        }
+    }
+
+    for token in tokens {
+        let ethereum_nonce = match nonces.batch_nonces.get(&token) {
+            Some(nonce) => *nonce,
+            None => continue,
+        };
+        if let Some(previous) = state.batch_nonces.insert(token, ethereum_nonce) {
+            if ethereum_nonce > previous {
+                state.last_relays.insert(token, now);
+            }
+        }
+        if let Some(relayed_at) = state.last_relays.get(&token) {
+            metrics::set_bridge_seconds_since_last_relay(token, now.saturating_sub(*relayed_at));
+        }
+
+        match get_latest_batch(grpc_client, token).await {
+            Ok(batch) => {
+                metrics::set_bridge_batch_nonce_gap(token, signed_gap(batch.nonce, ethereum_nonce))
+            }
+            Err(e) => debug!("No last batch for {} {:?}", token, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds when each token last had a batch relayed, from the batch events in the last `lookback_blocks`
+/// blocks. The search moves backwards `blocks_to_search` blocks at a time and stops early once every
+/// one of the `known_tokens` has been found.
+async fn find_last_relays<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+    gravity_contract_address: EthAddress,
+    latest_block: u64,
+    blocks_to_search: u64,
+    lookback_blocks: u64,
+    known_tokens: &HashSet<EthAddress>,
+) -> Result<HashMap<EthAddress, u64>, GravityError> {
+    let mut filter = Filter::new()
+        .address(ValueOrArray::Value(gravity_contract_address))
+        .event(&TransactionBatchExecutedEventFilter::abi_signature());
+    let oldest_block = latest_block.saturating_sub(lookback_blocks);
+
+    let mut last_blocks: HashMap<EthAddress, U256> = HashMap::new();
+    let mut end_block = latest_block;
+    loop {
+        let start_block = end_block.saturating_sub(blocks_to_search).max(oldest_block);
+        // select uses an inclusive version of the range
+        filter = filter.select(start_block..end_block);
+        let events =
+            TransactionBatchExecutedEvent::from_logs(&eth_client.get_logs(&filter).await?)?;
+        for event in events {
+            let block = last_blocks.entry(event.erc20).or_default();
+            *block = (*block).max(event.block_height);
+        }
+
+        let all_found = !known_tokens.is_empty()
+            && known_tokens
+                .iter()
+                .all(|token| last_blocks.contains_key(token));
+        if all_found || start_block <= oldest_block {
+            break;
+        }
+        end_block = start_block - 1;
+    }
+
+    let mut last_relays = HashMap::new();
+    for (token, block) in last_blocks {
+        if let Some(block) = eth_client.get_block(block.as_u64()).await? {
+            last_relays.insert(token, block.timestamp.as_u64());
+        }
+    }
+    Ok(last_relays)
+}
+
+/// How far `ahead` is in front of `behind`, negative if it's actually behind
+fn signed_gap(ahead: u64, behind: u64) -> i64 {
+    if ahead >= behind {
+        (ahead - behind).try_into().unwrap_or(i64::MAX)
+    } else {
+        let gap: i64 = (behind - ahead).try_into().unwrap_or(i64::MAX);
+        -gap
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[test]
+fn test_signed_gap() {
+    assert_eq!(signed_gap(10, 7), 3);
+    assert_eq!(signed_gap(7, 10), -3);
+    assert_eq!(signed_gap(5, 5), 0);
+    assert_eq!(signed_gap(u64::MAX, 0), i64::MAX);
+}
@@ -0,0 +1,437 @@
+//! Checks that the Ethereum events this oracle claimed match what the rest of the validator set claimed.
+//! `check_for_events` only confirms that our last event nonce advanced, a claim that differs from the
+//! one the other validators submitted for the same nonce means either our Ethereum node is returning bad
+//! data or a majority of the validators are, both of which an operator needs to look at right away.
+//! The chain does not expose its event vote records, so the claims are read back from the transactions
+//! that submitted them and the majority is counted per validator rather than by voting power.
+
+use crate::health;
+use crate::metrics;
+use cosmos_gravity::claims::EthereumEventClaim;
+use cosmos_gravity::query::{
+    get_denom_erc20, get_erc20_denom, get_submitted_claims, SubmittedClaim,
+};
+use deep_space::address::Address as CosmosAddress;
+use ethers::types::Address as EthAddress;
+use gravity_proto::cosmos_sdk_proto::cosmos::tx::v1beta1::service_client::ServiceClient as TxServiceClient;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::error::GravityError;
+use gravity_utils::notifier::{notify, NotificationKind};
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::time::sleep as delay_for;
+use tonic::transport::Channel;
+
+pub const CLAIM_VERIFIER_LOOP_SPEED: Duration = Duration::from_secs(60);
+
+/// How many of our most recent claims are kept for verification
+const MAX_RECORDED_CLAIMS: usize = 1000;
+
+lazy_static! {
+    /// The claims this oracle built from its own Ethereum node, by event nonce
+    static ref OUR_CLAIMS: RwLock<BTreeMap<u64, EthereumEventClaim>> = RwLock::new(BTreeMap::new());
+    /// The Cosmos denom of every ERC20 deploy this oracle claimed, by lowercase token contract. Kept
+    /// apart from the claims as deposits keep checking them long after the deploy
+    static ref OUR_ERC20_DEPLOYS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+/// Remembers the claims the oracle is about to submit so they can be checked against other validators
+pub fn record_claims(claims: &[EthereumEventClaim]) {
+    let mut ours = OUR_CLAIMS.write().unwrap();
+    for claim in claims {
+        ours.insert(claim.event_nonce(), claim.clone());
+        if let EthereumEventClaim::Erc20Deployed(event) = claim {
+            OUR_ERC20_DEPLOYS.write().unwrap().insert(
+                event.token_contract.to_ascii_lowercase(),
+                event.cosmos_denom.clone(),
+            );
+        }
+    }
+    while ours.len() > MAX_RECORDED_CLAIMS {
+        let oldest = *ours.keys().next().unwrap();
+        ours.remove(&oldest);
+    }
+}
+
+/// Settings for the claim verifier
+#[derive(Debug, Clone)]
+pub struct ClaimVerifierConfig {
+    /// the Cosmos gRPC endpoint, transactions are searched through its tx service
+    pub cosmos_grpc: String,
+    /// how many of the latest transactions submitting Ethereum events are searched each loop
+    pub transactions_to_search: u64,
+}
+
+/// How our claim for an event nonce compares with the claims other validators submitted for it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClaimComparison {
+    /// no other validator's claim for the nonce was found yet
+    Unconfirmed,
+    /// every other validator claimed the same event
+    Agreed,
+    /// most validators agree with us, these ones claimed something else
+    OthersDiverged(Vec<String>),
+    /// at least as many validators claimed a different event than claimed ours
+    Diverged {
+        majority: EthereumEventClaim,
+        signers: Vec<String>,
+    },
+}
+
+/// Compares our claim with the other validators' claims for the same event nonce
+pub fn compare_claims(ours: &EthereumEventClaim, others: &[&SubmittedClaim]) -> ClaimComparison {
+    if others.is_empty() {
+        return ClaimComparison::Unconfirmed;
+    }
+
+    // we count as one vote for our own claim
+    let agreeing = 1 + others.iter().filter(|other| other.claim == *ours).count();
+    let mut differing: Vec<(&EthereumEventClaim, Vec<String>)> = Vec::new();
+    for other in others.iter().filter(|other| other.claim != *ours) {
+        match differing
+            .iter_mut()
+            .find(|(claim, _)| **claim == other.claim)
+        {
+            Some((_, signers)) => signers.push(other.signer.clone()),
+            None => differing.push((&other.claim, vec![other.signer.clone()])),
+        }
+    }
+
+    match differing.iter().max_by_key(|(_, signers)| signers.len()) {
+        None => ClaimComparison::Agreed,
+        Some((claim, signers)) if signers.len() >= agreeing => ClaimComparison::Diverged {
+            majority: (*claim).clone(),
+            signers: signers.clone(),
+        },
+        Some(_) => ClaimComparison::OthersDiverged(
+            differing
+                .into_iter()
+                .flat_map(|(_, signers)| signers)
+                .collect(),
+        ),
+    }
+}
+
+/// What the verifier remembers between loops
+#[derive(Debug, Default)]
+struct VerifierState {
+    /// event nonces that have already been flagged, so each is only alerted on once
+    flagged: HashSet<u64>,
+    /// tokens and denoms whose ERC20 mapping on the chain matched our claims
+    verified_mappings: HashSet<String>,
+}
+
+/// Periodically compares our recent claims with the claims the rest of the validator set submitted
+pub async fn claim_verifier_main_loop(
+    grpc_client: GravityQueryClient<Channel>,
+    our_cosmos_address: CosmosAddress,
+    config: ClaimVerifierConfig,
+) {
+    let mut grpc_client = grpc_client;
+    let mut tx_client = None;
+    let mut state = VerifierState::default();
+
+    health::register_loop("claim_verifier", CLAIM_VERIFIER_LOOP_SPEED);
+    loop {
+        tokio::join!(
+            async {
+                if tx_client.is_none() {
+                    match TxServiceClient::connect(config.cosmos_grpc.clone()).await {
+                        Ok(client) => tx_client = Some(client),
+                        Err(e) => {
+                            warn!("Could not connect to the Cosmos tx service {:?}", e);
+                            return;
+                        }
+                    }
+                }
+                if let Err(e) = verify_claims(
+                    tx_client.as_mut().unwrap(),
+                    &mut grpc_client,
+                    our_cosmos_address,
+                    &config,
+                    &mut state,
+                )
+                .await
+                {
+                    warn!("Could not verify oracle claims {:?}", e);
+                }
+            },
+            delay_for(CLAIM_VERIFIER_LOOP_SPEED)
+        );
+        health::loop_ticked("claim_verifier");
+    }
+}
+
+async fn verify_claims(
+    tx_client: &mut TxServiceClient<Channel>,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    our_cosmos_address: CosmosAddress,
+    config: &ClaimVerifierConfig,
+    state: &mut VerifierState,
+) -> Result<(), GravityError> {
+    let ours: Vec<EthereumEventClaim> = OUR_CLAIMS.read().unwrap().values().cloned().collect();
+    if ours.is_empty() {
+        return Ok(());
+    }
+
+    let submitted = get_submitted_claims(tx_client, config.transactions_to_search).await?;
+    let our_address = our_cosmos_address.to_string();
+    let mut others: HashMap<u64, Vec<&SubmittedClaim>> = HashMap::new();
+    for claim in submitted.iter().filter(|claim| claim.signer != our_address) {
+        others
+            .entry(claim.claim.event_nonce())
+            .or_default()
+            .push(claim);
+    }
+
+    let (mut verified, mut divergent) = (0, 0);
+    for claim in &ours {
+        let nonce = claim.event_nonce();
+        let comparison = compare_claims(
+            claim,
+            others.get(&nonce).map(Vec::as_slice).unwrap_or_default(),
+        );
+        match comparison {
+            ClaimComparison::Unconfirmed => {}
+            ClaimComparison::Agreed => verified += 1,
+            ClaimComparison::OthersDiverged(signers) => {
+                verified += 1;
+                if state.flagged.insert(nonce) {
+                    warn!(
+                        "Validators {:?} claimed a different {} event for event nonce {} than us and most validators, their Ethereum nodes may be returning bad data",
+                        signers,
+                        claim.kind(),
+                        nonce
+                    );
+                }
+            }
+            ClaimComparison::Diverged { majority, signers } => {
+                divergent += 1;
+                if state.flagged.insert(nonce) {
+                    metrics::inc_oracle_claim_divergences(claim.kind());
+                    error!(
+                        "ORACLE CLAIM DIVERGENCE at event nonce {}! We claimed {:?} but validators {:?} claimed {:?}. Either our Ethereum node is returning bad data or a majority of validators are, check your Ethereum node now",
+                        nonce, claim, signers, majority
+                    );
+                    notify(
+                        NotificationKind::ClaimDivergence,
+                        format!(
+                            "Our {} claim for event nonce {} differs from the one validators {:?} claimed",
+                            claim.kind(),
+                            nonce,
+                            signers
+                        ),
+                    );
+                }
+            }
+        }
+
+        if let Err(e) = verify_erc20_mapping(grpc_client, claim, state).await {
+            debug!(
+                "Could not check the ERC20 mapping for event nonce {} {:?}",
+                nonce, e
+            );
+        }
+    }
+    metrics::set_oracle_claims(verified, divergent);
+
+    Ok(())
+}
+
+/// How the chain's denom for a token we saw deposited compares with what we saw on Ethereum
+#[derive(Debug, Clone, PartialEq)]
+enum DepositMapping {
+    Matches,
+    /// the chain maps the token to a different denom than the one we expected
+    Differs {
+        expected: String,
+    },
+    /// the token is Cosmos originated but we didn't see its deploy, so there is nothing to compare with
+    Unknown,
+}
+
+/// Tokens deployed for a Cosmos denom must map to the denom of the deploy we saw, any other token is
+/// Ethereum originated and the chain names its denom after the contract
+fn check_deposit_mapping(
+    token_contract: &str,
+    chain_denom: &str,
+    our_deploy_denom: Option<&str>,
+) -> DepositMapping {
+    let ethereum_originated = format!("gravity{}", token_contract);
+    match our_deploy_denom {
+        Some(denom) if denom == chain_denom => DepositMapping::Matches,
+        Some(denom) => DepositMapping::Differs {
+            expected: denom.to_string(),
+        },
+        None if chain_denom.eq_ignore_ascii_case(&ethereum_originated) => DepositMapping::Matches,
+        None if chain_denom.to_ascii_lowercase().starts_with("gravity0x") => {
+            DepositMapping::Differs {
+                expected: ethereum_originated,
+            }
+        }
+        None => DepositMapping::Unknown,
+    }
+}
+
+/// Checks the chain maps the tokens in our deposit and ERC20 deploy claims the way we saw them on Ethereum
+async fn verify_erc20_mapping(
+    grpc_client: &mut GravityQueryClient<Channel>,
+    claim: &EthereumEventClaim,
+    state: &mut VerifierState,
+) -> Result<(), GravityError> {
+    let divergence = match claim {
+        EthereumEventClaim::SendToCosmos(event) => {
+            if state.verified_mappings.contains(&event.token_contract) {
+                return Ok(());
+            }
+            let erc20: EthAddress = event.token_contract.parse()?;
+            let chain_denom = get_erc20_denom(grpc_client, erc20).await?;
+            let our_deploy_denom = OUR_ERC20_DEPLOYS
+                .read()
+                .unwrap()
+                .get(&event.token_contract.to_ascii_lowercase())
+                .cloned();
+            match check_deposit_mapping(
+                &event.token_contract,
+                &chain_denom,
+                our_deploy_denom.as_deref(),
+            ) {
+                DepositMapping::Matches => {
+                    state.verified_mappings.insert(event.token_contract.clone());
+                    return Ok(());
+                }
+                DepositMapping::Differs { expected } => format!(
+                    "We saw ERC20 {} deposited, which should map to denom {}, but the chain maps it to {}",
+                    event.token_contract, expected, chain_denom
+                ),
+                DepositMapping::Unknown => return Ok(()),
+            }
+        }
+        EthereumEventClaim::Erc20Deployed(event) => {
+            if state.verified_mappings.contains(&event.cosmos_denom) {
+                return Ok(());
+            }
+            let mapping = get_denom_erc20(grpc_client, event.cosmos_denom.clone()).await?;
+            if mapping.erc20.is_empty() {
+                // the deploy has not been observed yet
+                return Ok(());
+            }
+            if mapping.erc20.eq_ignore_ascii_case(&event.token_contract) {
+                state.verified_mappings.insert(event.token_contract.clone());
+                state.verified_mappings.insert(event.cosmos_denom.clone());
+                return Ok(());
+            }
+            format!(
+                "We saw ERC20 {} deployed for denom {} but the chain maps the denom to {}",
+                event.token_contract, event.cosmos_denom, mapping.erc20
+            )
+        }
+        _ => return Ok(()),
+    };
+
+    if state.flagged.insert(claim.event_nonce()) {
+        metrics::inc_oracle_claim_divergences("erc20_mapping");
+        error!(
+            "ORACLE CLAIM DIVERGENCE at event nonce {}! {}",
+            claim.event_nonce(),
+            divergence
+        );
+        notify(
+            NotificationKind::ClaimDivergence,
+            format!("At event nonce {}: {}", claim.event_nonce(), divergence),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn submitted(signer: &str, claim: &EthereumEventClaim) -> SubmittedClaim {
+    SubmittedClaim {
+        signer: signer.to_string(),
+        height: 1,
+        tx_hash: String::new(),
+        claim: claim.clone(),
+    }
+}
+
+#[test]
+fn test_compare_claims() {
+    use gravity_proto::gravity::BatchExecutedEvent;
+
+    let batch = |batch_nonce| {
+        EthereumEventClaim::BatchExecuted(BatchExecutedEvent {
+            event_nonce: 5,
+            batch_nonce,
+            ethereum_height: 100,
+            token_contract: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(),
+        })
+    };
+    let ours = batch(1);
+    let bad = batch(2);
+
+    assert_eq!(compare_claims(&ours, &[]), ClaimComparison::Unconfirmed);
+
+    let a = submitted("a", &ours);
+    let b = submitted("b", &ours);
+    let c = submitted("c", &bad);
+    let d = submitted("d", &bad);
+    let e = submitted("e", &bad);
+    assert_eq!(compare_claims(&ours, &[&a, &b]), ClaimComparison::Agreed);
+    assert_eq!(
+        compare_claims(&ours, &[&a, &c]),
+        ClaimComparison::OthersDiverged(vec!["c".to_string()])
+    );
+    assert_eq!(
+        compare_claims(&ours, &[&a, &c, &d, &e]),
+        ClaimComparison::Diverged {
+            majority: bad,
+            signers: vec!["c".to_string(), "d".to_string(), "e".to_string()],
+        }
+    );
+}
+
+#[test]
+fn test_check_deposit_mapping() {
+    let token = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+    let ethereum_denom = format!("gravity{}", token);
+
+    // an Ethereum originated token, whatever the address case the chain uses
+    assert_eq!(
+        check_deposit_mapping(token, &ethereum_denom.to_ascii_lowercase(), None),
+        DepositMapping::Matches
+    );
+    assert_eq!(
+        check_deposit_mapping(
+            token,
+            "gravity0x0000000000000000000000000000000000000001",
+            None
+        ),
+        DepositMapping::Differs {
+            expected: ethereum_denom.clone()
+        }
+    );
+    // a Cosmos originated token whose deploy we saw
+    assert_eq!(
+        check_deposit_mapping(token, "ustake", Some("ustake")),
+        DepositMapping::Matches
+    );
+    // the chain maps the token differently from the deploy we claimed
+    assert_eq!(
+        check_deposit_mapping(token, &ethereum_denom, Some("ustake")),
+        DepositMapping::Differs {
+            expected: "ustake".to_string()
+        }
+    );
+    assert_eq!(
+        check_deposit_mapping(token, "uatom", Some("ustake")),
+        DepositMapping::Differs {
+            expected: "ustake".to_string()
+        }
+    );
+    assert_eq!(
+        check_deposit_mapping(token, "ustake", None),
+        DepositMapping::Unknown
+    );
+}
@@ -1,13 +1,17 @@
 //! Ethereum Event watcher watches for events such as a deposit to the Gravity Ethereum contract or a validator set update
 //! or a transaction batch update. It then responds to these events by performing actions on the Cosmos chain if required
 
+use crate::claim_verifier;
 use crate::get_with_retry::get_block_number_with_retry;
 use crate::get_with_retry::get_network_id_with_retry;
+use crate::health;
 use crate::metrics;
 use cosmos_gravity::build;
+use cosmos_gravity::claims::ethereum_event_claims;
 use cosmos_gravity::crypto::CosmosSigner;
 use cosmos_gravity::query::get_last_event_nonce;
-use deep_space::{Contact, Msg};
+use cosmos_gravity::send::OutgoingMessages;
+use deep_space::Contact;
 use ethereum_gravity::types::EthClient;
 use ethers::prelude::*;
 use ethers::types::Address as EthAddress;
@@ -25,6 +29,7 @@ use gravity_utils::{
 };
 use std::{result::Result, time};
 use tonic::transport::Channel;
+use tracing::info_span;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
@@ -36,7 +41,7 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
     starting_block: U64,
     blocks_to_search: U64,
     block_delay: U64,
-    msg_sender: tokio::sync::mpsc::Sender<Vec<Msg>>,
+    msg_sender: tokio::sync::mpsc::Sender<OutgoingMessages>,
 ) -> Result<U64, GravityError> {
     let prefix = contact.get_prefix();
     let our_cosmos_address = cosmos_key.to_address(&prefix).unwrap();
@@ -113,6 +118,7 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
     // atomicly but lets not take that risk.
     let last_event_nonce = get_last_event_nonce(grpc_client, our_cosmos_address).await?;
     metrics::set_cosmos_last_event_nonce(last_event_nonce);
+    health::set_last_event_nonce(last_event_nonce);
 
     let erc20_deployed_events: Vec<Erc20DeployedEvent> =
         Erc20DeployedEvent::filter_by_event_nonce(last_event_nonce, &erc20_deployed_events);
@@ -129,6 +135,13 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
         ValsetUpdatedEvent::filter_by_event_nonce(last_event_nonce, &valset_updated_events);
 
     for erc20_deployed_event in erc20_deployed_events.iter() {
+        let _span = info_span!(
+            "ethereum_event",
+            kind = "erc20_deployed",
+            event_nonce = %erc20_deployed_event.event_nonce,
+            block_height = %erc20_deployed_event.block_height
+        )
+        .entered();
         info!(
             "Oracle observed ERC20 deploy with denom {}, erc20 name {}, symbol {}, and event_nonce {}",
             erc20_deployed_event.cosmos_denom,
@@ -139,6 +152,13 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
     }
 
     for logic_call_event in logic_call_events.iter() {
+        let _span = info_span!(
+            "ethereum_event",
+            kind = "logic_call_executed",
+            event_nonce = %logic_call_event.event_nonce,
+            block_height = %logic_call_event.block_height
+        )
+        .entered();
         info!(
             "Oracle observed logic call execution with invalidation_id {}, invalidation_nonce {}, and event_nonce {}",
             bytes_to_hex_str(&logic_call_event.invalidation_id),
@@ -148,6 +168,13 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
     }
 
     for send_to_cosmos_event in send_to_cosmos_events.iter() {
+        let _span = info_span!(
+            "ethereum_event",
+            kind = "send_to_cosmos",
+            event_nonce = %send_to_cosmos_event.event_nonce,
+            block_height = %send_to_cosmos_event.block_height
+        )
+        .entered();
         info!(
             "Oracle observed send to cosmos event with ethereum sender {}, cosmos receiver {}, amount {}, and event nonce {}",
             send_to_cosmos_event.sender,
@@ -158,6 +185,14 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
     }
 
     for transaction_batch_event in transaction_batch_events.iter() {
+        let _span = info_span!(
+            "ethereum_event",
+            kind = "batch_executed",
+            event_nonce = %transaction_batch_event.event_nonce,
+            block_height = %transaction_batch_event.block_height,
+            batch_nonce = %transaction_batch_event.batch_nonce
+        )
+        .entered();
         info!(
             "Oracle observed batch with batch_nonce {}, erc20 {}, and event_nonce {}",
             transaction_batch_event.batch_nonce,
@@ -167,6 +202,14 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
     }
 
     for valset_updated_event in valset_updated_events.iter() {
+        let _span = info_span!(
+            "ethereum_event",
+            kind = "valset_updated",
+            event_nonce = %valset_updated_event.event_nonce,
+            block_height = %valset_updated_event.block_height,
+            valset_nonce = %valset_updated_event.valset_nonce
+        )
+        .entered();
         info!(
             "Oracle observed valset update with valset_nonce {}, event_nonce {}, block_height {}, and members {:?}",
             valset_updated_event.valset_nonce,
@@ -193,6 +236,13 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
         );
 
         info!("Sending {} messages to cosmos", messages.len());
+        claim_verifier::record_claims(&ethereum_event_claims(
+            &send_to_cosmos_events,
+            &transaction_batch_events,
+            &erc20_deployed_events,
+            &logic_call_events,
+            &valset_updated_events,
+        ));
 
         if let Some(erc20_deployed_event) = erc20_deployed_events.last() {
             metrics::set_ethereum_last_erc20_event(erc20_deployed_event.event_nonce);
@@ -219,8 +269,17 @@ pub async fn check_for_events<S: Signer + 'static, CS: CosmosSigner>(
             metrics::set_ethereum_last_logic_call_nonce(logic_call_event.invalidation_nonce);
         }
 
+        // the claims for every event from the one after our last submitted nonce up to this one
+        let claims_span = info_span!(
+            "ethereum_claims",
+            first_event_nonce = last_event_nonce + 1,
+            last_event_nonce = last_message_nonce
+        );
         msg_sender
-            .send(messages)
+            .send(OutgoingMessages {
+                messages,
+                span: claims_span,
+            })
             .await
             .expect("Could not send messages");
 
@@ -0,0 +1,165 @@
+//! Tracks what the orchestrator loops last did so that the metrics server can answer health checks.
+//! `/healthz` fails when a loop has stopped ticking, `/readyz` also waits for the oracle resync and
+//! for both chains to be usable, and `/status` reports the progress of the oracle and signer.
+//! The loops themselves are tracked in `gravity_utils::health`, where the relayer and Cosmos send
+//! loops can reach them.
+
+use ethers::types::Address as EthAddress;
+use gravity_utils::health as loops;
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+pub use gravity_utils::health::{
+    loop_ticked, register_loop, set_ready_loop_periods, DEFAULT_READY_LOOP_PERIODS,
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PendingUnsigned {
+    pub valsets: usize,
+    pub batches: usize,
+    pub logic_calls: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfiguredAddresses {
+    pub cosmos_address: String,
+    pub ethereum_address: Option<EthAddress>,
+    pub gravity_contract: Option<EthAddress>,
+    pub payment_address: Option<EthAddress>,
+}
+
+/// The body of `/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct OrchestratorStatus {
+    pub ready: bool,
+    pub not_ready_reasons: Vec<String>,
+    pub oracle_resynced: bool,
+    pub cosmos_ready: bool,
+    pub ethereum_reachable: bool,
+    pub last_checked_block: Option<u64>,
+    pub last_event_nonce: Option<u64>,
+    pub pending_unsigned: PendingUnsigned,
+    pub addresses: ConfiguredAddresses,
+    /// seconds since each loop last ticked, None if it hasn't yet
+    pub loops: BTreeMap<String, Option<u64>>,
+}
+
+struct HealthState {
+    oracle_resynced: bool,
+    cosmos_ready: bool,
+    ethereum_reachable: bool,
+    last_checked_block: Option<u64>,
+    last_event_nonce: Option<u64>,
+    pending_unsigned: PendingUnsigned,
+    addresses: ConfiguredAddresses,
+}
+
+lazy_static! {
+    static ref HEALTH: RwLock<HealthState> = RwLock::new(HealthState {
+        oracle_resynced: false,
+        cosmos_ready: false,
+        ethereum_reachable: false,
+        last_checked_block: None,
+        last_event_nonce: None,
+        pending_unsigned: PendingUnsigned::default(),
+        addresses: ConfiguredAddresses::default(),
+    });
+}
+
+pub fn set_oracle_resynced() {
+    HEALTH.write().unwrap().oracle_resynced = true;
+}
+
+/// Records the chain status seen by a loop, Cosmos is ready once it's reachable and done syncing
+pub fn set_chain_status(cosmos_ready: bool, ethereum_reachable: bool) {
+    let mut health = HEALTH.write().unwrap();
+    health.cosmos_ready = cosmos_ready;
+    health.ethereum_reachable = ethereum_reachable;
+}
+
+pub fn set_last_checked_block(block: u64) {
+    HEALTH.write().unwrap().last_checked_block = Some(block);
+}
+
+pub fn set_last_event_nonce(nonce: u64) {
+    HEALTH.write().unwrap().last_event_nonce = Some(nonce);
+}
+
+pub fn set_pending_unsigned(pending: PendingUnsigned) {
+    HEALTH.write().unwrap().pending_unsigned = pending;
+}
+
+pub fn set_addresses(addresses: ConfiguredAddresses) {
+    HEALTH.write().unwrap().addresses = addresses;
+}
+
+fn not_ready_reasons(health: &HealthState, loops_not_ready: Vec<String>) -> Vec<String> {
+    let mut reasons = loops_not_ready;
+    if !health.oracle_resynced {
+        reasons.push("oracle resync has not completed".to_string());
+    }
+    if !health.cosmos_ready {
+        reasons.push("Cosmos node is unreachable or syncing".to_string());
+    }
+    if !health.ethereum_reachable {
+        reasons.push("Ethereum node is unreachable".to_string());
+    }
+    reasons
+}
+
+/// Ok once the orchestrator is doing its job, otherwise the reasons it isn't
+pub fn readiness() -> Result<(), Vec<String>> {
+    let health = HEALTH.read().unwrap();
+    let reasons = not_ready_reasons(&health, loops::loops_not_ready());
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons)
+    }
+}
+
+pub fn status() -> OrchestratorStatus {
+    let health = HEALTH.read().unwrap();
+    let not_ready_reasons = not_ready_reasons(&health, loops::loops_not_ready());
+    OrchestratorStatus {
+        ready: not_ready_reasons.is_empty(),
+        not_ready_reasons,
+        oracle_resynced: health.oracle_resynced,
+        cosmos_ready: health.cosmos_ready,
+        ethereum_reachable: health.ethereum_reachable,
+        last_checked_block: health.last_checked_block,
+        last_event_nonce: health.last_event_nonce,
+        pending_unsigned: health.pending_unsigned.clone(),
+        addresses: health.addresses.clone(),
+        loops: loops::loop_ages(),
+    }
+}
+
+#[test]
+fn test_not_ready_reasons() {
+    let mut health = HealthState {
+        oracle_resynced: true,
+        cosmos_ready: true,
+        ethereum_reachable: true,
+        last_checked_block: None,
+        last_event_nonce: None,
+        pending_unsigned: PendingUnsigned::default(),
+        addresses: ConfiguredAddresses::default(),
+    };
+    assert!(not_ready_reasons(&health, Vec::new()).is_empty());
+    // loops still starting up or stuck are passed in from the loop registry
+    assert_eq!(
+        not_ready_reasons(
+            &health,
+            vec!["eth_signer loop has not ticked yet".to_string()]
+        )
+        .len(),
+        1
+    );
+
+    health.cosmos_ready = false;
+    health.oracle_resynced = false;
+    assert_eq!(not_ready_reasons(&health, Vec::new()).len(), 2);
+}
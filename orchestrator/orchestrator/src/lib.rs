@@ -9,11 +9,15 @@
 //!   * Access to an Cosmos chain RPC server
 //!   * Access to an Ethereum chain RPC server
 
+pub mod bridge_lag;
+pub mod claim_verifier;
 pub mod ethereum_event_watcher;
 pub mod get_with_retry;
+pub mod health;
 pub mod main_loop;
 pub mod metrics;
 pub mod oracle_resync;
+pub mod participation;
 
 #[macro_use]
 extern crate log;
@@ -4,13 +4,18 @@
 //! and has its own crate and binary so that anyone may run it.
 
 use crate::ethereum_event_watcher::get_block_delay;
+use crate::health::{self, ConfiguredAddresses, PendingUnsigned};
 use crate::metrics;
 use crate::{
-    ethereum_event_watcher::check_for_events, metrics::metrics_main_loop,
+    bridge_lag::{bridge_lag_main_loop, BridgeLagConfig},
+    claim_verifier::{claim_verifier_main_loop, ClaimVerifierConfig},
+    ethereum_event_watcher::check_for_events,
+    metrics::metrics_main_loop,
     oracle_resync::get_last_checked_block,
+    participation::{participation_monitor_main_loop, ParticipationMonitorConfig},
 };
 use cosmos_gravity::crypto::CosmosSigner;
-use cosmos_gravity::send::send_main_loop;
+use cosmos_gravity::send::{send_main_loop, OutgoingMessages};
 use cosmos_gravity::{
     build,
     query::{
@@ -20,20 +25,27 @@ use cosmos_gravity::{
 };
 use deep_space::client::ChainStatus;
 use deep_space::error::CosmosGrpcError;
-use deep_space::{Contact, Msg};
+use deep_space::Contact;
+use ethereum_gravity::nonce_manager::AccountNonce;
 use ethereum_gravity::types::EthClient;
 use ethereum_gravity::utils::get_gravity_id;
 use ethers::{prelude::*, types::Address as EthAddress};
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_utils::ethereum::bytes_to_hex_str;
+use gravity_utils::notifier::{notify, NotificationKind};
 use gravity_utils::types::config::RelayerMode;
+use relayer::batch_requesting::{batch_request_main_loop, BatchRequestConfig};
+use relayer::budget::SpendLimits;
 use relayer::fee_manager::FeeManager;
-use relayer::main_loop::relayer_main_loop;
+use relayer::gas_scheduling::GasSchedule;
+use relayer::logic_call_relaying::LogicCallFilter;
+use relayer::main_loop::{relayer_main_loop, DEFAULT_BRIDGE_NAME};
 use std::convert::TryInto;
 use std::process::exit;
 use std::{net, time::Duration};
 use tokio::time::sleep as delay_for;
 use tonic::transport::Channel;
+use tracing::{info_span, Instrument};
 
 /// The execution speed governing all loops in this file
 /// which is to say all loops started by Orchestrator main
@@ -59,15 +71,31 @@ pub async fn orchestrator_main_loop<S: Signer + 'static, CS: CosmosSigner>(
     gas_price: (f64, String),
     gas_limit: u64,
     metrics_listen: &net::SocketAddr,
-    eth_gas_price_multiplier: f32,
-    eth_gas_multiplier: f32,
+    eth_gas_price_multiplier_bps: u32,
+    eth_gas_multiplier_bps: u32,
     blocks_to_search: u64,
     gas_adjustment: f64,
     relayer_opt_out: bool,
     cosmos_msg_batch_size: u32,
     mode: RelayerMode,
     supported_contracts: Vec<EthAddress>,
+    logic_call_filter: LogicCallFilter,
+    batch_request_config: Option<BatchRequestConfig>,
+    spend_limits: SpendLimits,
+    watch_pending_relays: bool,
+    gas_schedule: GasSchedule,
+    participation_monitor: Option<ParticipationMonitorConfig>,
+    bridge_lag: Option<BridgeLagConfig>,
+    claim_verifier: Option<ClaimVerifierConfig>,
 ) {
+    let our_cosmos_address = cosmos_key.to_address(&contact.get_prefix()).unwrap();
+    health::set_addresses(ConfiguredAddresses {
+        cosmos_address: our_cosmos_address.to_string(),
+        ethereum_address: Some(eth_client.address()),
+        gravity_contract: Some(gravity_contract_address),
+        payment_address: Some(payment_address),
+    });
+
     let (tx, rx) = tokio::sync::mpsc::channel(1);
 
     let a = send_main_loop(
@@ -92,7 +120,7 @@ pub async fn orchestrator_main_loop<S: Signer + 'static, CS: CosmosSigner>(
     );
 
     let c = eth_signer_main_loop(
-        cosmos_key,
+        cosmos_key.clone(),
         contact.clone(),
         eth_client.clone(),
         grpc_client.clone(),
@@ -100,7 +128,40 @@ pub async fn orchestrator_main_loop<S: Signer + 'static, CS: CosmosSigner>(
         tx.clone(),
     );
 
-    let d = metrics_main_loop(metrics_listen);
+    let d = futures::future::join4(
+        metrics_main_loop(metrics_listen),
+        async {
+            if let Some(config) = participation_monitor {
+                info!("Monitoring this orchestrator's signatures for slashing risk");
+                participation_monitor_main_loop(
+                    contact.clone(),
+                    grpc_client.clone(),
+                    our_cosmos_address,
+                    config,
+                )
+                .await;
+            }
+        },
+        async {
+            if let Some(config) = bridge_lag {
+                info!("Collecting bridge lag metrics");
+                bridge_lag_main_loop(
+                    eth_client.clone(),
+                    grpc_client.clone(),
+                    gravity_contract_address,
+                    our_cosmos_address,
+                    config,
+                )
+                .await;
+            }
+        },
+        async {
+            if let Some(config) = claim_verifier {
+                info!("Verifying oracle claims against the other validators");
+                claim_verifier_main_loop(grpc_client.clone(), our_cosmos_address, config).await;
+            }
+        },
+    );
 
     if !relayer_opt_out {
         let mut fee_manager = FeeManager::new_fee_manager(mode).await.unwrap();
@@ -110,13 +171,32 @@ pub async fn orchestrator_main_loop<S: Signer + 'static, CS: CosmosSigner>(
             grpc_client.clone(),
             gravity_contract_address,
             payment_address,
-            eth_gas_price_multiplier,
+            eth_gas_price_multiplier_bps,
             &mut fee_manager,
-            eth_gas_multiplier,
+            eth_gas_multiplier_bps,
             blocks_to_search,
             supported_contracts,
+            logic_call_filter,
+            spend_limits,
+            watch_pending_relays,
+            DEFAULT_BRIDGE_NAME.to_string(),
+            AccountNonce::default(),
+            gas_schedule,
         );
-        futures::future::join5(a, b, c, d, e).await;
+        let f = async {
+            if let Some(batch_request_config) = batch_request_config {
+                batch_request_main_loop(
+                    cosmos_key,
+                    contact.clone(),
+                    eth_client.clone(),
+                    grpc_client.clone(),
+                    mode,
+                    batch_request_config,
+                )
+                .await;
+            }
+        };
+        futures::future::join5(a, b, c, d, futures::future::join(e, f)).await;
     } else {
         futures::future::join4(a, b, c, d).await;
     }
@@ -138,8 +218,9 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
     grpc_client: GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     blocks_to_search: u64,
-    msg_sender: tokio::sync::mpsc::Sender<Vec<Msg>>,
+    msg_sender: tokio::sync::mpsc::Sender<OutgoingMessages>,
 ) {
+    health::register_loop("eth_oracle", ETH_ORACLE_LOOP_SPEED);
     info!("Check gravity id");
     let gravity_id = get_gravity_id(
         gravity_contract_address,
@@ -165,6 +246,10 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
     };
 
     info!("Using block delay {:?}", block_delay);
+    notify(
+        NotificationKind::ResyncStarted,
+        "Oracle resync started, searching Ethereum for the last event we claimed",
+    );
     let mut last_checked_block = get_last_checked_block(
         grpc_client.clone(),
         our_cosmos_address,
@@ -174,16 +259,33 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
     )
     .await;
     info!("Oracle resync complete, Oracle now operational");
+    notify(
+        NotificationKind::ResyncFinished,
+        format!(
+            "Oracle resync finished, resuming from Ethereum block {}",
+            last_checked_block
+        ),
+    );
+    health::set_oracle_resynced();
+    health::set_last_checked_block(last_checked_block.as_u64());
     let mut grpc_client = grpc_client;
     let mut loop_count: u32 = 0;
+    // the last block searched for events and its hash, to notice it being reorged away
+    let mut checked_block_hash: Option<(U64, H256)> = None;
 
     loop {
+        let span = info_span!(
+            "eth_oracle",
+            iteration = loop_count,
+            last_checked_block = last_checked_block.as_u64()
+        );
         let (async_resp, _) = tokio::join!(
             async {
                 let latest_eth_block = eth_client.get_block_number().await;
                 let latest_cosmos_block = contact.get_chain_status().await;
                 match (latest_eth_block, latest_cosmos_block) {
                     (Ok(latest_eth_block), Ok(ChainStatus::Moving { block_height })) => {
+                        health::set_chain_status(true, true);
                         metrics::set_cosmos_block_height(block_height);
                         metrics::set_ethereum_block_height(latest_eth_block.as_u64());
                         trace!(
@@ -193,24 +295,32 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                         );
                     }
                     (Ok(_latest_eth_block), Ok(ChainStatus::Syncing)) => {
+                        health::set_chain_status(false, true);
                         warn!("Cosmos node syncing, Eth oracle paused");
                         delay_for(DELAY).await;
                     }
                     (Ok(_latest_eth_block), Ok(ChainStatus::WaitingToStart)) => {
+                        health::set_chain_status(false, true);
                         warn!("Cosmos node syncing waiting for chain start, Eth oracle paused");
                         delay_for(DELAY).await;
                     }
                     (Ok(_), Err(_)) => {
+                        health::set_chain_status(false, true);
                         metrics::COSMOS_UNAVAILABLE.inc();
                         warn!("Could not contact Cosmos grpc, trying again");
                         delay_for(DELAY).await;
                     }
-                    (Err(_), Ok(_)) => {
+                    (Err(_), Ok(status)) => {
+                        health::set_chain_status(
+                            matches!(status, ChainStatus::Moving { .. }),
+                            false,
+                        );
                         metrics::ETHEREUM_UNAVAILABLE.inc();
                         warn!("Could not contact Eth node, trying again");
                         delay_for(DELAY).await;
                     }
                     (Err(_), Err(_)) => {
+                        health::set_chain_status(false, false);
                         metrics::COSMOS_UNAVAILABLE.inc();
                         metrics::ETHEREUM_UNAVAILABLE.inc();
                         error!("Could not reach Ethereum or Cosmos rpc!");
@@ -218,6 +328,10 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                     }
                 }
 
+                if let Some((block, hash)) = checked_block_hash {
+                    check_for_reorg(eth_client.clone(), block, hash).await;
+                }
+
                 // Relays events from Ethereum -> Cosmos
                 match check_for_events(
                     eth_client.clone(),
@@ -234,6 +348,10 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                 {
                     Ok(new_block) => {
                         last_checked_block = new_block;
+                        health::set_last_checked_block(last_checked_block.as_u64());
+                        checked_block_hash = get_block_hash(eth_client.clone(), new_block)
+                            .await
+                            .map(|hash| (new_block, hash));
 
                         // send Ethereum height to the Cosmos chain periodically
                         if loop_count % HEIGHT_UPDATE_INTERVAL == 0 {
@@ -245,7 +363,7 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                             .await;
 
                             msg_sender
-                                .send(messages)
+                                .send(OutgoingMessages::new(messages))
                                 .await
                                 .expect("Could not send Ethereum height votes");
                         }
@@ -261,14 +379,47 @@ pub async fn eth_oracle_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                         }
                     }
                 }
-            },
+            }
+            .instrument(span),
             delay_for(ETH_ORACLE_LOOP_SPEED)
         );
 
+        health::loop_ticked("eth_oracle");
         loop_count += 1;
     }
 }
 
+async fn get_block_hash<S: Signer + 'static>(eth_client: EthClient<S>, block: U64) -> Option<H256> {
+    match eth_client.get_block(block).await {
+        Ok(block) => block.and_then(|block| block.hash),
+        Err(e) => {
+            warn!("Could not get the hash of block {} {:?}", block, e);
+            None
+        }
+    }
+}
+
+/// Warns if a block the oracle has already searched for events now has a different hash. The oracle
+/// waits for the block delay before searching so this means a reorg deeper than it expects, and events
+/// it claimed may no longer exist on Ethereum
+async fn check_for_reorg<S: Signer + 'static>(eth_client: EthClient<S>, block: U64, hash: H256) {
+    if let Some(new_hash) = get_block_hash(eth_client, block).await {
+        if new_hash != hash {
+            error!(
+                "Ethereum reorg detected, block {} changed from {:?} to {:?} after the oracle searched it",
+                block, hash, new_hash
+            );
+            notify(
+                NotificationKind::ReorgDetected,
+                format!(
+                    "Ethereum block {} changed from {:?} to {:?} after the oracle searched it for events",
+                    block, hash, new_hash
+                ),
+            );
+        }
+    }
+}
+
 /// The eth_signer simply signs off on any batches or validator sets provided by the validator
 /// since these are provided directly by a trusted Cosmsos node they can simply be assumed to be
 /// valid and signed off on.
@@ -279,8 +430,9 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
     eth_client: EthClient<S>,
     grpc_client: GravityQueryClient<Channel>,
     contract_address: EthAddress,
-    msg_sender: tokio::sync::mpsc::Sender<Vec<Msg>>,
+    msg_sender: tokio::sync::mpsc::Sender<OutgoingMessages>,
 ) {
+    health::register_loop("eth_signer", ETH_SIGNER_LOOP_SPEED);
     let our_cosmos_address = cosmos_key.to_address(&contact.get_prefix()).unwrap();
     let mut grpc_client = grpc_client;
 
@@ -291,14 +443,17 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
         return;
     }
     let gravity_id = gravity_id.unwrap();
+    let mut loop_count: u64 = 0;
 
     loop {
+        let span = info_span!("eth_signer", iteration = loop_count);
         let (async_resp, _) = tokio::join!(
             async {
                 let latest_eth_block = eth_client.get_block_number().await;
                 let latest_cosmos_block = contact.get_chain_status().await;
                 match (latest_eth_block, latest_cosmos_block) {
                     (Ok(latest_eth_block), Ok(ChainStatus::Moving { block_height })) => {
+                        health::set_chain_status(true, true);
                         metrics::set_cosmos_block_height(block_height);
                         metrics::set_ethereum_block_height(latest_eth_block.as_u64());
                         trace!(
@@ -308,24 +463,32 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                         );
                     }
                     (Ok(_latest_eth_block), Ok(ChainStatus::Syncing)) => {
+                        health::set_chain_status(false, true);
                         warn!("Cosmos node syncing, Eth signer paused");
                         delay_for(DELAY).await;
                     }
                     (Ok(_latest_eth_block), Ok(ChainStatus::WaitingToStart)) => {
+                        health::set_chain_status(false, true);
                         warn!("Cosmos node syncing waiting for chain start, Eth signer paused");
                         delay_for(DELAY).await;
                     }
                     (Ok(_), Err(_)) => {
+                        health::set_chain_status(false, true);
                         metrics::COSMOS_UNAVAILABLE.inc();
                         warn!("Could not contact Cosmos grpc, trying again");
                         delay_for(DELAY).await;
                     }
-                    (Err(_), Ok(_)) => {
+                    (Err(_), Ok(status)) => {
+                        health::set_chain_status(
+                            matches!(status, ChainStatus::Moving { .. }),
+                            false,
+                        );
                         metrics::ETHEREUM_UNAVAILABLE.inc();
                         warn!("Could not contact Eth node, trying again");
                         delay_for(DELAY).await;
                     }
                     (Err(_), Err(_)) => {
+                        health::set_chain_status(false, false);
                         metrics::COSMOS_UNAVAILABLE.inc();
                         metrics::ETHEREUM_UNAVAILABLE.inc();
                         error!("Could not reach Ethereum or Cosmos rpc!");
@@ -333,29 +496,41 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                     }
                 }
 
+                let mut pending = PendingUnsigned::default();
+
                 // sign the last unsigned valsets
                 match get_oldest_unsigned_valsets(&mut grpc_client, our_cosmos_address).await {
                     Ok(valsets) => {
+                        pending.valsets = valsets.len();
                         if valsets.is_empty() {
                             trace!("No validator sets to sign, node is caught up!")
                         } else {
-                            info!(
-                                "Sending {} valset confirms starting with {}",
-                                valsets.len(),
-                                valsets[0].nonce
+                            let span = info_span!(
+                                "valset_confirm",
+                                first_valset_nonce = valsets[0].nonce,
+                                last_valset_nonce = valsets[valsets.len() - 1].nonce
                             );
-                            let messages = build::signer_set_tx_confirmation_messages(
-                                &contact,
-                                eth_client.clone(),
-                                valsets,
-                                cosmos_key.clone(),
-                                gravity_id.clone(),
-                            )
+                            async {
+                                info!(
+                                    "Sending {} valset confirms starting with {}",
+                                    valsets.len(),
+                                    valsets[0].nonce
+                                );
+                                let messages = build::signer_set_tx_confirmation_messages(
+                                    &contact,
+                                    eth_client.clone(),
+                                    valsets,
+                                    cosmos_key.clone(),
+                                    gravity_id.clone(),
+                                )
+                                .await;
+                                msg_sender
+                                    .send(OutgoingMessages::new(messages))
+                                    .await
+                                    .expect("Could not send messages");
+                            }
+                            .instrument(span)
                             .await;
-                            msg_sender
-                                .send(messages)
-                                .await
-                                .expect("Could not send messages");
                         }
                     }
                     Err(e) => {
@@ -372,26 +547,36 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                     .await
                 {
                     Ok(Some(last_unsigned_batch)) => {
-                        info!(
-                            "Sending batch confirm for {}:{} fees {} timeout {}",
-                            last_unsigned_batch.token_contract,
-                            last_unsigned_batch.nonce,
-                            last_unsigned_batch.total_fee.amount,
-                            last_unsigned_batch.batch_timeout,
+                        pending.batches = 1;
+                        let span = info_span!(
+                            "batch_confirm",
+                            batch_nonce = last_unsigned_batch.nonce,
+                            token_contract = %last_unsigned_batch.token_contract
                         );
-                        let transaction_batches = vec![last_unsigned_batch];
-                        let messages = build::batch_tx_confirmation_messages(
-                            &contact,
-                            eth_client.clone(),
-                            transaction_batches,
-                            cosmos_key.clone(),
-                            gravity_id.clone(),
-                        )
+                        async {
+                            info!(
+                                "Sending batch confirm for {}:{} fees {} timeout {}",
+                                last_unsigned_batch.token_contract,
+                                last_unsigned_batch.nonce,
+                                last_unsigned_batch.total_fee.amount,
+                                last_unsigned_batch.batch_timeout,
+                            );
+                            let transaction_batches = vec![last_unsigned_batch];
+                            let messages = build::batch_tx_confirmation_messages(
+                                &contact,
+                                eth_client.clone(),
+                                transaction_batches,
+                                cosmos_key.clone(),
+                                gravity_id.clone(),
+                            )
+                            .await;
+                            msg_sender
+                                .send(OutgoingMessages::new(messages))
+                                .await
+                                .expect("Could not send messages");
+                        }
+                        .instrument(span)
                         .await;
-                        msg_sender
-                            .send(messages)
-                            .await
-                            .expect("Could not send messages");
                     }
                     Ok(None) => info!("No unsigned batches! Everything good!"),
                     Err(e) => {
@@ -406,25 +591,35 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                 let logic_calls =
                     get_oldest_unsigned_logic_call(&mut grpc_client, our_cosmos_address).await;
                 if let Ok(logic_calls) = logic_calls {
+                    pending.logic_calls = logic_calls.len();
                     for logic_call in logic_calls {
-                        info!(
-                            "Sending Logic call confirm for {}:{}",
-                            bytes_to_hex_str(&logic_call.invalidation_id),
-                            logic_call.invalidation_nonce
+                        let span = info_span!(
+                            "logic_call_confirm",
+                            invalidation_id = %bytes_to_hex_str(&logic_call.invalidation_id),
+                            invalidation_nonce = logic_call.invalidation_nonce
                         );
-                        let logic_calls = vec![logic_call];
-                        let messages = build::contract_call_tx_confirmation_messages(
-                            &contact,
-                            eth_client.clone(),
-                            logic_calls,
-                            cosmos_key.clone(),
-                            gravity_id.clone(),
-                        )
+                        async {
+                            info!(
+                                "Sending Logic call confirm for {}:{}",
+                                bytes_to_hex_str(&logic_call.invalidation_id),
+                                logic_call.invalidation_nonce
+                            );
+                            let logic_calls = vec![logic_call];
+                            let messages = build::contract_call_tx_confirmation_messages(
+                                &contact,
+                                eth_client.clone(),
+                                logic_calls,
+                                cosmos_key.clone(),
+                                gravity_id.clone(),
+                            )
+                            .await;
+                            msg_sender
+                                .send(OutgoingMessages::new(messages))
+                                .await
+                                .expect("Could not send messages");
+                        }
+                        .instrument(span)
                         .await;
-                        msg_sender
-                            .send(messages)
-                            .await
-                            .expect("Could not send messages");
                     }
                 } else if let Err(e) = logic_calls {
                     metrics::UNSIGNED_LOGIC_CALL_FAILURES.inc();
@@ -433,9 +628,14 @@ pub async fn eth_signer_main_loop<S: Signer + 'static, CS: CosmosSigner>(
                         e
                     )
                 }
-            },
+                health::set_pending_unsigned(pending);
+            }
+            .instrument(span),
             delay_for(ETH_SIGNER_LOOP_SPEED)
         );
+
+        health::loop_ticked("eth_signer");
+        loop_count += 1;
     }
 }
 
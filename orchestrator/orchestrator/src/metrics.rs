@@ -1,27 +1,20 @@
 use std::{convert::TryInto, net};
 
-use axum::prelude::*;
+use crate::health;
 use ethers::prelude::*;
-use hyper::Server;
+use ethers::types::Address as EthAddress;
+use gravity_utils::ethereum::format_eth_address;
+use gravity_utils::metrics_server::{self, HealthChecks};
 use lazy_static::lazy_static;
 use prometheus::*;
 
+/// Serves the metrics along with the orchestrator's health checks
 pub async fn metrics_main_loop(addr: &net::SocketAddr) {
-    let get_metrics = || async {
-        let mut buffer = Vec::new();
-        let encoder = TextEncoder::new();
-        let metric_families = prometheus::gather();
-        encoder.encode(&metric_families, &mut buffer).unwrap();
-        String::from_utf8(buffer.clone()).unwrap()
+    let health_checks = HealthChecks {
+        readiness: health::readiness,
+        status: Some(|| serde_json::to_value(health::status()).unwrap_or_default()),
     };
-
-    let app = route("/", get(get_metrics));
-
-    info!("metrics listening on {}", addr);
-    Server::bind(addr)
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+    metrics_server::metrics_main_loop(addr, health_checks).await;
 }
 
 // Counters
@@ -170,6 +163,177 @@ lazy_static! {
     .unwrap();
 }
 
+// Participation gauges, these go down as well as up so are set directly
+lazy_static! {
+    static ref PARTICIPATION_UNSIGNED: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "participation_unsigned",
+            "outgoing objects this orchestrator has not signed yet",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["type"]
+    )
+    .unwrap();
+    static ref PARTICIPATION_OLDEST_UNSIGNED_BLOCKS: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "participation_oldest_unsigned_blocks",
+            "cosmos blocks the oldest unsigned outgoing object has been outstanding",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["type"]
+    )
+    .unwrap();
+    static ref PARTICIPATION_BLOCKS_TO_SLASH: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "participation_blocks_to_slash",
+            "cosmos blocks left before an unsigned outgoing object can be slashed",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["type"]
+    )
+    .unwrap();
+    static ref PARTICIPATION_SECONDS_TO_SLASH: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "participation_seconds_to_slash",
+            "estimated seconds left before an unsigned outgoing object can be slashed",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["type"]
+    )
+    .unwrap();
+}
+
+// Bridge lag gauges, comparing the Gravity contract to the gravity module
+lazy_static! {
+    static ref BRIDGE_EVENT_NONCE_GAP: IntGauge = register_int_gauge!(opts!(
+        "bridge_event_nonce_gap",
+        "ethereum event nonce minus the last event nonce this orchestrator submitted to cosmos",
+        labels! {"chain" => "ethereum"}
+    ))
+    .unwrap();
+    static ref BRIDGE_VALSET_NONCE_GAP: IntGauge = register_int_gauge!(opts!(
+        "bridge_valset_nonce_gap",
+        "latest cosmos valset nonce minus the valset nonce in the gravity contract",
+        labels! {"chain" => "cosmos"}
+    ))
+    .unwrap();
+    static ref BRIDGE_BATCH_NONCE_GAP: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "bridge_batch_nonce_gap",
+            "last cosmos batch nonce minus the batch nonce in the gravity contract",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["token"]
+    )
+    .unwrap();
+    static ref BRIDGE_ETHEREUM_HEIGHT_LAG: IntGauge = register_int_gauge!(opts!(
+        "bridge_ethereum_height_lag",
+        "latest ethereum block minus the last ethereum height observed on cosmos",
+        labels! {"chain" => "ethereum"}
+    ))
+    .unwrap();
+    static ref BRIDGE_OLDEST_PENDING_SEND_TO_ETHEREUM_SECONDS: IntGauge =
+        register_int_gauge!(opts!(
+            "bridge_oldest_pending_send_to_ethereum_seconds",
+            "seconds the oldest pending send to ethereum has been seen waiting",
+            labels! {"chain" => "cosmos"}
+        ))
+        .unwrap();
+    static ref BRIDGE_SECONDS_SINCE_LAST_RELAY: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "bridge_seconds_since_last_relay",
+            "seconds since a batch was last relayed for the token",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["token"]
+    )
+    .unwrap();
+}
+
+// Oracle claim verification, comparing our claims with the other validators'
+lazy_static! {
+    static ref ORACLE_CLAIM_DIVERGENCES: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "oracle_claim_divergences",
+            "event nonces where our claim differed from what the chain or most validators claimed",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["type"]
+    )
+    .unwrap();
+    static ref ORACLE_VERIFIED_CLAIMS: IntGauge = register_int_gauge!(opts!(
+        "oracle_verified_claims",
+        "recent claims of ours that most validators agree with",
+        labels! {"chain" => "cosmos"}
+    ))
+    .unwrap();
+    static ref ORACLE_DIVERGENT_CLAIMS: IntGauge = register_int_gauge!(opts!(
+        "oracle_divergent_claims",
+        "recent claims of ours that most validators disagree with",
+        labels! {"chain" => "cosmos"}
+    ))
+    .unwrap();
+}
+
+pub fn inc_oracle_claim_divergences(kind: &str) {
+    ORACLE_CLAIM_DIVERGENCES.with_label_values(&[kind]).inc();
+}
+
+pub fn set_oracle_claims(verified: usize, divergent: usize) {
+    ORACLE_VERIFIED_CLAIMS.set(verified.try_into().unwrap_or(i64::MAX));
+    ORACLE_DIVERGENT_CLAIMS.set(divergent.try_into().unwrap_or(i64::MAX));
+}
+
+pub fn set_bridge_event_nonce_gap(v: i64) {
+    BRIDGE_EVENT_NONCE_GAP.set(v);
+}
+
+pub fn set_bridge_valset_nonce_gap(v: i64) {
+    BRIDGE_VALSET_NONCE_GAP.set(v);
+}
+
+pub fn set_bridge_batch_nonce_gap(token: EthAddress, v: i64) {
+    BRIDGE_BATCH_NONCE_GAP
+        .with_label_values(&[&format_eth_address(token)])
+        .set(v);
+}
+
+pub fn set_bridge_ethereum_height_lag(v: i64) {
+    BRIDGE_ETHEREUM_HEIGHT_LAG.set(v);
+}
+
+pub fn set_bridge_oldest_pending_send_to_ethereum_seconds(v: u64) {
+    BRIDGE_OLDEST_PENDING_SEND_TO_ETHEREUM_SECONDS.set(v.try_into().unwrap_or(i64::MAX));
+}
+
+pub fn set_bridge_seconds_since_last_relay(token: EthAddress, v: u64) {
+    BRIDGE_SECONDS_SINCE_LAST_RELAY
+        .with_label_values(&[&format_eth_address(token)])
+        .set(v.try_into().unwrap_or(i64::MAX));
+}
+
+pub fn set_participation(
+    kind: &str,
+    unsigned: usize,
+    oldest_unsigned_blocks: u64,
+    blocks_to_slash: u64,
+    seconds_to_slash: u64,
+) {
+    let to_i64 = |v: u64| v.try_into().unwrap_or(i64::MAX);
+    PARTICIPATION_UNSIGNED
+        .with_label_values(&[kind])
+        .set(to_i64(unsigned as u64));
+    PARTICIPATION_OLDEST_UNSIGNED_BLOCKS
+        .with_label_values(&[kind])
+        .set(to_i64(oldest_unsigned_blocks));
+    PARTICIPATION_BLOCKS_TO_SLASH
+        .with_label_values(&[kind])
+        .set(to_i64(blocks_to_slash));
+    PARTICIPATION_SECONDS_TO_SLASH
+        .with_label_values(&[kind])
+        .set(to_i64(seconds_to_slash));
+}
+
 pub fn set_cosmos_block_height(v: u64) {
     set_u64(&COSMOS_BLOCK_HEIGHT, v)
 }
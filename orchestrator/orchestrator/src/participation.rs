@@ -0,0 +1,320 @@
+//! Watches this orchestrator's participation so that operators find out about missed signatures before
+//! they are slashed for them. The gravity module slashes validators that have not signed an outgoing
+//! valset, batch or logic call once it is `signed_batches_window` blocks old, valsets are also pruned
+//! after `signed_signer_set_txs_window` so the smaller of the two is used for them.
+
+use crate::health;
+use crate::metrics;
+use cosmos_gravity::query::get_gravity_params;
+use deep_space::address::Address as CosmosAddress;
+use deep_space::client::ChainStatus;
+use deep_space::Contact;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_proto::gravity::{
+    Params, UnsignedBatchTxsRequest, UnsignedContractCallTxsRequest, UnsignedSignerSetTxsRequest,
+};
+use gravity_utils::error::GravityError;
+use gravity_utils::ethereum::bytes_to_hex_str;
+use gravity_utils::notifier::{notify, NotificationKind};
+use serde_derive::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::time::sleep as delay_for;
+use tonic::transport::Channel;
+
+pub const PARTICIPATION_LOOP_SPEED: Duration = Duration::from_secs(30);
+
+/// Settings for the participation monitor
+#[derive(Debug, Clone)]
+pub struct ParticipationMonitorConfig {
+    /// alert once an unsigned object is this many blocks or fewer from being slashable
+    pub alert_blocks_to_slash: u64,
+    /// where alerts are posted as JSON, alerts also go to the notifier webhooks
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutgoingKind {
+    Valset,
+    Batch,
+    LogicCall,
+}
+
+impl OutgoingKind {
+    pub const ALL: [OutgoingKind; 3] = [
+        OutgoingKind::Valset,
+        OutgoingKind::Batch,
+        OutgoingKind::LogicCall,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutgoingKind::Valset => "valset",
+            OutgoingKind::Batch => "batch",
+            OutgoingKind::LogicCall => "logic_call",
+        }
+    }
+}
+
+/// An outgoing object waiting for our signature, with the Cosmos height it was created at
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnsignedObject {
+    pub kind: OutgoingKind,
+    pub id: String,
+    pub height: u64,
+}
+
+/// The slashing windows from the gravity params, in Cosmos blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlashingWindows {
+    pub valset: u64,
+    pub outgoing: u64,
+    pub average_block_time_ms: u64,
+}
+
+impl SlashingWindows {
+    pub fn from_params(params: &Params) -> Self {
+        SlashingWindows {
+            valset: params
+                .signed_signer_set_txs_window
+                .min(params.signed_batches_window),
+            outgoing: params.signed_batches_window,
+            average_block_time_ms: params.average_block_time,
+        }
+    }
+
+    pub fn window(&self, kind: OutgoingKind) -> u64 {
+        match kind {
+            OutgoingKind::Valset => self.valset,
+            OutgoingKind::Batch | OutgoingKind::LogicCall => self.outgoing,
+        }
+    }
+
+    /// Blocks left before `object` can be slashed, zero once it can be
+    pub fn blocks_to_slash(&self, object: &UnsignedObject, current_height: u64) -> u64 {
+        (object.height + self.window(object.kind)).saturating_sub(current_height)
+    }
+
+    pub fn blocks_to_seconds(&self, blocks: u64) -> u64 {
+        blocks.saturating_mul(self.average_block_time_ms) / 1000
+    }
+}
+
+/// The body posted to the webhook when an unsigned object gets close to being slashable
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticipationAlert {
+    pub cosmos_address: String,
+    pub kind: OutgoingKind,
+    pub id: String,
+    pub height: u64,
+    pub current_height: u64,
+    pub blocks_to_slash: u64,
+    pub seconds_to_slash: u64,
+}
+
+/// Periodically checks how long the outgoing objects we have not signed have been outstanding
+pub async fn participation_monitor_main_loop(
+    contact: Contact,
+    grpc_client: GravityQueryClient<Channel>,
+    our_cosmos_address: CosmosAddress,
+    config: ParticipationMonitorConfig,
+) {
+    let mut grpc_client = grpc_client;
+    let http_client = reqwest::Client::new();
+    let mut alerted: HashSet<UnsignedObject> = HashSet::new();
+
+    health::register_loop("participation_monitor", PARTICIPATION_LOOP_SPEED);
+    loop {
+        tokio::join!(
+            async {
+                if let Err(e) = check_participation(
+                    &contact,
+                    &mut grpc_client,
+                    our_cosmos_address,
+                    &config,
+                    &http_client,
+                    &mut alerted,
+                )
+                .await
+                {
+                    warn!("Could not check orchestrator participation {:?}", e);
+                }
+            },
+            delay_for(PARTICIPATION_LOOP_SPEED)
+        );
+        health::loop_ticked("participation_monitor");
+    }
+}
+
+async fn check_participation(
+    contact: &Contact,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    our_cosmos_address: CosmosAddress,
+    config: &ParticipationMonitorConfig,
+    http_client: &reqwest::Client,
+    alerted: &mut HashSet<UnsignedObject>,
+) -> Result<(), GravityError> {
+    let current_height = match contact.get_chain_status().await? {
+        ChainStatus::Moving { block_height } => block_height,
+        // heights don't mean much while the node catches up
+        ChainStatus::Syncing | ChainStatus::WaitingToStart => return Ok(()),
+    };
+    let windows = SlashingWindows::from_params(&get_gravity_params(grpc_client).await?);
+    let unsigned = get_unsigned_objects(grpc_client, our_cosmos_address).await?;
+
+    for kind in OutgoingKind::ALL.iter() {
+        let oldest = unsigned
+            .iter()
+            .filter(|object| object.kind == *kind)
+            .min_by_key(|object| object.height);
+        let count = unsigned
+            .iter()
+            .filter(|object| object.kind == *kind)
+            .count();
+        let (age, blocks_to_slash) = match oldest {
+            Some(object) => (
+                current_height.saturating_sub(object.height),
+                windows.blocks_to_slash(object, current_height),
+            ),
+            None => (0, windows.window(*kind)),
+        };
+        metrics::set_participation(
+            kind.name(),
+            count,
+            age,
+            blocks_to_slash,
+            windows.blocks_to_seconds(blocks_to_slash),
+        );
+    }
+
+    // alert once per object, signed objects drop out of the unsigned list and are forgotten
+    alerted.retain(|object| unsigned.contains(object));
+    for object in unsigned {
+        let blocks_to_slash = windows.blocks_to_slash(&object, current_height);
+        if blocks_to_slash > config.alert_blocks_to_slash || alerted.contains(&object) {
+            continue;
+        }
+
+        let alert = ParticipationAlert {
+            cosmos_address: our_cosmos_address.to_string(),
+            kind: object.kind,
+            id: object.id.clone(),
+            height: object.height,
+            current_height,
+            blocks_to_slash,
+            seconds_to_slash: windows.blocks_to_seconds(blocks_to_slash),
+        };
+        let message = format!(
+            "Unsigned {} {} created at height {} can be slashed in {} blocks (about {} seconds)",
+            object.kind.name(),
+            object.id,
+            object.height,
+            alert.blocks_to_slash,
+            alert.seconds_to_slash
+        );
+        error!("{}", message);
+        notify(NotificationKind::SlashingRisk, message);
+        if let Some(url) = &config.webhook_url {
+            if let Err(e) = send_alert(http_client, url, &alert).await {
+                warn!("Could not send participation alert to webhook {:?}", e);
+                // try again on the next loop
+                continue;
+            }
+        }
+        alerted.insert(object);
+    }
+
+    Ok(())
+}
+
+async fn send_alert(
+    http_client: &reqwest::Client,
+    url: &str,
+    alert: &ParticipationAlert,
+) -> Result<(), reqwest::Error> {
+    http_client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(alert).unwrap())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Gets every valset, batch and logic call we have not signed, the converted types used by the
+/// signer drop the height these were created at so the proto responses are used directly
+pub async fn get_unsigned_objects(
+    grpc_client: &mut GravityQueryClient<Channel>,
+    address: CosmosAddress,
+) -> Result<Vec<UnsignedObject>, GravityError> {
+    let mut unsigned = Vec::new();
+
+    let valsets = grpc_client
+        .unsigned_signer_set_txs(UnsignedSignerSetTxsRequest {
+            address: address.to_string(),
+        })
+        .await?
+        .into_inner()
+        .signer_sets;
+    unsigned.extend(valsets.into_iter().map(|valset| UnsignedObject {
+        kind: OutgoingKind::Valset,
+        id: valset.nonce.to_string(),
+        height: valset.height,
+    }));
+
+    let batches = grpc_client
+        .unsigned_batch_txs(UnsignedBatchTxsRequest {
+            address: address.to_string(),
+        })
+        .await?
+        .into_inner()
+        .batches;
+    unsigned.extend(batches.into_iter().map(|batch| UnsignedObject {
+        kind: OutgoingKind::Batch,
+        id: format!("{}/{}", batch.batch_nonce, batch.token_contract),
+        height: batch.height,
+    }));
+
+    let calls = grpc_client
+        .unsigned_contract_call_txs(UnsignedContractCallTxsRequest {
+            address: address.to_string(),
+        })
+        .await?
+        .into_inner()
+        .calls;
+    unsigned.extend(calls.into_iter().map(|call| UnsignedObject {
+        kind: OutgoingKind::LogicCall,
+        id: format!(
+            "{}/{}",
+            bytes_to_hex_str(&call.invalidation_scope),
+            call.invalidation_nonce
+        ),
+        height: call.height,
+    }));
+
+    Ok(unsigned)
+}
+
+#[test]
+fn test_slashing_windows() {
+    let windows = SlashingWindows::from_params(&Params {
+        signed_signer_set_txs_window: 10000,
+        signed_batches_window: 5000,
+        average_block_time: 6000,
+        ..Params::default()
+    });
+    assert_eq!(windows.window(OutgoingKind::Valset), 5000);
+    assert_eq!(windows.window(OutgoingKind::LogicCall), 5000);
+
+    let batch = UnsignedObject {
+        kind: OutgoingKind::Batch,
+        id: "1/0x0".to_string(),
+        height: 100,
+    };
+    assert_eq!(windows.blocks_to_slash(&batch, 100), 5000);
+    assert_eq!(windows.blocks_to_slash(&batch, 4900), 200);
+    assert_eq!(windows.blocks_to_slash(&batch, 6000), 0);
+    assert_eq!(windows.blocks_to_seconds(200), 1200);
+}
@@ -1,24 +1,31 @@
+use crate::budget::{RelayType, RelayerBudget};
 use crate::fee_manager::FeeManager;
+use crate::gas_scheduling::{get_recent_base_fees, GasSchedule, ScheduleDecision};
+use crate::metrics::{self, skip_reason};
+use crate::pending_relays::PendingRelayWatcher;
+use crate::relay_history;
+use crate::relay_skips::{handle_estimate_error, handle_relay_error, RelaySkips};
 use cosmos_gravity::query::get_transaction_batch_signatures;
 use cosmos_gravity::query::{get_latest_batch, get_latest_transaction_batches};
 use ethereum_gravity::{
-    one_eth_f32, submit_batch::send_eth_transaction_batch, types::EthClient,
-    utils::get_tx_batch_nonce,
+    nonce_manager::AccountNonce, submit_batch::send_eth_transaction_batch, types::EthClient,
+    utils::format_eth,
 };
 use ethers::prelude::*;
 use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
-use gravity_utils::ethereum::downcast_to_f32;
+use gravity_utils::error::GravityError;
 use gravity_utils::message_signatures::encode_tx_batch_confirm_hashed;
 use gravity_utils::types::{BatchConfirmResponse, TransactionBatch, Valset};
 use std::collections::HashMap;
 use std::time::Duration;
 use tonic::transport::Channel;
+use tracing::{field, info_span, Instrument};
 
 #[derive(Debug, Clone)]
-struct SubmittableBatch {
-    batch: TransactionBatch,
-    sigs: Vec<BatchConfirmResponse>,
+pub(crate) struct SubmittableBatch {
+    pub(crate) batch: TransactionBatch,
+    pub(crate) sigs: Vec<BatchConfirmResponse>,
 }
 
 /// This function relays batches from Cosmos to Ethereum. First we request
@@ -33,15 +40,22 @@ pub async fn relay_batches<S: Signer + 'static>(
     // the validator set currently in the contract on Ethereum
     current_valset: Valset,
     eth_client: EthClient<S>,
+    account_nonce: &AccountNonce,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     payment_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
-    eth_gas_price_multiplier: f32,
+    eth_gas_price_multiplier_bps: u32,
     fee_manager: &mut FeeManager,
-    eth_gas_multiplier: f32,
+    eth_gas_multiplier_bps: u32,
     supported_contracts: Vec<EthAddress>,
+    budget: &mut RelayerBudget,
+    pending_relays: &PendingRelayWatcher,
+    gas_schedule: &GasSchedule,
+    batch_skips: &mut RelaySkips<(EthAddress, u64)>,
+    bridge: &str,
+    latest_ethereum_batches: &HashMap<EthAddress, u64>,
 ) {
     let possible_batches = get_batches_and_signatures(
         current_valset.clone(),
@@ -56,14 +70,21 @@ pub async fn relay_batches<S: Signer + 'static>(
     submit_batches(
         current_valset,
         eth_client.clone(),
+        account_nonce,
         gravity_contract_address,
         payment_address,
         gravity_id,
         timeout,
-        eth_gas_price_multiplier,
-        eth_gas_multiplier,
+        eth_gas_price_multiplier_bps,
+        eth_gas_multiplier_bps,
         possible_batches,
         fee_manager,
+        budget,
+        pending_relays,
+        gas_schedule,
+        batch_skips,
+        bridge,
+        latest_ethereum_batches,
     )
     .await;
 }
@@ -76,7 +97,7 @@ pub async fn relay_batches<S: Signer + 'static>(
 /// set on Ethereum. In both the later and the former case the correct solution is to wait
 /// through timeouts, new signatures, or a later valid batch being submitted old batches will
 /// always be resolved.
-async fn get_batches_and_signatures(
+pub(crate) async fn get_batches_and_signatures(
     current_valset: Valset,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_id: String,
@@ -160,14 +181,24 @@ async fn get_batches_and_signatures(
 async fn submit_batches<S: Signer + 'static>(
     current_valset: Valset,
     eth_client: EthClient<S>,
+    account_nonce: &AccountNonce,
     gravity_contract_address: EthAddress,
     payment_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
-    eth_gas_price_multiplier: f32,
-    eth_gas_multiplier: f32,
+    eth_gas_price_multiplier_bps: u32,
+    eth_gas_multiplier_bps: u32,
     possible_batches: HashMap<EthAddress, Vec<SubmittableBatch>>,
     fee_manager: &mut FeeManager,
+    budget: &mut RelayerBudget,
+    pending_relays: &PendingRelayWatcher,
+    gas_schedule: &GasSchedule,
+    batch_skips: &mut RelaySkips<(EthAddress, u64)>,
+    bridge: &str,
+    // read once for all token types by the main loop, this is valid because we are iterating
+    // from oldest to newest, so submitting a batch earlier in the loop won't ever invalidate
+    // submitting a batch later in the loop. Another relayer could always do that though.
+    latest_ethereum_batches: &HashMap<EthAddress, u64>,
 ) {
     let ethereum_block_height = if let Ok(bn) = eth_client.get_block_number().await {
         bn
@@ -175,23 +206,26 @@ async fn submit_batches<S: Signer + 'static>(
         error!("Failed to get eth block height, is your eth node working?");
         return;
     };
+    let recent_base_fees = if gas_schedule.enabled() && !possible_batches.is_empty() {
+        get_recent_base_fees(eth_client.clone()).await
+    } else {
+        Vec::new()
+    };
 
-    // requests data from Ethereum only once per token type, this is valid because we are
-    // iterating from oldest to newest, so submitting a batch earlier in the loop won't
-    // ever invalidate submitting a batch later in the loop. Another relayer could always
-    // do that though.
     for (token_type, possible_batches) in possible_batches {
         let erc20_contract = token_type;
-        let latest_ethereum_batch =
-            get_tx_batch_nonce(gravity_contract_address, erc20_contract, eth_client.clone()).await;
-        if latest_ethereum_batch.is_err() {
-            error!(
-                "Failed to get latest Ethereum batch with {:?}",
-                latest_ethereum_batch
-            );
-            return;
-        }
-        let latest_ethereum_batch = latest_ethereum_batch.unwrap();
+        let latest_ethereum_batch = match latest_ethereum_batches.get(&erc20_contract) {
+            Some(nonce) => *nonce,
+            None => {
+                debug!(
+                    "Batches for {} appeared after this loop's nonces were read, relaying them next loop",
+                    erc20_contract
+                );
+                continue;
+            }
+        };
+        batch_skips
+            .retain(|(token, nonce)| *token != erc20_contract || *nonce > latest_ethereum_batch);
 
         for batch in possible_batches {
             let oldest_signed_batch = batch.batch;
@@ -202,11 +236,38 @@ async fn submit_batches<S: Signer + 'static>(
                     "Batch {}/{} has timed out and can not be submitted",
                     oldest_signed_batch.nonce, oldest_signed_batch.token_contract
                 );
+                if oldest_signed_batch.nonce > latest_ethereum_batch {
+                    metrics::inc_relays_considered(bridge, RelayType::Batch);
+                    metrics::inc_relays_skipped(bridge, RelayType::Batch, skip_reason::TIMED_OUT);
+                }
                 continue;
             }
 
             let latest_cosmos_batch_nonce = oldest_signed_batch.clone().nonce;
             if latest_cosmos_batch_nonce > latest_ethereum_batch {
+                metrics::inc_relays_considered(bridge, RelayType::Batch);
+                let skip_key = (erc20_contract, latest_cosmos_batch_nonce);
+                if batch_skips.should_skip(&skip_key) {
+                    info!(
+                        "Batch {}/{} was rejected by the contract recently, waiting",
+                        latest_cosmos_batch_nonce, erc20_contract
+                    );
+                    metrics::inc_relays_skipped(bridge, RelayType::Batch, skip_reason::BACKING_OFF);
+                    continue;
+                }
+                if pending_relays.batch_pending(erc20_contract, latest_cosmos_batch_nonce) {
+                    info!(
+                        "Batch {}/{} is already being relayed by another relayer, waiting",
+                        latest_cosmos_batch_nonce, erc20_contract
+                    );
+                    metrics::inc_relays_skipped(
+                        bridge,
+                        RelayType::Batch,
+                        skip_reason::PENDING_ELSEWHERE,
+                    );
+                    continue;
+                }
+
                 let cost = ethereum_gravity::submit_batch::estimate_tx_batch_cost(
                     current_valset.clone(),
                     oldest_signed_batch.clone(),
@@ -218,23 +279,27 @@ async fn submit_batches<S: Signer + 'static>(
                 )
                 .await;
 
-                if cost.is_err() {
-                    warn!("Batch cost estimate failed with {:?}", cost);
-                    continue;
-                }
-
-                let mut cost = cost.unwrap();
-                let total_cost = downcast_to_f32(cost.get_total());
-                if total_cost.is_none() {
-                    error!(
-                        "Total gas cost greater than f32 max, skipping batch submission: {}",
-                        oldest_signed_batch.nonce
-                    );
-                    continue;
-                }
-                let total_cost = total_cost.unwrap();
-                let gas_price_as_f32 = downcast_to_f32(cost.gas_price).unwrap(); // if the total cost isn't greater, this isn't
-                let gas_as_f32 = downcast_to_f32(cost.gas).unwrap(); // same as above re: total cost
+                let cost = match cost {
+                    Ok(cost) => cost,
+                    Err(e) => {
+                        let action = handle_estimate_error::<S>(
+                            bridge,
+                            RelayType::Batch,
+                            &format!(
+                                "Batch {}/{} cost estimate",
+                                latest_cosmos_batch_nonce, erc20_contract
+                            ),
+                            e,
+                        );
+                        batch_skips.record_failure(skip_key, action);
+                        metrics::inc_relays_skipped(
+                            bridge,
+                            RelayType::Batch,
+                            skip_reason::ESTIMATE_FAILED,
+                        );
+                        continue;
+                    }
+                };
 
                 if fee_manager
                     .can_send_batch(
@@ -246,19 +311,59 @@ async fn submit_batches<S: Signer + 'static>(
                 {
                     let token_contract = oldest_signed_batch.token_contract;
 
+                    let blocks_remaining =
+                        oldest_signed_batch.batch_timeout - ethereum_block_height.as_u64();
+                    if let ScheduleDecision::Defer(reason) = gas_schedule.schedule_batch(
+                        blocks_remaining,
+                        cost.gas_price,
+                        &recent_base_fees,
+                    ) {
+                        info!(
+                            "Waiting for cheaper gas to submit batch {}/{}: {}",
+                            latest_cosmos_batch_nonce, token_contract, reason
+                        );
+                        metrics::inc_relays_skipped(
+                            bridge,
+                            RelayType::Batch,
+                            skip_reason::GAS_PRICE,
+                        );
+                        continue;
+                    }
+
                     info!(
-                        "We have detected latest batch {} but latest on Ethereum is {} This batch is estimated to cost {} Gas / {:.4} ETH to submit",
+                        "We have detected latest batch {} but latest on Ethereum is {} This batch is estimated to cost {} Gas / {} ETH to submit",
                         latest_cosmos_batch_nonce,
                         latest_ethereum_batch,
                         cost.gas_price.clone(),
-                        total_cost / one_eth_f32()
+                        format_eth(cost.get_total())
                     );
 
-                    cost.gas_price =
-                        (gas_price_as_f32 as u128 * eth_gas_price_multiplier as u128).into();
+                    let cost =
+                        cost.with_multipliers(eth_gas_price_multiplier_bps, eth_gas_multiplier_bps);
 
-                    cost.gas = ((gas_as_f32 * eth_gas_multiplier) as u128).into();
+                    if let Err(reason) = budget.check(RelayType::Batch, &cost) {
+                        warn!(
+                            "Deferring batch {}/{}: {}",
+                            latest_cosmos_batch_nonce, token_contract, reason
+                        );
+                        metrics::inc_relays_skipped(
+                            bridge,
+                            RelayType::Batch,
+                            skip_reason::SPEND_LIMIT,
+                        );
+                        continue;
+                    }
+                    let max_cost = cost.get_total();
+                    let gas_limit = cost.gas;
+                    let total_fee = oldest_signed_batch.total_fee.clone();
+                    metrics::load_fee_decimals(&eth_client, &[total_fee.clone()]).await;
 
+                    let span = info_span!(
+                        "batch_relay",
+                        batch_nonce = latest_cosmos_batch_nonce,
+                        token_contract = %token_contract,
+                        tx_hash = field::Empty
+                    );
                     let res = send_eth_transaction_batch(
                         current_valset.clone(),
                         oldest_signed_batch,
@@ -269,14 +374,70 @@ async fn submit_batches<S: Signer + 'static>(
                         gravity_id.clone(),
                         cost,
                         eth_client.clone(),
+                        account_nonce,
                     )
+                    .instrument(span.clone())
                     .await;
 
-                    if res.is_err() {
-                        warn!("Batch submission failed with {:?}", res);
-                    } else {
-                        fee_manager.update_next_batch_send_time(token_contract)
+                    let _enter = span.enter();
+                    budget.record_relay(RelayType::Batch, &res, max_cost);
+                    match res {
+                        Ok(outcome) => {
+                            if let Some(tx_hash) = outcome.tx_hash() {
+                                span.record("tx_hash", &field::display(tx_hash));
+                            }
+                            metrics::record_relay_outcome(
+                                bridge,
+                                RelayType::Batch,
+                                gas_limit,
+                                &[total_fee.clone()],
+                                &outcome,
+                            );
+                            relay_history::record_outcome(
+                                bridge,
+                                RelayType::Batch,
+                                latest_cosmos_batch_nonce,
+                                token_contract,
+                                &[total_fee],
+                                &outcome,
+                            );
+                            fee_manager.update_next_batch_send_time(token_contract)
+                        }
+                        Err(e) => {
+                            if let GravityError::TransactionReverted(reverted) = &e {
+                                metrics::record_reverted_relay(
+                                    bridge,
+                                    RelayType::Batch,
+                                    gas_limit,
+                                    reverted,
+                                );
+                            }
+                            relay_history::record_failure(
+                                bridge,
+                                RelayType::Batch,
+                                latest_cosmos_batch_nonce,
+                                token_contract,
+                                &[total_fee],
+                                &e,
+                            );
+                            let action = handle_relay_error::<S>(
+                                bridge,
+                                RelayType::Batch,
+                                &format!(
+                                    "Batch {}/{} submission",
+                                    latest_cosmos_batch_nonce, token_contract
+                                ),
+                                e,
+                            );
+                            batch_skips.record_failure(skip_key, action);
+                        }
                     }
+                } else {
+                    metrics::inc_relays_skipped(
+                        bridge,
+                        RelayType::Batch,
+                        skip_reason::UNPROFITABLE,
+                    );
                 }
             }
         }
@@ -0,0 +1,264 @@
+//! This module contains the optional batch requesting role of the relayer. Batches are only created on
+//! Cosmos when someone sends a MsgRequestBatchTx, so this role watches the fees the module reports per
+//! denom and requests a batch once the FeeManager considers relaying it worthwhile.
+
+use crate::fee_manager::FeeManager;
+use crate::main_loop::DEFAULT_BRIDGE_NAME;
+use cosmos_gravity::crypto::CosmosSigner;
+use cosmos_gravity::query::{get_batch_tx_fees, get_denom_erc20, get_latest_transaction_batches};
+use cosmos_gravity::send::send_request_batch_tx;
+use deep_space::Contact;
+use ethereum_gravity::{
+    types::EthClient,
+    utils::{get_gas_price, GasCost},
+};
+use ethers::prelude::*;
+use ethers::types::Address as EthAddress;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::health;
+use gravity_utils::types::config::RelayerMode;
+use gravity_utils::types::{Erc20Token, TransactionBatch};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tonic::transport::Channel;
+
+pub const BATCH_REQUEST_LOOP_SPEED: Duration = Duration::from_secs(60);
+
+/// Settings for the batch requesting role
+#[derive(Debug, Clone)]
+pub struct BatchRequestConfig {
+    /// the bridge batches are requested for, used to name the health check loop
+    pub bridge: String,
+    pub cosmos_granter: Option<String>,
+    pub gas_price: (f64, String),
+    pub gas_limit: u64,
+    pub gas_adjustment: f64,
+    /// the Ethereum gas a batch is assumed to cost when deciding if it would be profitable
+    pub estimated_batch_gas: U256,
+    /// the minimum time between two batch requests for the same denom
+    pub min_request_interval: Duration,
+    /// where fees are priced, see `FeeManager::new_fee_manager_with_sources`
+    pub token_prices_path: Option<String>,
+    pub relayer_api_url: Option<String>,
+}
+
+/// Periodically requests batches for the tokens whose unbatched fees would make a profitable batch
+pub async fn batch_request_main_loop<S: Signer + 'static, CS: CosmosSigner>(
+    cosmos_key: CS,
+    contact: Contact,
+    eth_client: EthClient<S>,
+    grpc_client: GravityQueryClient<Channel>,
+    mode: RelayerMode,
+    config: BatchRequestConfig,
+) {
+    let mut grpc_client = grpc_client;
+    let mut fee_manager = match FeeManager::new_fee_manager_with_sources(
+        mode,
+        config.token_prices_path.clone(),
+        config.relayer_api_url.clone(),
+    )
+    .await
+    {
+        Ok(fee_manager) => fee_manager,
+        Err(_) => {
+            error!("Could not start the fee manager, batch requests are disabled");
+            return;
+        }
+    };
+    let mut last_requests: HashMap<String, Instant> = HashMap::new();
+
+    // each bridge of a multi-bridge relayer is tracked separately
+    let loop_name = if config.bridge == DEFAULT_BRIDGE_NAME {
+        "batch_requests".to_string()
+    } else {
+        format!("batch_requests {}", config.bridge)
+    };
+    health::register_loop(loop_name.clone(), BATCH_REQUEST_LOOP_SPEED);
+    loop {
+        let (_, _) = tokio::join!(
+            request_batches(
+                cosmos_key.clone(),
+                &contact,
+                eth_client.clone(),
+                &mut grpc_client,
+                &mut fee_manager,
+                &config,
+                &mut last_requests,
+            ),
+            tokio::time::sleep(BATCH_REQUEST_LOOP_SPEED)
+        );
+        health::loop_ticked(&loop_name);
+    }
+}
+
+/// Reads the module-wide fees per denom, then requests a batch for every token where the fees
+/// would cover relaying it and no request was sent for that denom recently
+#[allow(clippy::too_many_arguments)]
+pub async fn request_batches<S: Signer + 'static, CS: CosmosSigner>(
+    cosmos_key: CS,
+    contact: &Contact,
+    eth_client: EthClient<S>,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    fee_manager: &mut FeeManager,
+    config: &BatchRequestConfig,
+    last_requests: &mut HashMap<String, Instant>,
+) {
+    let fees_by_denom = match get_batch_tx_fees(grpc_client).await {
+        Ok(fees) => fees,
+        Err(e) => {
+            error!("Could not get batch fees: {:?}", e);
+            return;
+        }
+    };
+    if fees_by_denom.is_empty() {
+        debug!("No fees waiting to be batched, not requesting any batches");
+        return;
+    }
+
+    let latest_batch_fees = match get_latest_transaction_batches(grpc_client).await {
+        Ok(batches) => latest_batch_fees(&batches),
+        Err(e) => {
+            error!("Could not get pending batches: {:?}", e);
+            return;
+        }
+    };
+
+    let gas_price = match get_gas_price(eth_client.clone()).await {
+        Ok(gas_price) => gas_price,
+        Err(e) => {
+            error!("Could not get Ethereum gas price: {:?}", e);
+            return;
+        }
+    };
+    let cost = GasCost {
+        gas: config.estimated_batch_gas,
+        gas_price,
+    };
+
+    for (denom, amount) in fees_by_denom {
+        if let Some(last_request) = last_requests.get(&denom) {
+            if last_request.elapsed() < config.min_request_interval {
+                debug!("Batch for {} requested recently, waiting", denom);
+                continue;
+            }
+        }
+
+        let token_contract: EthAddress = match get_denom_erc20(grpc_client, denom.clone()).await {
+            Ok(res) => match res.erc20.parse() {
+                Ok(token_contract) => token_contract,
+                Err(e) => {
+                    error!("Invalid ERC20 address {} for {}: {:?}", res.erc20, denom, e);
+                    continue;
+                }
+            },
+            Err(e) => {
+                error!("Could not get ERC20 for {}: {:?}", denom, e);
+                continue;
+            }
+        };
+
+        let latest_batch_fee = latest_batch_fees.get(&token_contract).copied();
+        if !exceeds_latest_batch(amount, latest_batch_fee) {
+            debug!(
+                "The latest pending batch for {} already carries {} in fees, the module reports {}",
+                denom,
+                latest_batch_fee.unwrap_or_default(),
+                amount
+            );
+            continue;
+        }
+
+        let fee = Erc20Token {
+            amount,
+            token_contract_address: token_contract,
+        };
+        if !fee_manager.is_batch_profitable(&cost, &fee).await {
+            debug!(
+                "Fees of {} {} are not enough to cover a batch, waiting",
+                amount, denom
+            );
+            continue;
+        }
+
+        info!("Requesting a batch for {} with {} in fees", denom, amount);
+        let res = send_request_batch_tx(
+            cosmos_key.clone(),
+            config.cosmos_granter.clone(),
+            denom.clone(),
+            config.gas_price.clone(),
+            config.gas_limit,
+            contact,
+            config.gas_adjustment,
+        )
+        .await;
+        match res {
+            Ok(res) => info!("Requested batch for {} with txid {}", denom, res.txhash),
+            Err(e) => error!("Failed to request batch for {}: {:?}", denom, e),
+        }
+        // rate limit failed requests as well, they would most likely fail again
+        last_requests.insert(denom, Instant::now());
+    }
+}
+
+/// The total fee of the most recent pending batch of each token
+fn latest_batch_fees(batches: &[TransactionBatch]) -> HashMap<EthAddress, U256> {
+    let mut latest: HashMap<EthAddress, &TransactionBatch> = HashMap::new();
+    for batch in batches {
+        let entry = latest.entry(batch.token_contract).or_insert(batch);
+        if batch.nonce > entry.nonce {
+            *entry = batch;
+        }
+    }
+    latest
+        .into_iter()
+        .map(|(token, batch)| (token, batch.total_fee.amount))
+        .collect()
+}
+
+/// The chain will not build a new batch unless it carries more fees than the latest
+/// pending batch for the same token, so don't waste Cosmos fees asking for it
+fn exceeds_latest_batch(unbatched_fees: U256, latest_batch_fee: Option<U256>) -> bool {
+    match latest_batch_fee {
+        Some(latest_batch_fee) => unbatched_fees > latest_batch_fee,
+        None => true,
+    }
+}
+
+#[test]
+fn test_latest_batch_fees() {
+    let token_a: EthAddress = "0x0000000000000000000000000000000000000001"
+        .parse()
+        .unwrap();
+    let token_b: EthAddress = "0x0000000000000000000000000000000000000002"
+        .parse()
+        .unwrap();
+    let batch = |token_contract, nonce, fee: u64| TransactionBatch {
+        nonce,
+        batch_timeout: 0,
+        transactions: Vec::new(),
+        total_fee: Erc20Token {
+            amount: fee.into(),
+            token_contract_address: token_contract,
+        },
+        token_contract,
+    };
+
+    let fees = latest_batch_fees(&[
+        batch(token_a, 3, 500),
+        batch(token_a, 7, 200),
+        batch(token_a, 5, 900),
+        batch(token_b, 1, 50),
+    ]);
+    // the latest batch, not the sum or the largest of the pending ones
+    assert_eq!(fees[&token_a], 200u64.into());
+    assert_eq!(fees[&token_b], 50u64.into());
+    assert_eq!(fees.len(), 2);
+}
+
+#[test]
+fn test_exceeds_latest_batch() {
+    assert!(exceeds_latest_batch(1u64.into(), None));
+    assert!(exceeds_latest_batch(201u64.into(), Some(200u64.into())));
+    assert!(!exceeds_latest_batch(200u64.into(), Some(200u64.into())));
+    assert!(!exceeds_latest_batch(100u64.into(), Some(200u64.into())));
+}
@@ -0,0 +1,308 @@
+//! Spend limits for the relayer, so that a misconfigured gas multiplier or a gas price spike can't drain
+//! the relayer's ETH. Spending is tracked per transaction type over rolling windows and persisted to disk
+//! so that restarting the relayer does not reset the budget.
+
+use crate::metrics;
+use ethereum_gravity::utils::{receipt_cost, GasCost, RelayOutcome};
+use ethers::types::U256;
+use gravity_utils::error::GravityError;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HOUR_SECS: u64 = 60 * 60;
+const DAY_SECS: u64 = 24 * HOUR_SECS;
+
+/// The kinds of transactions the relayer sends to the Gravity contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RelayType {
+    Valset,
+    Batch,
+    LogicCall,
+}
+
+impl RelayType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelayType::Valset => "valset",
+            RelayType::Batch => "batch",
+            RelayType::LogicCall => "logic_call",
+        }
+    }
+}
+
+impl fmt::Display for RelayType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for RelayType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "valset" => Ok(RelayType::Valset),
+            "batch" => Ok(RelayType::Batch),
+            "logic_call" => Ok(RelayType::LogicCall),
+            _ => Err(format!("Unknown relay type {}", s)),
+        }
+    }
+}
+
+/// Caps on what the relayer may spend, in wei. Unset caps are not enforced, the
+/// hourly and daily caps apply to each transaction type separately.
+#[derive(Debug, Clone, Default)]
+pub struct SpendLimits {
+    pub max_gas_price: Option<U256>,
+    pub max_tx_cost: Option<U256>,
+    pub max_hourly_spend: Option<U256>,
+    pub max_daily_spend: Option<U256>,
+    /// where spending is persisted, if unset the budget resets on restart
+    pub state_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SpendRecord {
+    relay_type: RelayType,
+    timestamp: u64,
+    #[serde(with = "gravity_utils::types::serialization::u256")]
+    amount: U256,
+}
+
+pub struct RelayerBudget {
+    /// the bridge this budget is for, used to label metrics
+    bridge: String,
+    limits: SpendLimits,
+    records: Vec<SpendRecord>,
+}
+
+impl RelayerBudget {
+    pub fn new(bridge: String, limits: SpendLimits) -> Self {
+        let mut records: Vec<SpendRecord> = Vec::new();
+        if let Some(path) = &limits.state_path {
+            match std::fs::read_to_string(path) {
+                Ok(state) => match serde_json::from_str(&state) {
+                    Ok(state) => records = state,
+                    Err(e) => error!("Could not parse relayer budget state {:?}: {}", path, e),
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => error!("Could not read relayer budget state {:?}: {}", path, e),
+            }
+        }
+
+        let mut budget = RelayerBudget {
+            bridge,
+            limits,
+            records,
+        };
+        budget.prune(now());
+        budget.update_metrics();
+        budget
+    }
+
+    /// Checks that sending a transaction of the given type and cost would stay within
+    /// every cap, returning the reason it should be deferred otherwise
+    pub fn check(&self, relay_type: RelayType, cost: &GasCost) -> Result<(), String> {
+        let res = self.check_limits(relay_type, cost);
+        if res.is_err() {
+            metrics::inc_relayer_deferred_txs(&self.bridge, relay_type);
+        }
+        res
+    }
+
+    fn check_limits(&self, relay_type: RelayType, cost: &GasCost) -> Result<(), String> {
+        let total = cost.get_total();
+        if let Some(max_gas_price) = self.limits.max_gas_price {
+            if cost.gas_price > max_gas_price {
+                return Err(format!(
+                    "gas price {} is above the maximum of {}",
+                    cost.gas_price, max_gas_price
+                ));
+            }
+        }
+        if let Some(max_tx_cost) = self.limits.max_tx_cost {
+            if total > max_tx_cost {
+                return Err(format!(
+                    "cost {} is above the per transaction maximum of {}",
+                    total, max_tx_cost
+                ));
+            }
+        }
+
+        let now = now();
+        let windows = [
+            ("hourly", HOUR_SECS, self.limits.max_hourly_spend),
+            ("daily", DAY_SECS, self.limits.max_daily_spend),
+        ];
+        for (name, window, limit) in windows.iter() {
+            if let Some(limit) = limit {
+                let spent = self.spent_since(relay_type, now.saturating_sub(*window));
+                if spent.saturating_add(total) > *limit {
+                    return Err(format!(
+                        "{} {} spend of {} plus cost {} is above the maximum of {}",
+                        name, relay_type, spent, total, limit
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records what a relay handed to one of the send functions spent. Mined and reverted transactions
+    /// are charged what their receipt says, `max_cost` is charged for a transaction that was sent but
+    /// got no receipt as it may still be mined, and nothing is charged when nothing was sent.
+    pub fn record_relay(
+        &mut self,
+        relay_type: RelayType,
+        result: &Result<RelayOutcome, GravityError>,
+        max_cost: U256,
+    ) {
+        let receipt = match result {
+            Ok(RelayOutcome::NotSent) => return,
+            Ok(RelayOutcome::Dropped(_)) => None,
+            Ok(RelayOutcome::Mined(receipt)) => Some(receipt),
+            Err(GravityError::TransactionReverted(reverted)) => Some(&reverted.receipt),
+            Err(_) => return,
+        };
+        let spent = receipt.and_then(receipt_cost).unwrap_or(max_cost);
+        self.record(relay_type, spent);
+    }
+
+    /// Records ETH spent on a transaction of the given type
+    pub fn record(&mut self, relay_type: RelayType, amount: U256) {
+        let now = now();
+        self.records.push(SpendRecord {
+            relay_type,
+            timestamp: now,
+            amount,
+        });
+        self.prune(now);
+        self.update_metrics();
+        self.persist();
+    }
+
+    fn spent_since(&self, relay_type: RelayType, since: u64) -> U256 {
+        self.records
+            .iter()
+            .filter(|record| record.relay_type == relay_type && record.timestamp >= since)
+            .fold(U256::zero(), |total, record| {
+                total.saturating_add(record.amount)
+            })
+    }
+
+    fn prune(&mut self, now: u64) {
+        self.records
+            .retain(|record| record.timestamp >= now.saturating_sub(DAY_SECS));
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.limits.state_path {
+            let state = match serde_json::to_string(&self.records) {
+                Ok(state) => state,
+                Err(e) => {
+                    error!("Could not serialize relayer budget state: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = std::fs::write(path, state) {
+                error!("Could not write relayer budget state {:?}: {}", path, e);
+            }
+        }
+    }
+
+    fn update_metrics(&self) {
+        let now = now();
+        for relay_type in [RelayType::Valset, RelayType::Batch, RelayType::LogicCall] {
+            metrics::set_relayer_spend(
+                &self.bridge,
+                relay_type,
+                self.spent_since(relay_type, now.saturating_sub(HOUR_SECS)),
+                self.spent_since(relay_type, now.saturating_sub(DAY_SECS)),
+            );
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_relayer_budget() {
+    let mut budget = RelayerBudget::new(
+        "test".to_string(),
+        SpendLimits {
+            max_gas_price: Some(100u64.into()),
+            max_tx_cost: Some(10_000u64.into()),
+            max_hourly_spend: Some(15_000u64.into()),
+            max_daily_spend: Some(20_000u64.into()),
+            state_path: None,
+        },
+    );
+    let cost = |gas: u64, gas_price: u64| GasCost {
+        gas: gas.into(),
+        gas_price: gas_price.into(),
+    };
+
+    assert!(budget.check(RelayType::Batch, &cost(100, 100)).is_ok());
+    assert!(budget.check(RelayType::Batch, &cost(10, 101)).is_err());
+    assert!(budget.check(RelayType::Batch, &cost(101, 100)).is_err());
+
+    budget.record(RelayType::Batch, 10_000u64.into());
+    assert!(budget.check(RelayType::Batch, &cost(50, 100)).is_ok());
+    assert!(budget.check(RelayType::Batch, &cost(51, 100)).is_err());
+    // other transaction types have their own budget
+    assert!(budget.check(RelayType::Valset, &cost(100, 100)).is_ok());
+
+    budget.record(RelayType::Batch, 5_000u64.into());
+    assert!(budget.check(RelayType::Batch, &cost(1, 1)).is_err());
+}
+
+#[test]
+fn test_relayer_budget_records_what_was_spent() {
+    let mut budget = RelayerBudget::new("test".to_string(), SpendLimits::default());
+    let max_cost = U256::from(1_000u64);
+    let receipt = ethers::types::TransactionReceipt {
+        gas_used: Some(30u64.into()),
+        effective_gas_price: Some(10u64.into()),
+        ..Default::default()
+    };
+    let spent = |budget: &RelayerBudget| budget.spent_since(RelayType::Batch, 0);
+
+    budget.record_relay(RelayType::Batch, &Ok(RelayOutcome::NotSent), max_cost);
+    assert_eq!(spent(&budget), 0u64.into());
+    budget.record_relay(RelayType::Batch, &Err(GravityError::TimeoutError), max_cost);
+    assert_eq!(spent(&budget), 0u64.into());
+
+    budget.record_relay(
+        RelayType::Batch,
+        &Ok(RelayOutcome::Mined(receipt.clone())),
+        max_cost,
+    );
+    assert_eq!(spent(&budget), 300u64.into());
+    let reverted = gravity_utils::error::RevertedTransaction {
+        receipt,
+        contract_error: None,
+    };
+    budget.record_relay(
+        RelayType::Batch,
+        &Err(GravityError::TransactionReverted(Box::new(reverted))),
+        max_cost,
+    );
+    assert_eq!(spent(&budget), 600u64.into());
+
+    // sent without a receipt, so the worst case is charged
+    budget.record_relay(
+        RelayType::Batch,
+        &Ok(RelayOutcome::Dropped(Default::default())),
+        max_cost,
+    );
+    assert_eq!(spent(&budget), 1_600u64.into());
+}
@@ -3,7 +3,7 @@ use ethers::prelude::*;
 use ethers::types::Address as EthAddress;
 use gravity_utils::ethereum::format_eth_address;
 use gravity_utils::types::config::RelayerMode;
-use gravity_utils::types::Erc20Token;
+use gravity_utils::types::{Erc20Token, LogicCall};
 use reqwest::Client;
 use serde_json::json;
 use std::collections::HashMap;
@@ -17,6 +17,7 @@ pub struct FeeManager {
     token_price_map: HashMap<String, String>,
     relayer_api_url: String,
     next_batch_send_time: HashMap<EthAddress, Instant>,
+    next_logic_call_send_time: HashMap<Vec<u8>, Instant>,
     mode: RelayerMode,
 }
 
@@ -27,39 +28,64 @@ struct ApiResponse {
     reason_type: u32,
 }
 
+#[derive(serde::Deserialize, Debug)]
+struct ApiFeesValueResponse {
+    /// the value of the fees in wei, as a decimal string
+    value: String,
+}
+
 impl FeeManager {
     pub async fn new_fee_manager(mode: RelayerMode) -> Result<FeeManager, ()> {
+        Self::new_fee_manager_with_sources(mode, None, None).await
+    }
+
+    /// Creates a fee manager reading token prices from `token_prices_path` in File mode and querying
+    /// `relayer_api_url` in Api mode, unset sources fall back to the TOKEN_PRICES_JSON and
+    /// RELAYER_API_URL environment variables. Lets each bridge of a multi-bridge relayer price fees separately.
+    pub async fn new_fee_manager_with_sources(
+        mode: RelayerMode,
+        token_prices_path: Option<String>,
+        relayer_api_url: Option<String>,
+    ) -> Result<FeeManager, ()> {
         let mut fm = Self {
             token_price_map: Default::default(),
             relayer_api_url: String::default(),
             next_batch_send_time: HashMap::new(),
+            next_logic_call_send_time: HashMap::new(),
             mode,
         };
-        fm.init().await?;
+        fm.init(token_prices_path, relayer_api_url).await?;
         Ok(fm)
     }
 
-    async fn init(&mut self) -> Result<(), ()> {
+    async fn init(
+        &mut self,
+        token_prices_path: Option<String>,
+        relayer_api_url: Option<String>,
+    ) -> Result<(), ()> {
         match self.mode {
             RelayerMode::Api => {
-                self.init_with_api();
+                self.init_with_api(relayer_api_url);
             }
             RelayerMode::File => {
-                self.init_with_file().await?;
+                self.init_with_file(token_prices_path).await?;
             }
             RelayerMode::AlwaysRelay => {}
         }
         Ok(())
     }
 
-    fn init_with_api(&mut self) {
-        self.relayer_api_url =
-            std::env::var("RELAYER_API_URL").unwrap_or_else(|_| DEFAULT_RELAYER_API_URL.to_owned());
+    fn init_with_api(&mut self, relayer_api_url: Option<String>) {
+        self.relayer_api_url = relayer_api_url.unwrap_or_else(|| {
+            std::env::var("RELAYER_API_URL").unwrap_or_else(|_| DEFAULT_RELAYER_API_URL.to_owned())
+        });
     }
 
-    async fn init_with_file(&mut self) -> Result<(), ()> {
-        let config_file_path = std::env::var("TOKEN_PRICES_JSON")
-            .unwrap_or_else(|_| DEFAULT_TOKEN_PRICES_PATH.to_owned());
+    async fn init_with_file(&mut self, token_prices_path: Option<String>) -> Result<(), ()> {
+        let config_file_path = token_prices_path.unwrap_or_else(|| {
+            std::env::var("TOKEN_PRICES_JSON")
+                .unwrap_or_else(|_| DEFAULT_TOKEN_PRICES_PATH.to_owned())
+        });
 
         let config_str = tokio::fs::read_to_string(config_file_path)
             .await
@@ -117,46 +143,184 @@ impl FeeManager {
                 batch_value >= estimated_fee
             }
             RelayerMode::Api => {
-                let body = json!({
-                    "batchFee": {
-                        "amount": batch_fee.amount.to_string(),
-                        "tokenContractAddress": batch_fee.token_contract_address
-                    },
-                    "estimatedCost": {
-                        "gas": estimated_cost.gas.to_string(),
-                        "gasPrice": estimated_cost.gas_price.to_string()
-                    }}
-                );
-
-                return match Client::new()
-                    .post(self.relayer_api_url.as_str())
-                    .json(&body)
-                    .send()
+                match self
+                    .query_can_send("batchFee", fee_json(batch_fee), estimated_cost)
                     .await
                 {
-                    Ok(resp) => match resp.json().await {
-                        Ok(json) => {
-                            let api_response: ApiResponse = json;
-                            return if api_response.can_send {
-                                true
-                            } else {
-                                // code 5 means that it is not profitable but limit has not been
-                                // exceeded or no addresses are blacklisted
-                                // in that case we check if we should send at non profitable cost
-                                api_response.reason_type == 5
-                                    && self.should_send_at_non_profitable_cost(contract_address)
-                            };
-                        }
-                        Err(err) => {
-                            error!("error deserializing response from relayer api: {}", err);
-                            false
-                        }
-                    },
-                    Err(err) => {
-                        error!("error getting response from relayer api: {}", err);
-                        false
+                    Some(api_response) => {
+                        api_response.can_send
+                            // code 5 means that it is not profitable but limit has not been
+                            // exceeded or no addresses are blacklisted
+                            // in that case we check if we should send at non profitable cost
+                            || (api_response.reason_type == 5
+                                && self.should_send_at_non_profitable_cost(contract_address))
                     }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    // Only checks if a batch carrying the given fee would be profitable to relay, without
+    // considering how long it has been waiting. Used to decide when to request a new batch.
+    pub async fn is_batch_profitable(
+        &mut self,
+        estimated_cost: &GasCost,
+        batch_fee: &Erc20Token,
+    ) -> bool {
+        match self.mode {
+            RelayerMode::AlwaysRelay => true,
+            RelayerMode::File => {
+                let batch_value = match self.get_fees_value(std::slice::from_ref(batch_fee)).await {
+                    Ok(batch_value) => batch_value,
+                    Err(_) => return false,
                 };
+                batch_value >= estimated_cost.get_total()
+            }
+            RelayerMode::Api => {
+                match self
+                    .query_can_send("batchFee", fee_json(batch_fee), estimated_cost)
+                    .await
+                {
+                    Some(api_response) => api_response.can_send,
+                    None => false,
+                }
+            }
+        }
+    }
+
+    // A logic call can be send either if
+    // - Mode is AlwaysRelay
+    // - Mode is either API or File and the sum of all of its fees has a profitable cost
+    // - Mode is either API or File and the logic call has been waiting to be sent more than GRAVITY_BATCH_SENDING_SECS secs
+    pub async fn can_send_logic_call(
+        &mut self,
+        estimated_cost: &GasCost,
+        logic_call: &LogicCall,
+    ) -> bool {
+        match self.mode {
+            RelayerMode::AlwaysRelay => true,
+            RelayerMode::File => {
+                if self.should_send_logic_call_at_non_profitable_cost(&logic_call.invalidation_id) {
+                    return true;
+                }
+                let fees_value = match self.get_fees_value(&logic_call.fees).await {
+                    Ok(fees_value) => fees_value,
+                    Err(_) => return false,
+                };
+
+                let estimated_fee = estimated_cost.get_total();
+                info!(
+                    "estimate cost is {}, logic call value is {}",
+                    estimated_fee, fees_value
+                );
+                fees_value >= estimated_fee
+            }
+            RelayerMode::Api => {
+                let fees = logic_call.fees.iter().map(fee_json).collect();
+                match self
+                    .query_can_send("logicCallFees", fees, estimated_cost)
+                    .await
+                {
+                    Some(api_response) => {
+                        api_response.can_send
+                            || (api_response.reason_type == 5
+                                && self.should_send_logic_call_at_non_profitable_cost(
+                                    &logic_call.invalidation_id,
+                                ))
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    // Only checks if a logic call is profitable to relay, without considering how long it
+    // has been waiting.
+    pub async fn is_logic_call_profitable(
+        &mut self,
+        estimated_cost: &GasCost,
+        logic_call: &LogicCall,
+    ) -> bool {
+        match self.mode {
+            RelayerMode::AlwaysRelay => true,
+            RelayerMode::File => match self.get_fees_value(&logic_call.fees).await {
+                Ok(fees_value) => fees_value >= estimated_cost.get_total(),
+                Err(_) => false,
+            },
+            RelayerMode::Api => {
+                let fees = logic_call.fees.iter().map(fee_json).collect();
+                match self
+                    .query_can_send("logicCallFees", fees, estimated_cost)
+                    .await
+                {
+                    Some(api_response) => api_response.can_send,
+                    None => false,
+                }
+            }
+        }
+    }
+
+    pub fn mode(&self) -> RelayerMode {
+        self.mode
+    }
+
+    // The value of the given fees in wei, according to the token price map in File mode or
+    // as priced by the relayer API in Api mode. AlwaysRelay mode knows no token prices.
+    pub async fn fees_value(&mut self, fees: &[Erc20Token]) -> Option<U256> {
+        match self.mode {
+            RelayerMode::AlwaysRelay => None,
+            RelayerMode::File => self.get_fees_value(fees).await.ok(),
+            RelayerMode::Api => {
+                let fees: Vec<_> = fees.iter().map(fee_json).collect();
+                let body = json!({ "feesValue": fees });
+
+                let response: ApiFeesValueResponse = self.query_api(&body).await?;
+                U256::from_dec_str(&response.value)
+                    .map_err(|e| error!("error parsing fees value from relayer api: {}", e))
+                    .ok()
+            }
+        }
+    }
+
+    // Asks the relayer API if a relay paying `fees`, sent under the `fee_field` key, is worth its
+    // estimated cost
+    async fn query_can_send(
+        &self,
+        fee_field: &str,
+        fees: serde_json::Value,
+        estimated_cost: &GasCost,
+    ) -> Option<ApiResponse> {
+        let body = json!({
+            fee_field: fees,
+            "estimatedCost": {
+                "gas": estimated_cost.gas.to_string(),
+                "gasPrice": estimated_cost.gas_price.to_string()
+            }
+        });
+        self.query_api(&body).await
+    }
+
+    async fn query_api<T: serde::de::DeserializeOwned>(
+        &self,
+        body: &serde_json::Value,
+    ) -> Option<T> {
+        match Client::new()
+            .post(self.relayer_api_url.as_str())
+            .json(body)
+            .send()
+            .await
+        {
+            Ok(resp) => match resp.json().await {
+                Ok(api_response) => Some(api_response),
+                Err(err) => {
+                    error!("error deserializing response from relayer api: {}", err);
+                    None
+                }
+            },
+            Err(err) => {
+                error!("error getting response from relayer api: {}", err);
+                None
             }
         }
     }
@@ -169,17 +333,51 @@ impl FeeManager {
         true
     }
 
+    fn should_send_logic_call_at_non_profitable_cost(&mut self, invalidation_id: &[u8]) -> bool {
+        match self.next_logic_call_send_time.get(invalidation_id) {
+            Some(time) => return *time < Instant::now(),
+            None => self.update_next_logic_call_send_time(invalidation_id),
+        }
+        true
+    }
+
     pub(crate) fn update_next_batch_send_time(&mut self, contract_address: EthAddress) {
         if self.mode == RelayerMode::AlwaysRelay {
             return;
         }
 
-        let timeout_duration = std::env::var("GRAVITY_BATCH_SENDING_SECS")
-            .map(|value| Duration::from_secs(value.parse().unwrap()))
-            .unwrap_or_else(|_| Duration::from_secs(3600));
-
         self.next_batch_send_time
-            .insert(contract_address, Instant::now() + timeout_duration);
+            .insert(contract_address, Instant::now() + non_profitable_timeout());
+    }
+
+    pub(crate) fn update_next_logic_call_send_time(&mut self, invalidation_id: &[u8]) {
+        if self.mode == RelayerMode::AlwaysRelay {
+            return;
+        }
+
+        self.next_logic_call_send_time.insert(
+            invalidation_id.to_vec(),
+            Instant::now() + non_profitable_timeout(),
+        );
+    }
+
+    // Sums the value of a list of fees, possibly in several different tokens, using the
+    // token price map. Every token must have a price, otherwise we can't tell if it's profitable.
+    async fn get_fees_value(&mut self, fees: &[Erc20Token]) -> Result<U256, ()> {
+        let mut total = U256::zero();
+        for fee in fees {
+            let token_price = self.get_token_price(&fee.token_contract_address).await?;
+            let fee_value = fee.amount.checked_mul(token_price).ok_or_else(|| {
+                error!(
+                    "fee value exceeded for token {}",
+                    fee.token_contract_address
+                );
+            })?;
+            total = total.checked_add(fee_value).ok_or_else(|| {
+                error!("total fee value exceeded");
+            })?;
+        }
+        Ok(total)
     }
 
     async fn get_token_price(&mut self, contract_address: &EthAddress) -> Result<U256, ()> {
@@ -197,3 +395,16 @@ impl FeeManager {
         };
     }
 }
+
+fn fee_json(fee: &Erc20Token) -> serde_json::Value {
+    json!({
+        "amount": fee.amount.to_string(),
+        "tokenContractAddress": fee.token_contract_address
+    })
+}
+
+fn non_profitable_timeout() -> Duration {
+    std::env::var("GRAVITY_BATCH_SENDING_SECS")
+        .map(|value| Duration::from_secs(value.parse().unwrap()))
+        .unwrap_or_else(|_| Duration::from_secs(3600))
+}
@@ -8,6 +8,81 @@ use gravity_utils::{error::GravityError, ethereum::downcast_to_u64, types::Valse
 use std::{panic, result::Result};
 use tonic::transport::Channel;
 
+/// Keeps the latest valset on the Gravity contract between relayer loops, along with the block it was
+/// last confirmed at, so that only blocks produced since then need to be scanned for ValsetUpdatedEvents.
+/// The contract's last valset nonce, read by the caller, is checked every time, if the cache can't be brought back in line
+/// with it we fall back to the full search in `find_latest_valset`.
+#[derive(Debug, Clone, Default)]
+pub struct ValsetTracker {
+    latest: Option<(Valset, U64)>,
+}
+
+impl ValsetTracker {
+    pub async fn latest_valset<S: Signer + 'static>(
+        &mut self,
+        grpc_client: &mut GravityQueryClient<Channel>,
+        gravity_contract_address: EthAddress,
+        eth_client: EthClient<S>,
+        blocks_to_search: u64,
+        contract_nonce: u64,
+    ) -> Result<Valset, GravityError> {
+        let current_block = eth_client.get_block_number().await?;
+
+        // the cache is only replaced once a refresh succeeds, an error leaves it for the next loop
+        if let Some((cached_valset, searched_block)) = self.latest.clone() {
+            if contract_nonce == cached_valset.nonce {
+                self.latest = Some((cached_valset.clone(), current_block));
+                return Ok(cached_valset);
+            }
+
+            let mut latest_update = None;
+            for (start_block, end_block) in block_ranges_newest_first(
+                searched_block + U64::one(),
+                current_block,
+                blocks_to_search,
+            ) {
+                latest_update = search_valset_updates(
+                    grpc_client,
+                    gravity_contract_address,
+                    eth_client.clone(),
+                    start_block,
+                    end_block,
+                )
+                .await?;
+                if latest_update.is_some() {
+                    break;
+                }
+            }
+
+            if let Some(valset) = latest_update {
+                if valset.nonce == contract_nonce {
+                    debug!(
+                        "Valset updated from {} to {} since block {}",
+                        cached_valset.nonce, valset.nonce, searched_block
+                    );
+                    self.latest = Some((valset.clone(), current_block));
+                    return Ok(valset);
+                }
+            }
+
+            warn!(
+                "Cached valset {} does not match the contract's last valset nonce {}, searching the full history",
+                cached_valset.nonce, contract_nonce
+            );
+        }
+
+        let valset = find_latest_valset(
+            grpc_client,
+            gravity_contract_address,
+            eth_client,
+            blocks_to_search,
+        )
+        .await?;
+        self.latest = Some((valset.clone(), current_block));
+        Ok(valset)
+    }
+}
+
 /// This function finds the latest valset on the Gravity contract by looking back through the event
 /// history and finding the most recent ValsetUpdatedEvent. Most of the time this will be very fast
 /// as the latest update will be in recent blockchain history and the search moves from the present
@@ -19,55 +94,104 @@ pub async fn find_latest_valset<S: Signer + 'static>(
     eth_client: EthClient<S>,
     blocks_to_search: u64,
 ) -> Result<Valset, GravityError> {
-    let mut filter = Filter::new()
-        .address(ValueOrArray::Value(gravity_contract_address))
-        .event(&ValsetUpdatedEventFilter::abi_signature());
-    let mut end_filter_block = eth_client.get_block_number().await?;
+    let current_block = eth_client.get_block_number().await?;
 
-    while end_filter_block > 0u64.into() {
+    for (start_filter_block, end_filter_block) in
+        block_ranges_newest_first(U64::zero(), current_block, blocks_to_search)
+    {
         debug!("About to submit a Valset or Batch, looking back into the history to find the last Valset Update, on block {}", end_filter_block);
 
-        let start_filter_block = end_filter_block.saturating_sub(blocks_to_search.into());
-        filter = filter.select(start_filter_block..end_filter_block);
-
-        let mut filtered_logged_events = eth_client.get_logs(&filter).await?;
-        filtered_logged_events.reverse(); // we'll process these in reverse order to start from the most recent and work backwards
-
-        // TODO(bolten): the original logic only checked one valset event, even if there may have been multiple within the
-        // filtered blockspace...need more clarity on how severe an error it is if one of these events is malformed, and if
-        // we should return early with an error or just log it the way the previous version did
-        for logged_event in filtered_logged_events {
-            debug!("Found event {:?}", logged_event);
-
-            match ValsetUpdatedEvent::from_log(&logged_event) {
-                Ok(valset_updated_event) => {
-                    let downcast_nonce = downcast_to_u64(valset_updated_event.valset_nonce);
-                    if downcast_nonce.is_none() {
-                        error!(
-                            "ValsetUpdatedEvent has nonce larger than u64: {:?}",
-                            valset_updated_event
-                        );
-                        continue;
-                    }
-
-                    let latest_eth_valset = Valset {
-                        nonce: downcast_nonce.unwrap(),
-                        members: valset_updated_event.members,
-                    };
-                    let cosmos_chain_valset =
-                        cosmos_gravity::query::get_valset(grpc_client, latest_eth_valset.nonce)
-                            .await?;
-                    check_if_valsets_differ(cosmos_chain_valset, &latest_eth_valset)?;
-                    return Ok(latest_eth_valset);
+        if let Some(valset) = search_valset_updates(
+            grpc_client,
+            gravity_contract_address,
+            eth_client.clone(),
+            start_filter_block,
+            end_filter_block,
+        )
+        .await?
+        {
+            return Ok(valset);
+        }
+    }
+
+    panic!("Could not find the last validator set for contract {}, probably not a valid Gravity contract!", gravity_contract_address)
+}
+
+/// Splits the inclusive block range into ranges of at most `blocks_to_search` + 1 blocks, starting
+/// from the most recent so a search can stop at the first range with a match
+fn block_ranges_newest_first(
+    start_block: U64,
+    end_block: U64,
+    blocks_to_search: u64,
+) -> Vec<(U64, U64)> {
+    let mut ranges = Vec::new();
+    let mut end_filter_block = end_block;
+    while end_filter_block >= start_block && end_filter_block > U64::zero() {
+        let start_filter_block = end_filter_block
+            .saturating_sub(blocks_to_search.into())
+            .max(start_block);
+        ranges.push((start_filter_block, end_filter_block));
+        if start_filter_block == U64::zero() {
+            break;
+        }
+        end_filter_block = start_filter_block - U64::one(); // filter ranges are inclusive, avoid searching same block
+    }
+    ranges
+}
+
+/// Returns the most recent valset updated within the given (inclusive) block range, if any
+async fn search_valset_updates<S: Signer + 'static>(
+    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity_contract_address: EthAddress,
+    eth_client: EthClient<S>,
+    start_block: U64,
+    end_block: U64,
+) -> Result<Option<Valset>, GravityError> {
+    if start_block > end_block {
+        return Ok(None);
+    }
+
+    let filter = Filter::new()
+        .address(ValueOrArray::Value(gravity_contract_address))
+        .event(&ValsetUpdatedEventFilter::abi_signature())
+        .select(start_block..end_block);
+
+    let mut filtered_logged_events = eth_client.get_logs(&filter).await?;
+    filtered_logged_events.reverse(); // we'll process these in reverse order to start from the most recent and work backwards
+
+    // TODO(bolten): the original logic only checked one valset event, even if there may have been multiple within the
+    // filtered blockspace...need more clarity on how severe an error it is if one of these events is malformed, and if
+    // we should return early with an error or just log it the way the previous version did
+    for logged_event in filtered_logged_events {
+        debug!("Found event {:?}", logged_event);
+
+        match ValsetUpdatedEvent::from_log(&logged_event) {
+            Ok(valset_updated_event) => {
+                let downcast_nonce = downcast_to_u64(valset_updated_event.valset_nonce);
+                if downcast_nonce.is_none() {
+                    error!(
+                        "ValsetUpdatedEvent has nonce larger than u64: {:?}",
+                        valset_updated_event
+                    );
+                    continue;
                 }
-                Err(e) => error!("Got valset event that we can't parse {}", e),
+
+                let latest_eth_valset = Valset {
+                    nonce: downcast_nonce.unwrap(),
+                    members: valset_updated_event.members,
+                    reward_amount: valset_updated_event.reward_amount,
+                    reward_token: valset_updated_event.reward_token,
+                };
+                let cosmos_chain_valset =
+                    cosmos_gravity::query::get_valset(grpc_client, latest_eth_valset.nonce).await?;
+                check_if_valsets_differ(cosmos_chain_valset, &latest_eth_valset)?;
+                return Ok(Some(latest_eth_valset));
             }
+            Err(e) => error!("Got valset event that we can't parse {}", e),
         }
-
-        end_filter_block = start_filter_block.saturating_sub(1u64.into()); // filter ranges are inclusive, avoid searching same block
     }
 
-    panic!("Could not find the last validator set for contract {}, probably not a valid Gravity contract!", gravity_contract_address)
+    Ok(None)
 }
 
 /// This function exists to provide a warning if Cosmos and Ethereum have different validator sets
@@ -92,6 +216,13 @@ fn check_if_valsets_differ(
     }
     let cosmos_valset = cosmos_valset.unwrap();
     if cosmos_valset != *ethereum_valset {
+        if cosmos_valset.members == ethereum_valset.members {
+            warn!(
+                "Reward disagreement between Cosmos and Ethereum on Valset nonce {}",
+                ethereum_valset.nonce
+            );
+            return Ok(());
+        }
         let mut c_valset = cosmos_valset.members;
         let mut e_valset = ethereum_valset.members.clone();
         c_valset.sort();
@@ -108,3 +239,33 @@ fn check_if_valsets_differ(
 
     Ok(())
 }
+
+#[test]
+fn test_block_ranges_newest_first() {
+    // the full search covers everything down to the genesis block
+    assert_eq!(
+        block_ranges_newest_first(0u64.into(), 250u64.into(), 100),
+        vec![
+            (150u64.into(), 250u64.into()),
+            (49u64.into(), 149u64.into()),
+            (0u64.into(), 48u64.into()),
+        ]
+    );
+
+    // the incremental search only covers the blocks after the cached one, in chunks
+    assert_eq!(
+        block_ranges_newest_first(1001u64.into(), 1250u64.into(), 100),
+        vec![
+            (1150u64.into(), 1250u64.into()),
+            (1049u64.into(), 1149u64.into()),
+            (1001u64.into(), 1048u64.into()),
+        ]
+    );
+    assert_eq!(
+        block_ranges_newest_first(1001u64.into(), 1010u64.into(), 100),
+        vec![(1001u64.into(), 1010u64.into())]
+    );
+
+    // nothing to search when no block was produced since the cached one
+    assert!(block_ranges_newest_first(1001u64.into(), 1000u64.into(), 100).is_empty());
+}
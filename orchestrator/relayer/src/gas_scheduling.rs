@@ -0,0 +1,153 @@
+//! A batch can be relayed at any point before its timeout block, so a batch with plenty of time left doesn't
+//! need to be submitted into a gas price spike. This module decides if a batch should be submitted now or wait
+//! for cheaper gas, accepting higher prices as the timeout approaches so that no batch times out from waiting.
+
+use ethereum_gravity::types::EthClient;
+use ethers::prelude::*;
+
+/// How many recent blocks of base fees the schedule looks at
+pub const FEE_HISTORY_BLOCKS: u64 = 20;
+
+/// When to defer batches while gas is expensive, scheduling is disabled without a target gas price
+#[derive(Debug, Clone)]
+pub struct GasSchedule {
+    /// the gas price, in wei, batches wait for while they have time left
+    pub target_gas_price: Option<U256>,
+    /// batches further than this many blocks from their timeout only accept the target price
+    pub patience_blocks: u64,
+    /// batches this close to their timeout are submitted at any gas price
+    pub urgent_blocks: u64,
+    /// the price accepted just before the urgent window, as a percentage of the target
+    pub max_escalation_percent: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        GasSchedule {
+            target_gas_price: None,
+            patience_blocks: 1000,
+            urgent_blocks: 100,
+            max_escalation_percent: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleDecision {
+    Submit,
+    Defer(String),
+}
+
+impl GasSchedule {
+    pub fn enabled(&self) -> bool {
+        self.target_gas_price.is_some()
+    }
+
+    /// The highest gas price accepted for a batch `blocks_remaining` blocks from its timeout, None
+    /// if any price is accepted. The price escalates linearly from the target at `patience_blocks`
+    /// to `max_escalation_percent` of the target at `urgent_blocks`.
+    pub fn acceptable_gas_price(&self, blocks_remaining: u64) -> Option<U256> {
+        let target = self.target_gas_price?;
+        if blocks_remaining <= self.urgent_blocks {
+            return None;
+        }
+        if blocks_remaining >= self.patience_blocks {
+            return Some(target);
+        }
+
+        let window = self.patience_blocks - self.urgent_blocks;
+        let waited = self.patience_blocks - blocks_remaining;
+        let extra_percent = self.max_escalation_percent.saturating_sub(100) * waited / window;
+        Some(target.saturating_mul((100 + extra_percent).into()) / 100)
+    }
+
+    /// Decides if a batch should be submitted at `gas_price` given the recent base fees, oldest first.
+    /// Batches past `patience_blocks` stop waiting out gas that has been above their acceptable price
+    /// for the whole fee history, a sustained rise is unlikely to pass before the timeout.
+    pub fn schedule_batch(
+        &self,
+        blocks_remaining: u64,
+        gas_price: U256,
+        recent_base_fees: &[U256],
+    ) -> ScheduleDecision {
+        let acceptable = match self.acceptable_gas_price(blocks_remaining) {
+            Some(acceptable) => acceptable,
+            None => return ScheduleDecision::Submit,
+        };
+        if gas_price <= acceptable {
+            return ScheduleDecision::Submit;
+        }
+
+        let sustained = !recent_base_fees.is_empty()
+            && recent_base_fees
+                .iter()
+                .all(|base_fee| *base_fee > acceptable);
+        if sustained && blocks_remaining < self.patience_blocks {
+            return ScheduleDecision::Submit;
+        }
+
+        ScheduleDecision::Defer(format!(
+            "gas price {} is above the accepted {} with {} blocks until the batch times out",
+            gas_price, acceptable, blocks_remaining
+        ))
+    }
+}
+
+/// Gets the base fees of the latest blocks, oldest first. Empty if the node doesn't support
+/// fee history, the schedule then only looks at the current gas price.
+pub async fn get_recent_base_fees<S: Signer + 'static>(eth_client: EthClient<S>) -> Vec<U256> {
+    match eth_client
+        .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[])
+        .await
+    {
+        Ok(history) => history.base_fee_per_gas,
+        Err(e) => {
+            debug!("Could not get fee history: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[test]
+fn test_schedule_batch() {
+    let schedule = GasSchedule {
+        target_gas_price: Some(100u64.into()),
+        patience_blocks: 1000,
+        urgent_blocks: 100,
+        max_escalation_percent: 300,
+    };
+    let fees = |fees: &[u64]| -> Vec<U256> { fees.iter().map(|fee| (*fee).into()).collect() };
+
+    assert_eq!(schedule.acceptable_gas_price(5000), Some(100u64.into()));
+    assert_eq!(schedule.acceptable_gas_price(550), Some(200u64.into()));
+    assert_eq!(schedule.acceptable_gas_price(101), Some(299u64.into()));
+    assert_eq!(schedule.acceptable_gas_price(100), None);
+
+    assert_eq!(
+        schedule.schedule_batch(5000, 100u64.into(), &fees(&[50, 150])),
+        ScheduleDecision::Submit
+    );
+    assert!(matches!(
+        schedule.schedule_batch(5000, 150u64.into(), &fees(&[50, 150])),
+        ScheduleDecision::Defer(_)
+    ));
+    // plenty of slack, keep waiting even if gas has been high for a while
+    assert!(matches!(
+        schedule.schedule_batch(5000, 150u64.into(), &fees(&[140, 150])),
+        ScheduleDecision::Defer(_)
+    ));
+    // escalating, and gas has been above the accepted price for the whole history
+    assert_eq!(
+        schedule.schedule_batch(900, 150u64.into(), &fees(&[140, 150])),
+        ScheduleDecision::Submit
+    );
+    // never wait past the urgent window
+    assert_eq!(
+        schedule.schedule_batch(50, 10_000u64.into(), &fees(&[50])),
+        ScheduleDecision::Submit
+    );
+    assert_eq!(
+        GasSchedule::default().schedule_batch(5000, 10_000u64.into(), &[]),
+        ScheduleDecision::Submit
+    );
+}
@@ -1,8 +1,16 @@
 pub mod batch_relaying;
+pub mod batch_requesting;
+pub mod budget;
 pub mod fee_manager;
 pub mod find_latest_valset;
+pub mod gas_scheduling;
 pub mod logic_call_relaying;
 pub mod main_loop;
+pub mod metrics;
+pub mod pending_relays;
+pub mod relay_history;
+pub mod relay_plan;
+pub mod relay_skips;
 pub mod valset_relaying;
 
 #[macro_use]
@@ -1,34 +1,302 @@
+use crate::budget::{RelayType, RelayerBudget};
+use crate::fee_manager::FeeManager;
 use crate::main_loop::LOOP_SPEED;
+use crate::metrics::{self, skip_reason};
+use crate::pending_relays::PendingRelayWatcher;
+use crate::relay_history;
+use crate::relay_skips::{handle_estimate_error, handle_relay_error};
 use cosmos_gravity::query::{get_latest_logic_calls, get_logic_call_signatures};
 use ethereum_gravity::logic_call::LogicCallSkips;
-use ethereum_gravity::one_eth_f32;
-use ethereum_gravity::utils::handle_contract_error;
-use ethereum_gravity::{
-    logic_call::send_eth_logic_call, types::EthClient, utils::get_logic_call_nonce,
-};
+use ethereum_gravity::nonce_manager::AccountNonce;
+use ethereum_gravity::utils::{format_eth, ContractErrorAction};
+use ethereum_gravity::{logic_call::send_eth_logic_call, types::EthClient};
 use ethers::signers::Signer;
 use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
-use gravity_utils::ethereum::{bytes_to_hex_str, downcast_to_f32};
+use gravity_utils::error::GravityError;
+use gravity_utils::ethereum::bytes_to_hex_str;
 use gravity_utils::types::{LogicCallConfirmResponse, Valset};
 use gravity_utils::{message_signatures::encode_logic_call_confirm_hashed, types::LogicCall};
+use std::collections::HashMap;
 use std::time::Duration;
 use tonic::transport::Channel;
+use tracing::{field, info_span, Instrument};
+
+/// Restricts the logic calls this relayer is willing to submit by logic contract address
+/// and by invalidation id. Deny lists always win, an empty allow list allows everything
+/// that is not denied.
+#[derive(Debug, Clone, Default)]
+pub struct LogicCallFilter {
+    pub allowed_contracts: Vec<EthAddress>,
+    pub denied_contracts: Vec<EthAddress>,
+    pub allowed_invalidation_ids: Vec<Vec<u8>>,
+    pub denied_invalidation_ids: Vec<Vec<u8>>,
+}
+
+impl LogicCallFilter {
+    pub fn is_allowed(&self, call: &LogicCall) -> bool {
+        if self.denied_contracts.contains(&call.logic_contract_address)
+            || self.denied_invalidation_ids.contains(&call.invalidation_id)
+        {
+            return false;
+        }
+
+        (self.allowed_contracts.is_empty()
+            || self
+                .allowed_contracts
+                .contains(&call.logic_contract_address))
+            && (self.allowed_invalidation_ids.is_empty()
+                || self
+                    .allowed_invalidation_ids
+                    .contains(&call.invalidation_id))
+    }
+}
 
 #[allow(clippy::too_many_arguments)]
 pub async fn relay_logic_calls<S: Signer + 'static>(
     // the validator set currently in the contract on Ethereum
     current_valset: Valset,
     eth_client: EthClient<S>,
+    account_nonce: &AccountNonce,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     payment_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
-    eth_gas_price_multiplier: f32,
-    eth_gas_multiplier: f32,
+    eth_gas_price_multiplier_bps: u32,
+    eth_gas_multiplier_bps: u32,
     logic_call_skips: &mut LogicCallSkips,
+    logic_call_filter: &LogicCallFilter,
+    fee_manager: &mut FeeManager,
+    budget: &mut RelayerBudget,
+    pending_relays: &PendingRelayWatcher,
+    bridge: &str,
+    // the invalidation nonces in the contract, read by the main loop in its nonce multicall
+    latest_ethereum_calls: &HashMap<Vec<u8>, u64>,
 ) {
+    let (oldest_signed_call, oldest_signatures) = match get_oldest_signed_logic_call(
+        &current_valset,
+        grpc_client,
+        &gravity_id,
+        logic_call_skips,
+        logic_call_filter,
+    )
+    .await
+    {
+        Some(call) => call,
+        None => return,
+    };
+
+    let latest_ethereum_call = match latest_ethereum_calls.get(&oldest_signed_call.invalidation_id)
+    {
+        Some(nonce) => *nonce,
+        None => {
+            debug!(
+                "LogicCall {} appeared after this loop's nonces were read, relaying it next loop",
+                bytes_to_hex_str(&oldest_signed_call.invalidation_id)
+            );
+            return;
+        }
+    };
+    let latest_cosmos_call_nonce = oldest_signed_call.clone().invalidation_nonce;
+    if latest_cosmos_call_nonce > latest_ethereum_call {
+        metrics::inc_relays_considered(bridge, RelayType::LogicCall);
+        if pending_relays.logic_call_pending(
+            &oldest_signed_call.invalidation_id,
+            latest_cosmos_call_nonce,
+        ) {
+            info!(
+                "LogicCall {}/{} is already being relayed by another relayer, waiting",
+                bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+                latest_cosmos_call_nonce
+            );
+            metrics::inc_relays_skipped(
+                bridge,
+                RelayType::LogicCall,
+                skip_reason::PENDING_ELSEWHERE,
+            );
+            return;
+        }
+
+        let cost = ethereum_gravity::logic_call::estimate_logic_call_cost(
+            current_valset.clone(),
+            oldest_signed_call.clone(),
+            &oldest_signatures,
+            gravity_contract_address,
+            payment_address,
+            gravity_id.clone(),
+            eth_client.clone(),
+        )
+        .await;
+
+        let cost = match cost {
+            Ok(cost) => cost,
+            Err(e) => {
+                let action = handle_estimate_error::<S>(
+                    bridge,
+                    RelayType::LogicCall,
+                    &format!(
+                        "LogicCall {}/{} cost estimate",
+                        bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+                        latest_cosmos_call_nonce
+                    ),
+                    e,
+                );
+                skip_logic_call(logic_call_skips, &oldest_signed_call, action);
+                metrics::inc_relays_skipped(
+                    bridge,
+                    RelayType::LogicCall,
+                    skip_reason::ESTIMATE_FAILED,
+                );
+                return;
+            }
+        };
+
+        if !fee_manager
+            .can_send_logic_call(&cost, &oldest_signed_call)
+            .await
+        {
+            info!(
+                "LogicCall {}/{} is not profitable to submit at {} ETH, skipping",
+                bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+                oldest_signed_call.invalidation_nonce,
+                format_eth(cost.get_total()),
+            );
+            metrics::inc_relays_skipped(bridge, RelayType::LogicCall, skip_reason::UNPROFITABLE);
+            return;
+        }
+
+        info!(
+            "We have detected latest LogicCall {} but latest on Ethereum is {} This LogicCall is estimated to cost {} Gas / {} ETH to submit",
+            latest_cosmos_call_nonce,
+            latest_ethereum_call,
+            cost.gas_price.clone(),
+            format_eth(cost.get_total()),
+        );
+
+        let cost = cost.with_multipliers(eth_gas_price_multiplier_bps, eth_gas_multiplier_bps);
+
+        if let Err(reason) = budget.check(RelayType::LogicCall, &cost) {
+            warn!(
+                "Deferring LogicCall {}/{}: {}",
+                bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+                oldest_signed_call.invalidation_nonce,
+                reason
+            );
+            metrics::inc_relays_skipped(bridge, RelayType::LogicCall, skip_reason::SPEND_LIMIT);
+            return;
+        }
+        let max_cost = cost.get_total();
+        let gas_limit = cost.gas;
+        metrics::load_fee_decimals(&eth_client, &oldest_signed_call.fees).await;
+
+        let span = info_span!(
+            "logic_call_relay",
+            invalidation_id = %bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+            invalidation_nonce = oldest_signed_call.invalidation_nonce,
+            tx_hash = field::Empty
+        );
+        let res = send_eth_logic_call(
+            current_valset,
+            oldest_signed_call.clone(),
+            &oldest_signatures,
+            timeout,
+            gravity_contract_address,
+            payment_address,
+            gravity_id.clone(),
+            cost,
+            eth_client.clone(),
+            account_nonce,
+            logic_call_skips,
+        )
+        .instrument(span.clone())
+        .await;
+
+        let _enter = span.enter();
+        // logic calls are recorded under the token of their first fee
+        let fee_token = oldest_signed_call
+            .fees
+            .first()
+            .map(|fee| fee.token_contract_address)
+            .unwrap_or_default();
+        budget.record_relay(RelayType::LogicCall, &res, max_cost);
+        match res {
+            Ok(outcome) => {
+                if let Some(tx_hash) = outcome.tx_hash() {
+                    span.record("tx_hash", &field::display(tx_hash));
+                }
+                metrics::record_relay_outcome(
+                    bridge,
+                    RelayType::LogicCall,
+                    gas_limit,
+                    &oldest_signed_call.fees,
+                    &outcome,
+                );
+                relay_history::record_outcome(
+                    bridge,
+                    RelayType::LogicCall,
+                    oldest_signed_call.invalidation_nonce,
+                    fee_token,
+                    &oldest_signed_call.fees,
+                    &outcome,
+                );
+                fee_manager.update_next_logic_call_send_time(&oldest_signed_call.invalidation_id);
+            }
+            Err(e) => {
+                if let GravityError::TransactionReverted(reverted) = &e {
+                    metrics::record_reverted_relay(
+                        bridge,
+                        RelayType::LogicCall,
+                        gas_limit,
+                        reverted,
+                    );
+                }
+                relay_history::record_failure(
+                    bridge,
+                    RelayType::LogicCall,
+                    oldest_signed_call.invalidation_nonce,
+                    fee_token,
+                    &oldest_signed_call.fees,
+                    &e,
+                );
+                let action = handle_relay_error::<S>(
+                    bridge,
+                    RelayType::LogicCall,
+                    &format!(
+                        "LogicCall {}/{} submission",
+                        bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+                        latest_cosmos_call_nonce
+                    ),
+                    e,
+                );
+                skip_logic_call(logic_call_skips, &oldest_signed_call, action);
+            }
+        }
+    }
+}
+
+/// Logic calls are always backed off after a failure, as they were before errors were classified
+fn skip_logic_call(
+    logic_call_skips: &mut LogicCallSkips,
+    call: &LogicCall,
+    action: ContractErrorAction,
+) {
+    if action == ContractErrorAction::SkipPermanently {
+        logic_call_skips.skip_permanently(call);
+    } else {
+        logic_call_skips.skip(call);
+    }
+}
+
+/// Finds the logic call to relay among the latest ones, skipping calls that are filtered out, backed
+/// off after a failure, or that don't have enough signatures from the current validator set yet
+pub(crate) async fn get_oldest_signed_logic_call(
+    current_valset: &Valset,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity_id: &str,
+    logic_call_skips: &mut LogicCallSkips,
+    logic_call_filter: &LogicCallFilter,
+) -> Option<(LogicCall, Vec<LogicCallConfirmResponse>)> {
     let latest_calls = match get_latest_logic_calls(grpc_client).await {
         Ok(calls) => {
             debug!("Latest Logic calls {:?}", calls);
@@ -36,12 +304,22 @@ pub async fn relay_logic_calls<S: Signer + 'static>(
         }
         Err(err) => {
             error!("Error while retrieving latest logic calls: {:?}", err);
-            return;
+            return None;
         }
     };
     let mut oldest_signed_call: Option<LogicCall> = None;
     let mut oldest_signatures: Option<Vec<LogicCallConfirmResponse>> = None;
     for call in latest_calls {
+        if !logic_call_filter.is_allowed(&call) {
+            debug!(
+                "LogicCall {}/{} for contract {} is filtered out by the relayer config",
+                bytes_to_hex_str(&call.invalidation_id),
+                call.invalidation_nonce,
+                call.logic_contract_address
+            );
+            continue;
+        }
+
         if logic_call_skips.permanently_skipped(&call) {
             info!("LogicCall {}/{} permanently skipped until oracle confirms or on-chain timeout after eth height {}",
                 bytes_to_hex_str(&call.invalidation_id), call.invalidation_nonce, call.timeout
@@ -67,7 +345,7 @@ pub async fn relay_logic_calls<S: Signer + 'static>(
         .await;
         debug!("Got sigs {:?}", sigs);
         if let Ok(sigs) = sigs {
-            let hash = encode_logic_call_confirm_hashed(gravity_id.clone(), call.clone());
+            let hash = encode_logic_call_confirm_hashed(gravity_id.to_string(), call.clone());
             // this checks that the signatures for the logic call are actually possible to submit to the chain
             if current_valset.order_sigs(&hash, &sigs).is_ok() {
                 oldest_signed_call = Some(call);
@@ -90,96 +368,46 @@ pub async fn relay_logic_calls<S: Signer + 'static>(
     }
     if oldest_signed_call.is_none() {
         debug!("Could not find Call with signatures! exiting");
-        return;
+        return None;
     }
-    let oldest_signed_call = oldest_signed_call.unwrap();
-    let oldest_signatures = oldest_signatures.unwrap();
-
-    let latest_ethereum_call = get_logic_call_nonce(
-        gravity_contract_address,
-        oldest_signed_call.invalidation_id.clone(),
-        eth_client.clone(),
-    )
-    .await;
-    if latest_ethereum_call.is_err() {
-        error!(
-            "Failed to get latest Ethereum LogicCall with {:?}",
-            latest_ethereum_call
-        );
-        return;
-    }
-    let latest_ethereum_call = latest_ethereum_call.unwrap();
-    let latest_cosmos_call_nonce = oldest_signed_call.clone().invalidation_nonce;
-    if latest_cosmos_call_nonce > latest_ethereum_call {
-        let cost = ethereum_gravity::logic_call::estimate_logic_call_cost(
-            current_valset.clone(),
-            oldest_signed_call.clone(),
-            &oldest_signatures,
-            gravity_contract_address,
-            payment_address,
-            gravity_id.clone(),
-            eth_client.clone(),
-        )
-        .await;
 
-        if cost.is_err() {
-            warn!("LogicCall cost estimate failed");
-            let should_permanently_skip = handle_contract_error::<S>(cost.unwrap_err());
-            if should_permanently_skip {
-                logic_call_skips.skip_permanently(&oldest_signed_call);
-            } else {
-                logic_call_skips.skip(&oldest_signed_call);
-            }
-            return;
-        }
-
-        let mut cost = cost.unwrap();
-        let total_cost = downcast_to_f32(cost.get_total());
-        if total_cost.is_none() {
-            error!(
-                "Total gas cost greater than f32 max, skipping logic call submission: {}",
-                oldest_signed_call.invalidation_nonce
-            );
-            logic_call_skips.skip(&oldest_signed_call);
-            return;
-        }
-        let total_cost = total_cost.unwrap();
-        let gas_price_as_f32 = downcast_to_f32(cost.gas_price).unwrap(); // if the total cost isn't greater, this isn't
-        let gas_as_f32 = downcast_to_f32(cost.gas).unwrap(); // same as above re: total cost
+    Some((oldest_signed_call.unwrap(), oldest_signatures.unwrap()))
+}
 
-        info!(
-            "We have detected latest LogicCall {} but latest on Ethereum is {} This LogicCall is estimated to cost {} Gas / {:.4} ETH to submit",
-            latest_cosmos_call_nonce,
-            latest_ethereum_call,
-            cost.gas_price.clone(),
-            total_cost / one_eth_f32(),
-        );
+#[test]
+fn test_logic_call_filter() {
+    let allowed_contract: EthAddress = "0x0000000000000000000000000000000000000001"
+        .parse()
+        .unwrap();
+    let denied_contract: EthAddress = "0x0000000000000000000000000000000000000002"
+        .parse()
+        .unwrap();
+    let call = |logic_contract_address, invalidation_id: Vec<u8>| LogicCall {
+        logic_contract_address,
+        invalidation_id,
+        ..Default::default()
+    };
 
-        cost.gas_price = ((gas_price_as_f32 * eth_gas_price_multiplier) as u128).into();
-        cost.gas = ((gas_as_f32 * eth_gas_multiplier) as u128).into();
+    let filter = LogicCallFilter::default();
+    assert!(filter.is_allowed(&call(allowed_contract, vec![1])));
+    assert!(filter.is_allowed(&call(denied_contract, vec![2])));
 
-        let res = send_eth_logic_call(
-            current_valset,
-            oldest_signed_call.clone(),
-            &oldest_signatures,
-            timeout,
-            gravity_contract_address,
-            payment_address,
-            gravity_id.clone(),
-            cost,
-            eth_client.clone(),
-            logic_call_skips,
-        )
-        .await;
+    let filter = LogicCallFilter {
+        denied_contracts: vec![denied_contract],
+        denied_invalidation_ids: vec![vec![2]],
+        ..Default::default()
+    };
+    assert!(filter.is_allowed(&call(allowed_contract, vec![1])));
+    assert!(!filter.is_allowed(&call(allowed_contract, vec![2])));
+    assert!(!filter.is_allowed(&call(denied_contract, vec![1])));
 
-        if res.is_err() {
-            warn!("LogicCall submission failed");
-            let should_permanently_skip = handle_contract_error::<S>(res.unwrap_err());
-            if should_permanently_skip {
-                logic_call_skips.skip_permanently(&oldest_signed_call);
-            } else {
-                logic_call_skips.skip(&oldest_signed_call);
-            }
-        }
-    }
+    let filter = LogicCallFilter {
+        allowed_contracts: vec![allowed_contract],
+        allowed_invalidation_ids: vec![vec![1]],
+        denied_invalidation_ids: vec![vec![1]],
+        ..Default::default()
+    };
+    assert!(!filter.is_allowed(&call(allowed_contract, vec![1])));
+    assert!(!filter.is_allowed(&call(allowed_contract, vec![3])));
+    assert!(!filter.is_allowed(&call(denied_contract, vec![1])));
 }
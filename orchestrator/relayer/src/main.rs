@@ -1,14 +1,21 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
+use crate::budget::SpendLimits;
 use crate::fee_manager::FeeManager;
-use crate::main_loop::relayer_main_loop;
+use crate::gas_scheduling::GasSchedule;
+use crate::logic_call_relaying::LogicCallFilter;
 use crate::main_loop::LOOP_SPEED;
+use crate::main_loop::{relayer_main_loop, DEFAULT_BRIDGE_NAME};
 use cosmos_gravity::crypto::EthPubkey;
 use docopt::Docopt;
 use env_logger::Env;
+use ethereum_gravity::nonce_manager::AccountNonce;
+use ethereum_gravity::utils::ONE_BPS;
 use ethers::prelude::*;
 use ethers::signers::LocalWallet as EthWallet;
 use ethers::types::Address as EthAddress;
+use gravity_utils::metrics_server::{metrics_main_loop, HealthChecks};
 use gravity_utils::types::config::RelayerMode;
 use gravity_utils::{
     connection_prep::{check_for_eth, create_rpc_connections, wait_for_cosmos_node_ready},
@@ -16,10 +23,18 @@ use gravity_utils::{
 };
 
 pub mod batch_relaying;
+pub mod batch_requesting;
+pub mod budget;
 pub mod fee_manager;
 pub mod find_latest_valset;
+pub mod gas_scheduling;
 pub mod logic_call_relaying;
 pub mod main_loop;
+pub mod metrics;
+pub mod pending_relays;
+pub mod relay_history;
+pub mod relay_plan;
+pub mod relay_skips;
 pub mod valset_relaying;
 
 #[macro_use]
@@ -38,20 +53,22 @@ struct Args {
     flag_contract_address: String,
     flag_payment_address: String,
     flag_mode: RelayerMode,
+    flag_metrics_listen: Option<String>,
 }
 
 lazy_static! {
     pub static ref USAGE: String = format!(
-    "Usage: {} --ethereum-key=<key> --cosmos-grpc=<url> --address-prefix=<prefix> --ethereum-rpc=<url> --contract-address=<addr> --payment-address=<addr> --mode=<mode>
+    "Usage: {} --ethereum-key=<key> --cosmos-grpc=<url> --address-prefix=<prefix> --ethereum-rpc=<url> --contract-address=<addr> --payment-address=<addr> --mode=<mode> [--metrics-listen=<addr>]
         Options:
             -h --help                    Show this screen.
             --ethereum-key=<ekey>        An Ethereum private key containing non-trivial funds
             --cosmos-grpc=<gurl>         The Cosmos gRPC url
             --address-prefix=<prefix>    The prefix for addresses on this Cosmos chain
-            --ethereum-grpc=<eurl>       The Ethereum RPC url, Geth light clients work and sync fast
+            --ethereum-grpc=<eurl>       The Ethereum RPC url (http, ws or ipc), Geth light clients work and sync fast
             --contract-address=<addr>    The Ethereum contract address for Gravity
             --payment-address=<addr>     The address to collect the batch fee
             --mode=<mode>                The relayer mode, valid values are : AlwaysRelay, Api, File
+            --metrics-listen=<addr>      Serve Prometheus metrics on this address, such as 127.0.0.1:3000
         About:
             The Gravity relayer component, responsible for relaying data from the Cosmos blockchain
             to the Ethereum blockchain, cosmos key and fees are optional since they are only used
@@ -126,17 +143,33 @@ async fn main() {
     wait_for_cosmos_node_ready(&contact).await;
     check_for_eth(public_eth_key, eth_client.clone()).await;
 
+    if let Some(metrics_listen) = args.flag_metrics_listen {
+        let metrics_listen: SocketAddr = metrics_listen
+            .parse()
+            .expect("Invalid metrics listen address!");
+        tokio::spawn(
+            async move { metrics_main_loop(&metrics_listen, HealthChecks::default()).await },
+        );
+    }
+
     let mut fee_manager = FeeManager::new_fee_manager(mode).await.unwrap();
     relayer_main_loop(
         eth_client,
         connections.grpc.unwrap(),
         gravity_contract_address,
         payment_address,
-        1f32,
+        ONE_BPS,
         &mut fee_manager,
-        1.1f32,
+        11_000,
         5_000u64,
         Vec::new(),
+        LogicCallFilter::default(),
+        true,
+        SpendLimits::default(),
+        false,
+        DEFAULT_BRIDGE_NAME.to_string(),
+        AccountNonce::default(),
+        GasSchedule::default(),
     )
     .await
 }
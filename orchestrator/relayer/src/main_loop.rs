@@ -1,17 +1,43 @@
+use crate::budget::{RelayerBudget, SpendLimits};
 use crate::fee_manager::FeeManager;
+use crate::gas_scheduling::GasSchedule;
+use crate::metrics;
 use crate::{
-    batch_relaying::relay_batches, find_latest_valset::find_latest_valset,
-    logic_call_relaying::relay_logic_calls, valset_relaying::relay_valsets,
+    batch_relaying::relay_batches,
+    find_latest_valset::ValsetTracker,
+    logic_call_relaying::{relay_logic_calls, LogicCallFilter},
+    pending_relays::PendingRelayWatcher,
+    relay_skips::RelaySkips,
+    valset_relaying::{relay_valsets, ValsetCache},
 };
-use ethereum_gravity::{logic_call::LogicCallSkips, types::EthClient, utils::get_gravity_id};
+use cosmos_gravity::query::{
+    get_latest_logic_calls, get_latest_transaction_batches, get_latest_valset,
+};
+use ethereum_gravity::{
+    logic_call::LogicCallSkips,
+    nonce_manager::AccountNonce,
+    types::EthClient,
+    utils::{get_gravity_id, get_gravity_nonces, GravityNonces, NonceRequest},
+};
+use ethers::providers::Middleware;
 use ethers::signers::Signer;
 use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::error::GravityError;
+use gravity_utils::health;
+use gravity_utils::notifier;
 use std::time::Duration;
 use tonic::transport::Channel;
+use tracing::{info_span, Instrument};
 
 pub const LOOP_SPEED: Duration = Duration::from_secs(17);
 pub const PENDING_TX_TIMEOUT: Duration = Duration::from_secs(120);
+/// The health period of the relayer loop. The loop ticks between its valset, batch and logic call
+/// phases, each of which may wait on a receipt, so a tick can take a receipt wait past the loop speed
+const LOOP_HEALTH_PERIOD: Duration =
+    Duration::from_secs(LOOP_SPEED.as_secs() + PENDING_TX_TIMEOUT.as_secs());
+/// The name metrics are labelled with when a single bridge is relayed
+pub const DEFAULT_BRIDGE_NAME: &str = "gravity";
 
 /// This function contains the orchestrator primary loop, it is broken out of the main loop so that
 /// it can be called in the test runner for easier orchestration of multi-node tests
@@ -22,11 +48,17 @@ pub async fn relayer_main_loop<S: Signer + 'static>(
     grpc_client: GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     payment_address: EthAddress,
-    eth_gas_price_multiplier: f32,
+    eth_gas_price_multiplier_bps: u32,
     fee_manager: &mut FeeManager,
-    eth_gas_multiplier: f32,
+    eth_gas_multiplier_bps: u32,
     blocks_to_search: u64,
     supported_contracts: Vec<EthAddress>,
+    logic_call_filter: LogicCallFilter,
+    spend_limits: SpendLimits,
+    watch_pending_relays: bool,
+    bridge: String,
+    account_nonce: AccountNonce,
+    gas_schedule: GasSchedule,
 ) {
     let mut grpc_client = grpc_client;
 
@@ -42,65 +74,191 @@ pub async fn relayer_main_loop<S: Signer + 'static>(
     }
     let gravity_id = gravity_id.unwrap();
     let mut logic_call_skips = LogicCallSkips::default();
+    let mut budget = RelayerBudget::new(bridge.clone(), spend_limits);
+    let mut valset_tracker = ValsetTracker::default();
+    let mut valset_cache = ValsetCache::default();
+    let mut pending_relays = PendingRelayWatcher::new(watch_pending_relays);
+    let mut valset_skips = RelaySkips::default();
+    let mut batch_skips = RelaySkips::default();
+    let mut loop_count: u64 = 0;
+    // each bridge of a multi-bridge relayer is tracked separately
+    let loop_name = if bridge == DEFAULT_BRIDGE_NAME {
+        "relayer".to_string()
+    } else {
+        format!("relayer {}", bridge)
+    };
+    health::register_loop(loop_name.clone(), LOOP_HEALTH_PERIOD);
 
     loop {
+        let span = info_span!("relayer", bridge = %bridge, iteration = loop_count);
         let (async_resp, _) = tokio::join!(
             async {
-                let current_eth_valset = find_latest_valset(
+                let nonces = match get_loop_nonces(
+                    eth_client.clone(),
                     &mut grpc_client,
                     gravity_contract_address,
-                    eth_client.clone(),
-                    blocks_to_search,
+                    &supported_contracts,
                 )
-                .await;
+                .await
+                {
+                    Ok(nonces) => nonces,
+                    Err(e) => {
+                        error!("Could not get the Gravity contract nonces! {:?}", e);
+                        return;
+                    }
+                };
+                let current_eth_valset = valset_tracker
+                    .latest_valset(
+                        &mut grpc_client,
+                        gravity_contract_address,
+                        eth_client.clone(),
+                        blocks_to_search,
+                        nonces.valset_nonce.unwrap_or_default(),
+                    )
+                    .await;
                 if current_eth_valset.is_err() {
                     error!("Could not get current valset! {:?}", current_eth_valset);
                     return;
                 }
                 let current_eth_valset = current_eth_valset.unwrap();
+                update_metrics(
+                    &bridge,
+                    current_eth_valset.nonce,
+                    eth_client.clone(),
+                    &mut grpc_client,
+                )
+                .await;
+
+                pending_relays
+                    .poll(eth_client.clone(), gravity_contract_address)
+                    .await;
 
                 relay_valsets(
                     current_eth_valset.clone(),
                     eth_client.clone(),
+                    &account_nonce,
                     &mut grpc_client,
                     gravity_contract_address,
                     gravity_id.clone(),
                     PENDING_TX_TIMEOUT,
-                    eth_gas_price_multiplier,
-                    eth_gas_multiplier,
+                    eth_gas_price_multiplier_bps,
+                    eth_gas_multiplier_bps,
+                    &mut budget,
+                    &mut valset_cache,
+                    &pending_relays,
+                    &mut valset_skips,
+                    &bridge,
                 )
                 .await;
+                health::loop_ticked(&loop_name);
 
                 relay_batches(
                     current_eth_valset.clone(),
                     eth_client.clone(),
+                    &account_nonce,
                     &mut grpc_client,
                     gravity_contract_address,
                     payment_address,
                     gravity_id.clone(),
                     PENDING_TX_TIMEOUT,
-                    eth_gas_price_multiplier,
+                    eth_gas_price_multiplier_bps,
                     fee_manager,
-                    eth_gas_multiplier,
+                    eth_gas_multiplier_bps,
                     supported_contracts.clone(),
+                    &mut budget,
+                    &pending_relays,
+                    &gas_schedule,
+                    &mut batch_skips,
+                    &bridge,
+                    &nonces.batch_nonces,
                 )
                 .await;
+                health::loop_ticked(&loop_name);
 
                 relay_logic_calls(
                     current_eth_valset,
                     eth_client.clone(),
+                    &account_nonce,
                     &mut grpc_client,
                     gravity_contract_address,
                     payment_address,
                     gravity_id.clone(),
                     PENDING_TX_TIMEOUT,
-                    eth_gas_price_multiplier,
-                    eth_gas_multiplier,
+                    eth_gas_price_multiplier_bps,
+                    eth_gas_multiplier_bps,
                     &mut logic_call_skips,
+                    &logic_call_filter,
+                    fee_manager,
+                    &mut budget,
+                    &pending_relays,
+                    &bridge,
+                    &nonces.logic_call_nonces,
                 )
                 .await;
-            },
+            }
+            .instrument(span),
             tokio::time::sleep(LOOP_SPEED)
         );
+        health::loop_ticked(&loop_name);
+        loop_count += 1;
+    }
+}
+
+/// Reads the contract's valset nonce along with the nonces of every batch token and logic call
+/// invalidation id pending on Cosmos, in one multicall where Multicall3 is deployed, so the rest of
+/// the loop doesn't need a round trip per nonce
+async fn get_loop_nonces<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity_contract_address: EthAddress,
+    supported_contracts: &[EthAddress],
+) -> Result<GravityNonces, GravityError> {
+    let mut batch_tokens = supported_contracts.to_vec();
+    if batch_tokens.is_empty() {
+        for batch in get_latest_transaction_batches(grpc_client).await? {
+            if !batch_tokens.contains(&batch.token_contract) {
+                batch_tokens.push(batch.token_contract);
+            }
+        }
+    }
+    let mut logic_call_invalidation_ids = Vec::new();
+    for call in get_latest_logic_calls(grpc_client).await? {
+        if !logic_call_invalidation_ids.contains(&call.invalidation_id) {
+            logic_call_invalidation_ids.push(call.invalidation_id);
+        }
+    }
+
+    let request = NonceRequest {
+        valset_nonce: true,
+        batch_tokens,
+        logic_call_invalidation_ids,
+        ..NonceRequest::default()
+    };
+    get_gravity_nonces(gravity_contract_address, &request, eth_client).await
+}
+
+/// Updates the relayer gauges that aren't tied to a relay, failures to read them are only logged
+/// as the relays will report the same problems
+async fn update_metrics<S: Signer + 'static>(
+    bridge: &str,
+    ethereum_valset_nonce: u64,
+    eth_client: EthClient<S>,
+    grpc_client: &mut GravityQueryClient<Channel>,
+) {
+    metrics::set_relayer_ethereum_valset_nonce(bridge, ethereum_valset_nonce);
+    match get_latest_valset(grpc_client).await {
+        Ok(Some(valset)) => metrics::set_relayer_cosmos_valset_nonce(bridge, valset.nonce),
+        Ok(None) => {}
+        Err(e) => debug!(
+            "Could not get the latest Cosmos valset for metrics: {:?}",
+            e
+        ),
+    }
+    match eth_client.get_balance(eth_client.address(), None).await {
+        Ok(balance) => {
+            metrics::set_relayer_eth_balance(bridge, balance);
+            notifier::check_eth_balance(eth_client.address(), balance);
+        }
+        Err(e) => debug!("Could not get the relayer balance for metrics: {:?}", e),
     }
 }
@@ -0,0 +1,388 @@
+use crate::budget::RelayType;
+use ethereum_gravity::types::EthClient;
+use ethereum_gravity::utils::{get_erc20_decimals, RelayOutcome};
+use ethers::prelude::Signer;
+use ethers::types::{Address as EthAddress, TransactionReceipt, U256};
+use ethers::utils::format_units;
+use gravity_utils::error::RevertedTransaction;
+use gravity_utils::ethereum::format_eth_address;
+use gravity_utils::notifier::{notify, NotificationKind};
+use gravity_utils::types::Erc20Token;
+use lazy_static::lazy_static;
+use prometheus::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Why a relay was considered but not sent, used as the `reason` label of `relayer_relays_skipped`
+pub mod skip_reason {
+    pub const ALREADY_RELAYED: &str = "already_relayed";
+    pub const BACKING_OFF: &str = "backing_off";
+    pub const ESTIMATE_FAILED: &str = "estimate_failed";
+    pub const GAS_PRICE: &str = "gas_price";
+    pub const PENDING_ELSEWHERE: &str = "pending_elsewhere";
+    pub const SPEND_LIMIT: &str = "spend_limit";
+    pub const TIMED_OUT: &str = "timed_out";
+    pub const UNPROFITABLE: &str = "unprofitable";
+}
+
+// Counters
+lazy_static! {
+    static ref RELAYER_DEFERRED_TXS: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_deferred_txs",
+            "transactions deferred because they would exceed a spend limit",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_CONTRACT_ERRORS: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_contract_errors",
+            "relays rejected by the gravity contract, by contract error",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type", "error"]
+    )
+    .unwrap();
+    static ref RELAYER_RELAYS_CONSIDERED: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_relays_considered",
+            "relays found on cosmos that are not yet on ethereum",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_RELAYS_SKIPPED: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_relays_skipped",
+            "relays considered but not sent, by reason",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type", "reason"]
+    )
+    .unwrap();
+    static ref RELAYER_RELAYS_SUBMITTED: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_relays_submitted",
+            "relay transactions sent to ethereum",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_RELAYS_SUCCEEDED: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_relays_succeeded",
+            "relay transactions mined successfully",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_RELAYS_REVERTED: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_relays_reverted",
+            "relay transactions mined but reverted",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_RELAYS_LOST: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_relays_lost",
+            "relay transactions that reverted because another relayer got there first",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_ESTIMATED_GAS: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_estimated_gas",
+            "gas limit of mined relay transactions, compare with relayer_gas_used",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_GAS_USED: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_gas_used",
+            "gas used by mined relay transactions",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_ETH_SPENT_GWEI: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "relayer_eth_spent_gwei",
+            "ethereum paid for mined relay transactions, including reverted ones",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_FEES_EARNED: CounterVec = register_counter_vec!(
+        opts!(
+            "relayer_fees_earned",
+            "fees paid to the relayer by successful relays, in whole units of each token",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type", "token"]
+    )
+    .unwrap();
+}
+
+// Gauges
+lazy_static! {
+    static ref RELAYER_SPEND_HOURLY_GWEI: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "relayer_spend_hourly_gwei",
+            "ethereum spent by the relayer over the last hour",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_SPEND_DAILY_GWEI: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "relayer_spend_daily_gwei",
+            "ethereum spent by the relayer over the last day",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge", "type"]
+    )
+    .unwrap();
+    static ref RELAYER_ETH_BALANCE_GWEI: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "relayer_eth_balance_gwei",
+            "ethereum balance of the relayer account",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge"]
+    )
+    .unwrap();
+    static ref RELAYER_ETHEREUM_VALSET_NONCE: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "relayer_ethereum_valset_nonce",
+            "nonce of the validator set in the gravity contract",
+            labels! {"chain" => "ethereum"}
+        ),
+        &["bridge"]
+    )
+    .unwrap();
+    static ref RELAYER_COSMOS_VALSET_NONCE: IntGaugeVec = register_int_gauge_vec!(
+        opts!(
+            "relayer_cosmos_valset_nonce",
+            "nonce of the latest validator set on cosmos",
+            labels! {"chain" => "cosmos"}
+        ),
+        &["bridge"]
+    )
+    .unwrap();
+}
+
+lazy_static! {
+    /// decimals of the tokens relays are paid in, filled by `load_fee_decimals`
+    static ref TOKEN_DECIMALS: Mutex<HashMap<EthAddress, u8>> = Mutex::new(HashMap::new());
+}
+
+pub fn inc_relayer_deferred_txs(bridge: &str, relay_type: RelayType) {
+    RELAYER_DEFERRED_TXS
+        .with_label_values(&[bridge, relay_type.as_str()])
+        .inc();
+}
+
+pub fn inc_relayer_contract_errors(bridge: &str, relay_type: RelayType, error: &str) {
+    RELAYER_CONTRACT_ERRORS
+        .with_label_values(&[bridge, relay_type.as_str(), error])
+        .inc();
+}
+
+pub fn set_relayer_spend(bridge: &str, relay_type: RelayType, hourly: U256, daily: U256) {
+    RELAYER_SPEND_HOURLY_GWEI
+        .with_label_values(&[bridge, relay_type.as_str()])
+        .set(to_gwei(hourly));
+    RELAYER_SPEND_DAILY_GWEI
+        .with_label_values(&[bridge, relay_type.as_str()])
+        .set(to_gwei(daily));
+}
+
+pub fn inc_relays_considered(bridge: &str, relay_type: RelayType) {
+    RELAYER_RELAYS_CONSIDERED
+        .with_label_values(&[bridge, relay_type.as_str()])
+        .inc();
+}
+
+/// Counts a relay that was considered but not sent, `reason` is one of `skip_reason`
+pub fn inc_relays_skipped(bridge: &str, relay_type: RelayType, reason: &str) {
+    RELAYER_RELAYS_SKIPPED
+        .with_label_values(&[bridge, relay_type.as_str(), reason])
+        .inc();
+}
+
+/// Records what happened to a relay handed to one of the send functions. `gas_limit` is the gas
+/// it was sent with and `fees` what it pays the relayer if it succeeds.
+pub fn record_relay_outcome(
+    bridge: &str,
+    relay_type: RelayType,
+    gas_limit: U256,
+    fees: &[Erc20Token],
+    outcome: &RelayOutcome,
+) {
+    let labels = [bridge, relay_type.as_str()];
+    let receipt = match outcome {
+        RelayOutcome::NotSent => {
+            inc_relays_skipped(bridge, relay_type, skip_reason::ALREADY_RELAYED);
+            return;
+        }
+        RelayOutcome::Dropped(tx_hash) => {
+            RELAYER_RELAYS_SUBMITTED.with_label_values(&labels).inc();
+            notify(
+                NotificationKind::RelayFailed,
+                format!(
+                    "{} {} relay {:?} was not mined before the timeout",
+                    bridge,
+                    relay_type.as_str(),
+                    tx_hash
+                ),
+            );
+            return;
+        }
+        RelayOutcome::Mined(receipt) => receipt,
+    };
+
+    record_mined_transaction(&labels, gas_limit, receipt);
+    RELAYER_RELAYS_SUCCEEDED.with_label_values(&labels).inc();
+    notify(
+        NotificationKind::RelaySucceeded,
+        format!(
+            "{} {} relayed in {:?}",
+            bridge,
+            relay_type.as_str(),
+            receipt.transaction_hash
+        ),
+    );
+    for fee in fees.iter().filter(|fee| !fee.amount.is_zero()) {
+        let token = fee.token_contract_address;
+        let decimals = TOKEN_DECIMALS.lock().unwrap().get(&token).copied();
+        match decimals.and_then(|decimals| to_whole_tokens(fee.amount, decimals)) {
+            Some(amount) => RELAYER_FEES_EARNED
+                .with_label_values(&[bridge, relay_type.as_str(), &format_eth_address(token)])
+                .inc_by(amount),
+            None => debug!(
+                "Not recording {} fees of {}, its decimals are unknown",
+                fee.amount,
+                format_eth_address(token)
+            ),
+        }
+    }
+}
+
+/// Looks up the decimals of fee tokens not seen before so `record_relay_outcome` can record fees
+/// in whole tokens. Fees in a token whose decimals can't be read are left out of the metrics.
+pub async fn load_fee_decimals<S: Signer + 'static>(
+    eth_client: &EthClient<S>,
+    fees: &[Erc20Token],
+) {
+    for fee in fees.iter().filter(|fee| !fee.amount.is_zero()) {
+        let token = fee.token_contract_address;
+        if TOKEN_DECIMALS.lock().unwrap().contains_key(&token) {
+            continue;
+        }
+        match get_erc20_decimals(token, eth_client.clone()).await {
+            Ok(decimals) => {
+                TOKEN_DECIMALS.lock().unwrap().insert(token, decimals);
+            }
+            Err(e) => warn!(
+                "Could not get the decimals of {}: {:?}",
+                format_eth_address(token),
+                e
+            ),
+        }
+    }
+}
+
+/// Scales an amount in the smallest unit of a token to whole tokens before it becomes a float, so the
+/// counter doesn't lose the fees of small relays once it holds large amounts
+fn to_whole_tokens(amount: U256, decimals: u8) -> Option<f64> {
+    format_units(amount, decimals as u32).ok()?.parse().ok()
+}
+
+/// Records a relay transaction that was mined but reverted, telling a relay another relayer got to
+/// first apart from a real failure. Failures are notified when the error is handled.
+pub fn record_reverted_relay(
+    bridge: &str,
+    relay_type: RelayType,
+    gas_limit: U256,
+    reverted: &RevertedTransaction,
+) {
+    let labels = [bridge, relay_type.as_str()];
+    record_mined_transaction(&labels, gas_limit, &reverted.receipt);
+    RELAYER_RELAYS_REVERTED.with_label_values(&labels).inc();
+    if reverted.already_relayed() {
+        RELAYER_RELAYS_LOST.with_label_values(&labels).inc();
+    }
+}
+
+/// Counts a mined relay transaction and the gas it paid for
+fn record_mined_transaction(labels: &[&str], gas_limit: U256, receipt: &TransactionReceipt) {
+    RELAYER_RELAYS_SUBMITTED.with_label_values(labels).inc();
+    let gas_used = receipt.gas_used.unwrap_or_default();
+    RELAYER_ESTIMATED_GAS
+        .with_label_values(labels)
+        .inc_by(to_u64(gas_limit));
+    RELAYER_GAS_USED
+        .with_label_values(labels)
+        .inc_by(to_u64(gas_used));
+    let spent = gas_used.saturating_mul(receipt.effective_gas_price.unwrap_or_default());
+    RELAYER_ETH_SPENT_GWEI
+        .with_label_values(labels)
+        .inc_by(to_u64(spent / U256::exp10(9)));
+}
+
+pub fn set_relayer_eth_balance(bridge: &str, balance: U256) {
+    RELAYER_ETH_BALANCE_GWEI
+        .with_label_values(&[bridge])
+        .set(to_gwei(balance));
+}
+
+pub fn set_relayer_ethereum_valset_nonce(bridge: &str, nonce: u64) {
+    RELAYER_ETHEREUM_VALSET_NONCE
+        .with_label_values(&[bridge])
+        .set(nonce as i64);
+}
+
+pub fn set_relayer_cosmos_valset_nonce(bridge: &str, nonce: u64) {
+    RELAYER_COSMOS_VALSET_NONCE
+        .with_label_values(&[bridge])
+        .set(nonce as i64);
+}
+
+fn to_u64(value: U256) -> u64 {
+    if value > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        value.as_u64()
+    }
+}
+
+fn to_gwei(wei: U256) -> i64 {
+    (wei / U256::exp10(9)).to_string().parse().unwrap_or(-1)
+}
+
+#[test]
+fn test_to_whole_tokens() {
+    assert_eq!(
+        to_whole_tokens(U256::from(1_500_000_000_000_000_000u64), 18),
+        Some(1.5)
+    );
+    assert_eq!(to_whole_tokens(U256::from(2_500_000u64), 6), Some(2.5));
+    assert_eq!(to_whole_tokens(U256::from(42u64), 0), Some(42.0));
+    // more decimals than any U256 amount can carry
+    assert_eq!(to_whole_tokens(U256::one(), 100), None);
+}
@@ -0,0 +1,239 @@
+//! Many relayers run the same selection logic, so they race to submit the same valset, batch or logic call and
+//! the losers pay for a revert. This module watches transactions to the Gravity contract that are still pending
+//! in the mempool so that the relayer can back off from relays another relayer already has in flight.
+
+use ethereum_gravity::types::EthClient;
+use ethers::abi::AbiDecode;
+use ethers::prelude::*;
+use ethers::types::Address as EthAddress;
+use gravity_abi::gravity::GravityCalls;
+use gravity_utils::ethereum::downcast_to_u64;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The most pending transactions looked up per poll when watching a pending transaction filter
+const MAX_PENDING_TX_LOOKUPS: usize = 256;
+/// How long a pending relay seen through a pending transaction filter is considered in flight, the
+/// filter only reports new transactions so we can't tell when it leaves the mempool
+const PENDING_RELAY_TTL: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PendingRelay {
+    Valset(u64),
+    Batch(EthAddress, u64),
+    LogicCall(Vec<u8>, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PendingSource {
+    /// not polled yet, the first poll picks the source
+    Unknown,
+    /// `txpool_content`, usually only available on a local node
+    TxPool,
+    /// a `newPendingTransactions` filter with the given id
+    PendingFilter(U256),
+    Unavailable,
+}
+
+pub struct PendingRelayWatcher {
+    source: PendingSource,
+    relays: HashMap<H256, (PendingRelay, Instant)>,
+}
+
+impl PendingRelayWatcher {
+    pub fn new(enabled: bool) -> Self {
+        PendingRelayWatcher {
+            source: if enabled {
+                PendingSource::Unknown
+            } else {
+                PendingSource::Unavailable
+            },
+            relays: HashMap::new(),
+        }
+    }
+
+    /// Refreshes the relays other relayers have pending, our own transactions are ignored
+    pub async fn poll<S: Signer + 'static>(
+        &mut self,
+        eth_client: EthClient<S>,
+        gravity_contract_address: EthAddress,
+    ) {
+        if self.source == PendingSource::Unknown {
+            self.source = detect_source(eth_client.clone()).await;
+        }
+
+        let own_address = eth_client.address();
+        match self.source {
+            PendingSource::Unknown | PendingSource::Unavailable => {}
+            PendingSource::TxPool => match eth_client.txpool_content().await {
+                Ok(content) => {
+                    self.relays.clear();
+                    let now = Instant::now();
+                    for tx in content.pending.values().flat_map(|txs| txs.values()) {
+                        if let Some(relay) = decode_relay(tx, gravity_contract_address, own_address)
+                        {
+                            self.relays.insert(tx.hash, (relay, now));
+                        }
+                    }
+                }
+                Err(e) => warn!("Could not get the pending transaction pool: {:?}", e),
+            },
+            PendingSource::PendingFilter(id) => {
+                self.relays
+                    .retain(|_, (_, seen)| seen.elapsed() < PENDING_RELAY_TTL);
+
+                let hashes: Vec<H256> = match eth_client.get_filter_changes(id).await {
+                    Ok(hashes) => hashes,
+                    Err(e) => {
+                        warn!(
+                            "Could not get pending transactions, recreating filter: {:?}",
+                            e
+                        );
+                        self.source = PendingSource::Unknown;
+                        return;
+                    }
+                };
+                if hashes.len() > MAX_PENDING_TX_LOOKUPS {
+                    debug!(
+                        "{} new pending transactions, only looking up the latest {}",
+                        hashes.len(),
+                        MAX_PENDING_TX_LOOKUPS
+                    );
+                }
+                for hash in hashes.iter().rev().take(MAX_PENDING_TX_LOOKUPS) {
+                    if let Ok(Some(tx)) = eth_client.get_transaction(*hash).await {
+                        if let Some(relay) =
+                            decode_relay(&tx, gravity_contract_address, own_address)
+                        {
+                            self.relays.insert(tx.hash, (relay, Instant::now()));
+                        }
+                    }
+                }
+            }
+        }
+
+        if !self.relays.is_empty() {
+            debug!("Pending relays from other relayers {:?}", self.relays);
+        }
+    }
+
+    /// If another relayer is submitting a valset with at least this nonce
+    pub fn valset_pending(&self, nonce: u64) -> bool {
+        self.relays.values().any(|(relay, _)| match relay {
+            PendingRelay::Valset(pending_nonce) => *pending_nonce >= nonce,
+            _ => false,
+        })
+    }
+
+    /// If another relayer is submitting a batch for this token with at least this nonce
+    pub fn batch_pending(&self, token_contract: EthAddress, nonce: u64) -> bool {
+        self.relays.values().any(|(relay, _)| match relay {
+            PendingRelay::Batch(pending_token, pending_nonce) => {
+                *pending_token == token_contract && *pending_nonce >= nonce
+            }
+            _ => false,
+        })
+    }
+
+    /// If another relayer is submitting a logic call for this invalidation id with at least this nonce
+    pub fn logic_call_pending(&self, invalidation_id: &[u8], nonce: u64) -> bool {
+        self.relays.values().any(|(relay, _)| match relay {
+            PendingRelay::LogicCall(pending_id, pending_nonce) => {
+                pending_id.as_slice() == invalidation_id && *pending_nonce >= nonce
+            }
+            _ => false,
+        })
+    }
+}
+
+async fn detect_source<S: Signer + 'static>(eth_client: EthClient<S>) -> PendingSource {
+    if eth_client.txpool_content().await.is_ok() {
+        info!("Watching for competing relayers in the transaction pool");
+        return PendingSource::TxPool;
+    }
+    match eth_client.new_filter(FilterKind::PendingTransactions).await {
+        Ok(id) => {
+            info!("Watching for competing relayers with a pending transaction filter");
+            PendingSource::PendingFilter(id)
+        }
+        Err(e) => {
+            warn!(
+                "The Ethereum node can't report pending transactions, competing relayers won't be detected: {:?}",
+                e
+            );
+            PendingSource::Unavailable
+        }
+    }
+}
+
+/// Decodes a transaction to the Gravity contract into the relay it submits, if any
+fn decode_relay(
+    tx: &Transaction,
+    gravity_contract_address: EthAddress,
+    own_address: EthAddress,
+) -> Option<PendingRelay> {
+    if tx.to != Some(gravity_contract_address) || tx.from == own_address {
+        return None;
+    }
+
+    match GravityCalls::decode(&tx.input).ok()? {
+        GravityCalls::UpdateValset(call) => Some(PendingRelay::Valset(downcast_to_u64(
+            call.new_valset.valset_nonce,
+        )?)),
+        GravityCalls::SubmitBatch(call) => Some(PendingRelay::Batch(
+            call.token_contract,
+            downcast_to_u64(call.batch_nonce)?,
+        )),
+        GravityCalls::SubmitLogicCall(call) => Some(PendingRelay::LogicCall(
+            call.args.invalidation_id.to_vec(),
+            downcast_to_u64(call.args.invalidation_nonce)?,
+        )),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_decode_relay() {
+    use ethers::abi::AbiEncode;
+    use gravity_abi::gravity::{PaymentArgs, SubmitBatchCall, ValsetArgs};
+
+    let gravity_contract_address = EthAddress::from_low_u64_be(1);
+    let own_address = EthAddress::from_low_u64_be(2);
+    let token_contract = EthAddress::from_low_u64_be(3);
+    let call = GravityCalls::SubmitBatch(SubmitBatchCall {
+        current_valset: ValsetArgs {
+            validators: vec![],
+            powers: vec![],
+            valset_nonce: 1u64.into(),
+            reward_amount: 0u64.into(),
+            reward_token: EthAddress::zero(),
+        },
+        sigs: vec![],
+        payments: PaymentArgs {
+            amounts: vec![],
+            destinations: vec![],
+            fees: vec![],
+            fee_payment_address: EthAddress::zero(),
+        },
+        batch_nonce: 7u64.into(),
+        token_contract,
+        batch_timeout: 100u64.into(),
+    });
+    let mut tx = Transaction {
+        to: Some(gravity_contract_address),
+        from: EthAddress::from_low_u64_be(4),
+        input: call.encode().into(),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        decode_relay(&tx, gravity_contract_address, own_address),
+        Some(PendingRelay::Batch(token_contract, 7))
+    );
+
+    tx.from = own_address;
+    assert_eq!(
+        decode_relay(&tx, gravity_contract_address, own_address),
+        None
+    );
+}
@@ -0,0 +1,595 @@
+//! Keeps every relay the relayer attempts in an SQLite database: what was relayed, the transaction
+//! that carried it, the gas it used and the fees it paid, so operators can work out what relaying
+//! earned and what it cost. `gorc relayer report` reads the history back. Like the metrics the
+//! history is global, nothing is written until `init` is called.
+
+use crate::budget::RelayType;
+use crate::fee_manager::FeeManager;
+use ethereum_gravity::utils::RelayOutcome;
+use ethers::types::{Address as EthAddress, TxHash, U256};
+use gravity_utils::error::GravityError;
+use gravity_utils::ethereum::{format_eth_address, format_eth_hash};
+use gravity_utils::types::Erc20Token;
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection};
+use serde_derive::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS relays (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    bridge TEXT NOT NULL,
+    relay_type TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    token TEXT NOT NULL,
+    tx_hash TEXT,
+    gas_used TEXT,
+    effective_gas_price TEXT,
+    outcome TEXT NOT NULL,
+    error TEXT
+);
+CREATE INDEX IF NOT EXISTS relays_timestamp ON relays (timestamp);
+CREATE TABLE IF NOT EXISTS relay_fees (
+    relay_id INTEGER NOT NULL REFERENCES relays (id),
+    token TEXT NOT NULL,
+    amount TEXT NOT NULL
+);
+";
+
+/// What happened to a relay attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded,
+    Reverted,
+    /// sent but never mined
+    Dropped,
+    /// another relayer got there before the transaction was sent
+    NotSent,
+    /// the transaction could not be sent
+    Failed,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "succeeded",
+            Outcome::Reverted => "reverted",
+            Outcome::Dropped => "dropped",
+            Outcome::NotSent => "not_sent",
+            Outcome::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "succeeded" => Ok(Outcome::Succeeded),
+            "reverted" => Ok(Outcome::Reverted),
+            "dropped" => Ok(Outcome::Dropped),
+            "not_sent" => Ok(Outcome::NotSent),
+            "failed" => Ok(Outcome::Failed),
+            _ => Err(format!("Unknown relay outcome {}", s)),
+        }
+    }
+}
+
+/// One relay attempt
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayRecord {
+    /// unix time in seconds
+    pub timestamp: u64,
+    pub bridge: String,
+    pub relay_type: RelayType,
+    /// the batch or validator set nonce, or the invalidation nonce of a logic call
+    pub nonce: u64,
+    /// the batch token, the validator set reward token or the token of a logic call's first fee
+    pub token: EthAddress,
+    pub tx_hash: Option<TxHash>,
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+    /// what the relay pays the relayer, only earned if it succeeded
+    pub fees: Vec<Erc20Token>,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+}
+
+impl RelayRecord {
+    /// The ETH paid for the transaction, reverted transactions cost gas too
+    pub fn eth_spent(&self) -> U256 {
+        self.gas_used
+            .unwrap_or_default()
+            .saturating_mul(self.effective_gas_price.unwrap_or_default())
+    }
+
+    pub fn fees_earned(&self) -> &[Erc20Token] {
+        if self.outcome == Outcome::Succeeded {
+            &self.fees
+        } else {
+            &[]
+        }
+    }
+}
+
+pub struct RelayHistory {
+    connection: Connection,
+}
+
+impl RelayHistory {
+    /// Opens the history database, creating it if it does not exist
+    pub fn open(path: &Path) -> Result<Self, GravityError> {
+        let connection = Connection::open(path).map_err(database_error)?;
+        connection.execute_batch(SCHEMA).map_err(database_error)?;
+        Ok(RelayHistory { connection })
+    }
+
+    pub fn insert(&mut self, record: &RelayRecord) -> Result<(), GravityError> {
+        let transaction = self.connection.transaction().map_err(database_error)?;
+        transaction
+            .execute(
+                "INSERT INTO relays (timestamp, bridge, relay_type, nonce, token, tx_hash, gas_used, effective_gas_price, outcome, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    record.timestamp as i64,
+                    record.bridge,
+                    record.relay_type.as_str(),
+                    record.nonce as i64,
+                    format_eth_address(record.token),
+                    record.tx_hash.map(format_eth_hash),
+                    record.gas_used.map(|gas| gas.to_string()),
+                    record.effective_gas_price.map(|price| price.to_string()),
+                    record.outcome.as_str(),
+                    record.error,
+                ],
+            )
+            .map_err(database_error)?;
+        let relay_id = transaction.last_insert_rowid();
+        for fee in &record.fees {
+            transaction
+                .execute(
+                    "INSERT INTO relay_fees (relay_id, token, amount) VALUES (?1, ?2, ?3)",
+                    params![
+                        relay_id,
+                        format_eth_address(fee.token_contract_address),
+                        fee.amount.to_string()
+                    ],
+                )
+                .map_err(database_error)?;
+        }
+        transaction.commit().map_err(database_error)
+    }
+
+    /// The relays attempted at or after `since`, oldest first, optionally only those of one bridge
+    pub fn records_since(
+        &self,
+        since: u64,
+        bridge: Option<&str>,
+    ) -> Result<Vec<RelayRecord>, GravityError> {
+        let mut fees: HashMap<i64, Vec<Erc20Token>> = HashMap::new();
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT relay_fees.relay_id, relay_fees.token, relay_fees.amount
+                 FROM relay_fees JOIN relays ON relays.id = relay_fees.relay_id
+                 WHERE relays.timestamp >= ?1 AND (?2 IS NULL OR relays.bridge = ?2)",
+            )
+            .map_err(database_error)?;
+        let rows = statement
+            .query_map(params![since as i64, bridge], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(database_error)?;
+        for row in rows {
+            let (relay_id, token, amount): (i64, String, String) = row.map_err(database_error)?;
+            fees.entry(relay_id).or_default().push(Erc20Token {
+                amount: U256::from_dec_str(&amount)?,
+                token_contract_address: token.parse()?,
+            });
+        }
+
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT id, timestamp, bridge, relay_type, nonce, token, tx_hash, gas_used, effective_gas_price, outcome, error
+                 FROM relays WHERE timestamp >= ?1 AND (?2 IS NULL OR bridge = ?2) ORDER BY id",
+            )
+            .map_err(database_error)?;
+        let rows = statement
+            .query_map(params![since as i64, bridge], |row| {
+                Ok(RawRecord {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    bridge: row.get(2)?,
+                    relay_type: row.get(3)?,
+                    nonce: row.get(4)?,
+                    token: row.get(5)?,
+                    tx_hash: row.get(6)?,
+                    gas_used: row.get(7)?,
+                    effective_gas_price: row.get(8)?,
+                    outcome: row.get(9)?,
+                    error: row.get(10)?,
+                })
+            })
+            .map_err(database_error)?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let raw = row.map_err(database_error)?;
+            let parse_u256 = |value: Option<String>| -> Result<Option<U256>, GravityError> {
+                value
+                    .map(|value| U256::from_dec_str(&value))
+                    .transpose()
+                    .map_err(Into::into)
+            };
+            records.push(RelayRecord {
+                timestamp: raw.timestamp as u64,
+                bridge: raw.bridge,
+                relay_type: raw
+                    .relay_type
+                    .parse()
+                    .map_err(GravityError::DatabaseError)?,
+                nonce: raw.nonce as u64,
+                token: raw.token.parse()?,
+                tx_hash: raw.tx_hash.map(|hash| hash.parse()).transpose()?,
+                gas_used: parse_u256(raw.gas_used)?,
+                effective_gas_price: parse_u256(raw.effective_gas_price)?,
+                fees: fees.remove(&raw.id).unwrap_or_default(),
+                outcome: raw.outcome.parse().map_err(GravityError::DatabaseError)?,
+                error: raw.error,
+            });
+        }
+        Ok(records)
+    }
+}
+
+struct RawRecord {
+    id: i64,
+    timestamp: i64,
+    bridge: String,
+    relay_type: String,
+    nonce: i64,
+    token: String,
+    tx_hash: Option<String>,
+    gas_used: Option<String>,
+    effective_gas_price: Option<String>,
+    outcome: String,
+    error: Option<String>,
+}
+
+fn database_error(error: rusqlite::Error) -> GravityError {
+    GravityError::DatabaseError(error.to_string())
+}
+
+lazy_static! {
+    static ref RELAY_HISTORY: Mutex<Option<RelayHistory>> = Mutex::new(None);
+}
+
+/// Starts recording relays to the database at `path`
+pub fn init(path: &Path) -> Result<(), GravityError> {
+    let history = RelayHistory::open(path)?;
+    *RELAY_HISTORY.lock().unwrap() = Some(history);
+    Ok(())
+}
+
+/// Writes the record on the blocking thread pool, SQLite writes must not stall the relayer's executor
+fn record(record: RelayRecord) {
+    tokio::task::spawn_blocking(move || {
+        if let Some(history) = RELAY_HISTORY.lock().unwrap().as_mut() {
+            if let Err(e) = history.insert(&record) {
+                warn!(
+                    "Could not record {} {} relay in the relay history {}",
+                    record.bridge, record.relay_type, e
+                );
+            }
+        }
+    });
+}
+
+/// Records what happened to a relay handed to one of the send functions
+pub fn record_outcome(
+    bridge: &str,
+    relay_type: RelayType,
+    nonce: u64,
+    token: EthAddress,
+    fees: &[Erc20Token],
+    relay_outcome: &RelayOutcome,
+) {
+    let (outcome, receipt) = match relay_outcome {
+        RelayOutcome::NotSent => (Outcome::NotSent, None),
+        RelayOutcome::Dropped(_) => (Outcome::Dropped, None),
+        RelayOutcome::Mined(receipt) => (Outcome::Succeeded, Some(receipt)),
+    };
+    record(RelayRecord {
+        timestamp: now(),
+        bridge: bridge.to_string(),
+        relay_type,
+        nonce,
+        token,
+        tx_hash: relay_outcome.tx_hash(),
+        gas_used: receipt.and_then(|receipt| receipt.gas_used),
+        effective_gas_price: receipt.and_then(|receipt| receipt.effective_gas_price),
+        fees: fees.to_vec(),
+        outcome,
+        error: None,
+    });
+}
+
+/// Records a relay that could not be sent or that reverted
+pub fn record_failure(
+    bridge: &str,
+    relay_type: RelayType,
+    nonce: u64,
+    token: EthAddress,
+    fees: &[Erc20Token],
+    error: &GravityError,
+) {
+    let (outcome, receipt) = match error {
+        GravityError::TransactionReverted(reverted) => (Outcome::Reverted, Some(&reverted.receipt)),
+        _ => (Outcome::Failed, None),
+    };
+    record(RelayRecord {
+        timestamp: now(),
+        bridge: bridge.to_string(),
+        relay_type,
+        nonce,
+        token,
+        tx_hash: receipt.map(|receipt| receipt.transaction_hash),
+        gas_used: receipt.and_then(|receipt| receipt.gas_used),
+        effective_gas_price: receipt.and_then(|receipt| receipt.effective_gas_price),
+        fees: fees.to_vec(),
+        outcome,
+        error: Some(error.to_string()),
+    });
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// How relays are grouped in a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Token,
+    /// the UTC day the relay was attempted on
+    Day,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "token" => Ok(GroupBy::Token),
+            "day" => Ok(GroupBy::Day),
+            _ => Err(format!("Can not group by {}, expected token or day", s)),
+        }
+    }
+}
+
+/// What the relays of one group cost and earned. Values are in wei, fee amounts in the smallest
+/// unit of each token.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportRow {
+    pub group: String,
+    pub relays: u64,
+    pub succeeded: u64,
+    pub reverted: u64,
+    /// dropped, not sent or failed before sending
+    pub other: u64,
+    #[serde(with = "gravity_utils::types::serialization::u256")]
+    pub gas_used: U256,
+    #[serde(with = "gravity_utils::types::serialization::u256")]
+    pub eth_spent: U256,
+    pub fees_earned: Vec<Erc20Token>,
+    /// only known when the fee manager can price tokens, in File and Api modes
+    #[serde(with = "gravity_utils::types::serialization::option_u256")]
+    pub fees_value: Option<U256>,
+    /// fees value minus ETH spent as a decimal string, negative for a loss
+    pub profit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayReport {
+    pub rows: Vec<ReportRow>,
+    pub total: ReportRow,
+}
+
+/// Totals the relays by group and values the fees they earned with the fee manager's token prices
+pub async fn build_report(
+    records: &[RelayRecord],
+    group_by: GroupBy,
+    fee_manager: &mut FeeManager,
+) -> RelayReport {
+    let mut groups: BTreeMap<String, Vec<&RelayRecord>> = BTreeMap::new();
+    for record in records {
+        let group = match group_by {
+            GroupBy::Token => format_eth_address(record.token),
+            GroupBy::Day => utc_day(record.timestamp),
+        };
+        groups.entry(group).or_default().push(record);
+    }
+
+    let mut rows = Vec::new();
+    for (group, records) in groups {
+        rows.push(report_row(group, &records, fee_manager).await);
+    }
+    let all: Vec<&RelayRecord> = records.iter().collect();
+    let total = report_row("total".to_string(), &all, fee_manager).await;
+    RelayReport { rows, total }
+}
+
+async fn report_row(
+    group: String,
+    records: &[&RelayRecord],
+    fee_manager: &mut FeeManager,
+) -> ReportRow {
+    let mut row = ReportRow {
+        group,
+        ..Default::default()
+    };
+    let mut fees: BTreeMap<EthAddress, U256> = BTreeMap::new();
+    for record in records {
+        row.relays += 1;
+        match record.outcome {
+            Outcome::Succeeded => row.succeeded += 1,
+            Outcome::Reverted => row.reverted += 1,
+            _ => row.other += 1,
+        }
+        row.gas_used = row
+            .gas_used
+            .saturating_add(record.gas_used.unwrap_or_default());
+        row.eth_spent = row.eth_spent.saturating_add(record.eth_spent());
+        for fee in record.fees_earned() {
+            let earned = fees.entry(fee.token_contract_address).or_default();
+            *earned = earned.saturating_add(fee.amount);
+        }
+    }
+    row.fees_earned = fees
+        .into_iter()
+        .map(|(token_contract_address, amount)| Erc20Token {
+            amount,
+            token_contract_address,
+        })
+        .collect();
+    row.fees_value = fee_manager.fees_value(&row.fees_earned).await;
+    row.profit = row.fees_value.map(|value| {
+        if value >= row.eth_spent {
+            (value - row.eth_spent).to_string()
+        } else {
+            format!("-{}", row.eth_spent - value)
+        }
+    });
+    row
+}
+
+/// The UTC date of a unix timestamp as YYYY-MM-DD
+pub fn utc_day(timestamp: u64) -> String {
+    // civil_from_days from http://howardhinnant.github.io/date_algorithms.html
+    let z = (timestamp / DAY_SECS) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Days since the unix epoch of a UTC date, days_from_civil from the same source as `utc_day`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * mp + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Parses the start of a report period, either a UTC date such as 2024-01-31 or a time before
+/// `now` such as 30m, 12h, 7d or 2w
+pub fn parse_since(since: &str, now: u64) -> Result<u64, GravityError> {
+    let invalid = || {
+        GravityError::InvalidArgumentError(format!(
+            "Could not parse {:?}, expected a date such as 2024-01-31 or a duration such as 12h or 7d",
+            since
+        ))
+    };
+
+    if let Some(unit) = since.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        let count: u64 = since[..since.len() - 1].parse().map_err(|_| invalid())?;
+        let unit_secs = match unit {
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => DAY_SECS,
+            'w' => 7 * DAY_SECS,
+            _ => return Err(invalid()),
+        };
+        return Ok(now.saturating_sub(count.saturating_mul(unit_secs)));
+    }
+
+    let parts: Vec<i64> = since
+        .split('-')
+        .map(|part| part.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    match parts.as_slice() {
+        [year, month, day] if (1..=12).contains(month) && (1..=31).contains(day) => {
+            let days = days_from_civil(*year, *month, *day);
+            if days < 0 {
+                return Err(invalid());
+            }
+            Ok(days as u64 * DAY_SECS)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[test]
+fn test_report_dates() {
+    assert_eq!(utc_day(0), "1970-01-01");
+    assert_eq!(utc_day(951782400), "2000-02-29");
+    assert_eq!(utc_day(1706659200 + DAY_SECS - 1), "2024-01-31");
+
+    assert_eq!(parse_since("2024-01-31", 0).unwrap(), 1706659200);
+    assert_eq!(parse_since("2000-02-29", 0).unwrap(), 951782400);
+    assert_eq!(parse_since("12h", 100_000).unwrap(), 100_000 - 12 * 60 * 60);
+    assert_eq!(parse_since("7d", 100).unwrap(), 0);
+    assert!(parse_since("7y", 100).is_err());
+    assert!(parse_since("2024-13-01", 0).is_err());
+    assert!(parse_since("yesterday", 0).is_err());
+}
+
+#[test]
+fn test_relay_history_round_trip() {
+    let token: EthAddress = "0x6B175474E89094C44Da98b954EedeAC495271d0F"
+        .parse()
+        .unwrap();
+    let record = |timestamp, outcome| RelayRecord {
+        timestamp,
+        bridge: "default".to_string(),
+        relay_type: RelayType::Batch,
+        nonce: 7,
+        token,
+        tx_hash: Some(TxHash::repeat_byte(1)),
+        gas_used: Some(200_000u64.into()),
+        effective_gas_price: Some(30_000_000_000u64.into()),
+        fees: vec![Erc20Token {
+            amount: 1_000_000u64.into(),
+            token_contract_address: token,
+        }],
+        outcome,
+        error: None,
+    };
+
+    let mut history = RelayHistory::open(Path::new(":memory:")).unwrap();
+    history.insert(&record(100, Outcome::Succeeded)).unwrap();
+    history.insert(&record(200, Outcome::Reverted)).unwrap();
+
+    let records = history.records_since(150, Some("default")).unwrap();
+    assert_eq!(records, vec![record(200, Outcome::Reverted)]);
+    assert!(records[0].fees_earned().is_empty());
+    assert_eq!(
+        records[0].eth_spent(),
+        U256::from(200_000u64) * U256::from(30_000_000_000u64)
+    );
+
+    assert_eq!(history.records_since(0, None).unwrap().len(), 2);
+    assert!(history.records_since(0, Some("other")).unwrap().is_empty());
+}
@@ -0,0 +1,396 @@
+//! A dry run of the relayer. Finds everything that could be relayed right now, estimates what relaying it would
+//! cost and asks the FeeManager if it would be relayed, without ever sending a transaction to Ethereum. This is
+//! meant for tuning the relayer settings against a live bridge.
+
+use crate::batch_relaying::{get_batches_and_signatures, SubmittableBatch};
+use crate::budget::{RelayType, RelayerBudget, SpendLimits};
+use crate::fee_manager::FeeManager;
+use crate::find_latest_valset::find_latest_valset;
+use crate::gas_scheduling::{get_recent_base_fees, GasSchedule, ScheduleDecision};
+use crate::logic_call_relaying::{get_oldest_signed_logic_call, LogicCallFilter};
+use crate::valset_relaying::{get_latest_submittable_valset, ValsetCache};
+use ethereum_gravity::logic_call::{estimate_logic_call_cost, LogicCallSkips};
+use ethereum_gravity::submit_batch::estimate_tx_batch_cost;
+use ethereum_gravity::valset_update::estimate_valset_cost;
+use ethereum_gravity::{
+    types::EthClient,
+    utils::{get_gravity_id, get_gravity_nonces, GasCost, NonceRequest},
+};
+use ethers::prelude::*;
+use ethers::types::Address as EthAddress;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::error::GravityError;
+use gravity_utils::types::config::RelayerMode;
+use gravity_utils::types::Erc20Token;
+use serde_derive::Serialize;
+use tonic::transport::Channel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Relay,
+    Skip,
+}
+
+/// What the relayer would do with one valset, batch or logic call. Values are in wei, the gas
+/// and cost fields include the gas multipliers and are missing if the cost could not be estimated.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedRelay {
+    pub relay_type: RelayType,
+    pub nonce: u64,
+    pub fees: Vec<Erc20Token>,
+    /// only known in File and Api modes, where the relayer can price tokens
+    #[serde(with = "gravity_utils::types::serialization::option_u256")]
+    pub fee_value: Option<U256>,
+    #[serde(with = "gravity_utils::types::serialization::option_u256")]
+    pub gas: Option<U256>,
+    #[serde(with = "gravity_utils::types::serialization::option_u256")]
+    pub gas_price: Option<U256>,
+    #[serde(with = "gravity_utils::types::serialization::option_u256")]
+    pub cost: Option<U256>,
+    pub decision: Decision,
+    pub reason: String,
+}
+
+impl PlannedRelay {
+    fn skipped(relay_type: RelayType, nonce: u64, fees: Vec<Erc20Token>, reason: String) -> Self {
+        PlannedRelay {
+            relay_type,
+            nonce,
+            fees,
+            fee_value: None,
+            gas: None,
+            gas_price: None,
+            cost: None,
+            decision: Decision::Skip,
+            reason,
+        }
+    }
+
+    fn estimated(
+        relay_type: RelayType,
+        nonce: u64,
+        fees: Vec<Erc20Token>,
+        fee_value: Option<U256>,
+        cost: &GasCost,
+        (decision, reason): (Decision, String),
+    ) -> Self {
+        PlannedRelay {
+            relay_type,
+            nonce,
+            fees,
+            fee_value,
+            gas: Some(cost.gas),
+            gas_price: Some(cost.gas_price),
+            cost: Some(cost.get_total()),
+            decision,
+            reason,
+        }
+    }
+}
+
+/// Goes through the same valset, batch and logic call selection as the relayer main loop and
+/// reports what it would relay, see `relayer_main_loop` for the arguments
+#[allow(clippy::too_many_arguments)]
+pub async fn plan_relays<S: Signer + 'static>(
+    eth_client: EthClient<S>,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity_contract_address: EthAddress,
+    payment_address: EthAddress,
+    fee_manager: &mut FeeManager,
+    blocks_to_search: u64,
+    supported_contracts: Vec<EthAddress>,
+    logic_call_filter: &LogicCallFilter,
+    gas_schedule: &GasSchedule,
+    eth_gas_price_multiplier_bps: u32,
+    eth_gas_multiplier_bps: u32,
+    spend_limits: SpendLimits,
+    bridge: &str,
+) -> Result<Vec<PlannedRelay>, GravityError> {
+    let send_limits = SendLimits {
+        budget: RelayerBudget::new(bridge.to_string(), spend_limits),
+        eth_gas_price_multiplier_bps,
+        eth_gas_multiplier_bps,
+    };
+    let gravity_id = get_gravity_id(
+        gravity_contract_address,
+        eth_client.clone(),
+        grpc_client.clone(),
+    )
+    .await?;
+    let current_eth_valset = find_latest_valset(
+        grpc_client,
+        gravity_contract_address,
+        eth_client.clone(),
+        blocks_to_search,
+    )
+    .await?;
+    let ethereum_block_height = eth_client.get_block_number().await?;
+    let mut plan = Vec::new();
+
+    if let Some((valset, confirms)) = get_latest_submittable_valset(
+        &current_eth_valset,
+        grpc_client,
+        &gravity_id,
+        &mut ValsetCache::default(),
+    )
+    .await
+    {
+        // the chain signs valsets without a reward, there is nothing to weigh the cost against
+        let fees: Vec<Erc20Token> = Vec::new();
+        let cost = estimate_valset_cost(
+            &valset,
+            &current_eth_valset,
+            &confirms,
+            gravity_contract_address,
+            gravity_id.clone(),
+            eth_client.clone(),
+        )
+        .await;
+        plan.push(match cost {
+            Ok(cost) => {
+                let decision = (
+                    Decision::Relay,
+                    "valsets carry no reward and are always relayed".to_string(),
+                );
+                let (cost, decision) = send_limits.apply(RelayType::Valset, cost, decision);
+                PlannedRelay::estimated(
+                    RelayType::Valset,
+                    valset.nonce,
+                    fees,
+                    None,
+                    &cost,
+                    decision,
+                )
+            }
+            Err(e) => PlannedRelay::skipped(
+                RelayType::Valset,
+                valset.nonce,
+                fees,
+                format!("cost estimate failed: {}", e),
+            ),
+        });
+    }
+
+    let possible_batches = get_batches_and_signatures(
+        current_eth_valset.clone(),
+        grpc_client,
+        gravity_id.clone(),
+        supported_contracts,
+    )
+    .await;
+    let recent_base_fees = if gas_schedule.enabled() && !possible_batches.is_empty() {
+        get_recent_base_fees(eth_client.clone()).await
+    } else {
+        Vec::new()
+    };
+    // a fresh set of skips, the plan should not be influenced by earlier failures
+    let mut logic_call_skips = LogicCallSkips::default();
+    let oldest_logic_call = get_oldest_signed_logic_call(
+        &current_eth_valset,
+        grpc_client,
+        &gravity_id,
+        &mut logic_call_skips,
+        logic_call_filter,
+    )
+    .await;
+
+    // the batch and logic call nonces are read in the same multicall
+    let nonce_request = NonceRequest {
+        batch_tokens: possible_batches.keys().copied().collect(),
+        logic_call_invalidation_ids: oldest_logic_call
+            .iter()
+            .map(|(call, _)| call.invalidation_id.clone())
+            .collect(),
+        ..NonceRequest::default()
+    };
+    let ethereum_nonces =
+        get_gravity_nonces(gravity_contract_address, &nonce_request, eth_client.clone()).await?;
+    for (token_contract, batches) in possible_batches {
+        let latest_ethereum_batch = ethereum_nonces
+            .batch_nonces
+            .get(&token_contract)
+            .copied()
+            .unwrap_or_default();
+        for SubmittableBatch { batch, sigs } in batches {
+            if batch.nonce <= latest_ethereum_batch {
+                continue;
+            }
+            let fees = vec![batch.total_fee.clone()];
+            if batch.batch_timeout < ethereum_block_height.as_u64() {
+                plan.push(PlannedRelay::skipped(
+                    RelayType::Batch,
+                    batch.nonce,
+                    fees,
+                    format!("timed out at Ethereum block {}", batch.batch_timeout),
+                ));
+                continue;
+            }
+
+            let nonce = batch.nonce;
+            let cost = estimate_tx_batch_cost(
+                current_eth_valset.clone(),
+                batch.clone(),
+                &sigs,
+                gravity_contract_address,
+                payment_address,
+                gravity_id.clone(),
+                eth_client.clone(),
+            )
+            .await;
+            plan.push(match cost {
+                Ok(cost) => {
+                    let fee_value = fee_manager.fees_value(&fees).await;
+                    let profitable = fee_manager
+                        .is_batch_profitable(&cost, &batch.total_fee)
+                        .await;
+                    let mut decision =
+                        describe_decision(fee_manager.mode(), profitable, fee_value, &cost, true);
+                    let schedule = gas_schedule.schedule_batch(
+                        batch.batch_timeout - ethereum_block_height.as_u64(),
+                        cost.gas_price,
+                        &recent_base_fees,
+                    );
+                    if let (Decision::Relay, ScheduleDecision::Defer(reason)) =
+                        (decision.0, schedule)
+                    {
+                        decision = (
+                            Decision::Skip,
+                            format!("waiting for cheaper gas, {}", reason),
+                        );
+                    }
+                    let (cost, decision) = send_limits.apply(RelayType::Batch, cost, decision);
+                    PlannedRelay::estimated(
+                        RelayType::Batch,
+                        nonce,
+                        fees,
+                        fee_value,
+                        &cost,
+                        decision,
+                    )
+                }
+                Err(e) => PlannedRelay::skipped(
+                    RelayType::Batch,
+                    nonce,
+                    fees,
+                    format!("cost estimate failed: {}", e),
+                ),
+            });
+        }
+    }
+
+    if let Some((call, sigs)) = oldest_logic_call {
+        let latest_ethereum_call = ethereum_nonces
+            .logic_call_nonces
+            .get(&call.invalidation_id)
+            .copied()
+            .unwrap_or_default();
+        if call.invalidation_nonce > latest_ethereum_call {
+            let cost = estimate_logic_call_cost(
+                current_eth_valset.clone(),
+                call.clone(),
+                &sigs,
+                gravity_contract_address,
+                payment_address,
+                gravity_id.clone(),
+                eth_client.clone(),
+            )
+            .await;
+            plan.push(match cost {
+                Ok(cost) => {
+                    let fee_value = fee_manager.fees_value(&call.fees).await;
+                    let profitable = fee_manager.is_logic_call_profitable(&cost, &call).await;
+                    let decision =
+                        describe_decision(fee_manager.mode(), profitable, fee_value, &cost, true);
+                    let (cost, decision) = send_limits.apply(RelayType::LogicCall, cost, decision);
+                    PlannedRelay::estimated(
+                        RelayType::LogicCall,
+                        call.invalidation_nonce,
+                        call.fees.clone(),
+                        fee_value,
+                        &cost,
+                        decision,
+                    )
+                }
+                Err(e) => PlannedRelay::skipped(
+                    RelayType::LogicCall,
+                    call.invalidation_nonce,
+                    call.fees.clone(),
+                    format!("cost estimate failed: {}", e),
+                ),
+            });
+        }
+    }
+
+    Ok(plan)
+}
+
+/// The checks the main loop makes after the FeeManager approves a relay: the transaction is sent
+/// with the gas multipliers applied and deferred if that would break the spend limits
+struct SendLimits {
+    budget: RelayerBudget,
+    eth_gas_price_multiplier_bps: u32,
+    eth_gas_multiplier_bps: u32,
+}
+
+impl SendLimits {
+    fn apply(
+        &self,
+        relay_type: RelayType,
+        cost: GasCost,
+        decision: (Decision, String),
+    ) -> (GasCost, (Decision, String)) {
+        let cost = cost.with_multipliers(
+            self.eth_gas_price_multiplier_bps,
+            self.eth_gas_multiplier_bps,
+        );
+        if decision.0 != Decision::Relay {
+            return (cost, decision);
+        }
+        match self.budget.check(relay_type, &cost) {
+            Ok(()) => (cost, decision),
+            Err(reason) => (
+                cost,
+                (Decision::Skip, format!("over the spend limits, {}", reason)),
+            ),
+        }
+    }
+}
+
+/// Explains a FeeManager decision. Batches and logic calls that aren't profitable are still
+/// relayed by the main loop once they have been pending long enough, which a single run can't see.
+fn describe_decision(
+    mode: RelayerMode,
+    profitable: bool,
+    fee_value: Option<U256>,
+    cost: &GasCost,
+    relayed_after_waiting: bool,
+) -> (Decision, String) {
+    let mut reason = match (mode, fee_value) {
+        (RelayerMode::AlwaysRelay, _) => "AlwaysRelay mode relays everything".to_string(),
+        (RelayerMode::File, Some(value)) if profitable => {
+            format!(
+                "fees worth {} cover the cost of {}",
+                value,
+                cost.get_total()
+            )
+        }
+        (RelayerMode::File, Some(value)) => format!(
+            "fees worth {} do not cover the cost of {}",
+            value,
+            cost.get_total()
+        ),
+        (RelayerMode::File, None) => "a fee token has no price in the token price file".to_string(),
+        (RelayerMode::Api, _) if profitable => "approved by the relayer API".to_string(),
+        (RelayerMode::Api, _) => "not approved by the relayer API".to_string(),
+    };
+
+    if profitable {
+        (Decision::Relay, reason)
+    } else {
+        if relayed_after_waiting {
+            reason.push_str(", relayed anyway once pending for GRAVITY_BATCH_SENDING_SECS");
+        }
+        (Decision::Skip, reason)
+    }
+}
@@ -0,0 +1,160 @@
+//! Backs off from valsets and batches the Gravity contract rejected, the way `LogicCallSkips` does for logic
+//! calls. Each failure is classified by its contract error, relays that can never succeed are skipped for good
+//! while the rest are retried after an exponentially growing number of relayer loops.
+
+use crate::budget::RelayType;
+use crate::metrics;
+use ethereum_gravity::utils::{classify_contract_error, ContractErrorAction};
+use ethers::prelude::*;
+use gravity_utils::error::GravityError;
+use gravity_utils::notifier::{notify, NotificationKind};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// How many loops a relay is skipped after its first failure, doubled on each further failure
+const INITIAL_SKIPS: u32 = 2;
+
+#[derive(Debug, Clone, Default)]
+struct SkipState {
+    starting_skip_counter: u32,
+    skips_left: u32,
+    permanently_skipped: bool,
+}
+
+/// Skips for one kind of relay, keyed by valset nonce or by batch token and nonce
+#[derive(Debug, Clone)]
+pub struct RelaySkips<K> {
+    skips: HashMap<K, SkipState>,
+}
+
+impl<K> Default for RelaySkips<K> {
+    fn default() -> Self {
+        RelaySkips {
+            skips: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> RelaySkips<K> {
+    /// If the relay should be skipped on this loop, counting down its backoff
+    pub fn should_skip(&mut self, key: &K) -> bool {
+        match self.skips.get_mut(key) {
+            Some(state) if state.permanently_skipped => true,
+            Some(state) if state.skips_left > 0 => {
+                state.skips_left -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records a failed relay according to the action for its contract error
+    pub fn record_failure(&mut self, key: K, action: ContractErrorAction) {
+        match action {
+            ContractErrorAction::Retry => {}
+            ContractErrorAction::SkipPermanently => {
+                self.skips.entry(key).or_default().permanently_skipped = true;
+            }
+            ContractErrorAction::Backoff => {
+                let state = self.skips.entry(key).or_default();
+                state.starting_skip_counter = if state.starting_skip_counter == 0 {
+                    INITIAL_SKIPS
+                } else {
+                    state.starting_skip_counter.saturating_mul(2)
+                };
+                state.skips_left = state.starting_skip_counter;
+            }
+        }
+    }
+
+    /// Drops the skips of relays that no longer need tracking, such as ones already on Ethereum
+    pub fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        self.skips.retain(|key, _| keep(key))
+    }
+}
+
+/// Classifies why a relay failed, counting the contract error in the metrics and logging what the
+/// relayer will do about it. `description` names the relay in the log, such as "batch 5/0x..."
+pub fn handle_relay_error<S: Signer + 'static>(
+    bridge: &str,
+    relay_type: RelayType,
+    description: &str,
+    error: GravityError,
+) -> ContractErrorAction {
+    classify_relay_error::<S>(bridge, relay_type, description, error, true)
+}
+
+/// The same as `handle_relay_error` for a relay whose cost could not be estimated. Nothing was
+/// sent, so no RelayFailed notification goes out, the failure is only logged and counted.
+pub fn handle_estimate_error<S: Signer + 'static>(
+    bridge: &str,
+    relay_type: RelayType,
+    description: &str,
+    error: GravityError,
+) -> ContractErrorAction {
+    classify_relay_error::<S>(bridge, relay_type, description, error, false)
+}
+
+fn classify_relay_error<S: Signer + 'static>(
+    bridge: &str,
+    relay_type: RelayType,
+    description: &str,
+    error: GravityError,
+    notify_failure: bool,
+) -> ContractErrorAction {
+    let error = classify_contract_error::<S>(error);
+    metrics::inc_relayer_contract_errors(bridge, relay_type, error.name);
+
+    match error.action {
+        _ if error.already_relayed => info!(
+            "{} was relayed by another relayer first: {}",
+            description, error.message
+        ),
+        ContractErrorAction::SkipPermanently => info!(
+            "{} can not be relayed, skipping it: {}",
+            description, error.message
+        ),
+        ContractErrorAction::Backoff => warn!(
+            "{} was rejected, backing off before retrying: {}",
+            description, error.message
+        ),
+        ContractErrorAction::Retry => warn!("{} failed: {}", description, error.message),
+    }
+    if notify_failure && error.action != ContractErrorAction::SkipPermanently {
+        notify(
+            NotificationKind::RelayFailed,
+            format!("{} {} failed: {}", bridge, description, error.message),
+        );
+    }
+    error.action
+}
+
+#[test]
+fn test_relay_skips() {
+    let mut skips: RelaySkips<u64> = RelaySkips::default();
+    assert!(!skips.should_skip(&1));
+
+    skips.record_failure(1, ContractErrorAction::Retry);
+    assert!(!skips.should_skip(&1));
+
+    skips.record_failure(1, ContractErrorAction::Backoff);
+    assert!(skips.should_skip(&1));
+    assert!(skips.should_skip(&1));
+    assert!(!skips.should_skip(&1));
+
+    // failing again doubles the backoff
+    skips.record_failure(1, ContractErrorAction::Backoff);
+    for _ in 0..4 {
+        assert!(skips.should_skip(&1));
+    }
+    assert!(!skips.should_skip(&1));
+
+    skips.record_failure(2, ContractErrorAction::SkipPermanently);
+    for _ in 0..10 {
+        assert!(skips.should_skip(&2));
+    }
+
+    skips.retain(|nonce| *nonce > 1);
+    assert!(!skips.should_skip(&1));
+    assert!(skips.should_skip(&2));
+}
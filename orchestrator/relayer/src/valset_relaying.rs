@@ -3,17 +3,29 @@
 
 use std::time::Duration;
 
+use crate::budget::{RelayType, RelayerBudget};
+use crate::metrics::{self, skip_reason};
+use crate::pending_relays::PendingRelayWatcher;
+use crate::relay_history;
+use crate::relay_skips::{handle_estimate_error, handle_relay_error, RelaySkips};
 use cosmos_gravity::query::get_latest_valset;
-use cosmos_gravity::query::{get_all_valset_confirms, get_valset};
-use ethereum_gravity::{one_eth_f32, types::EthClient, valset_update::send_eth_valset_update};
+use cosmos_gravity::query::{get_all_valset_confirms, get_valsets_from};
+use ethereum_gravity::{
+    nonce_manager::AccountNonce, types::EthClient, utils::format_eth,
+    valset_update::send_eth_valset_update,
+};
 use ethers::signers::Signer;
 use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_utils::{
-    ethereum::bytes_to_hex_str, ethereum::downcast_to_f32,
-    message_signatures::encode_valset_confirm_hashed, types::Valset,
+    error::GravityError, ethereum::bytes_to_hex_str,
+    message_signatures::encode_valset_confirm_hashed, types::Erc20Token, types::Valset,
+    types::ValsetConfirmResponse,
 };
+use lru_cache::LruCache;
+use std::collections::HashMap;
 use tonic::transport::Channel;
+use tracing::{field, info_span, Instrument};
 
 /// Check the last validator set on Ethereum, if it's lower than our latest validator
 /// set then we should package and submit the update as an Ethereum transaction
@@ -22,12 +34,18 @@ pub async fn relay_valsets<S: Signer + 'static>(
     // the validator set currently in the contract on Ethereum
     current_eth_valset: Valset,
     eth_client: EthClient<S>,
+    account_nonce: &AccountNonce,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
-    eth_gas_price_multiplier: f32,
-    eth_gas_multiplier: f32,
+    eth_gas_price_multiplier_bps: u32,
+    eth_gas_multiplier_bps: u32,
+    budget: &mut RelayerBudget,
+    valset_cache: &mut ValsetCache,
+    pending_relays: &PendingRelayWatcher,
+    valset_skips: &mut RelaySkips<u64>,
+    bridge: &str,
 ) {
     // we have to start with the current ethereum valset, we need to know what's currently
     // in the contract in order to determine if a new validator set is valid.
@@ -38,108 +56,17 @@ pub async fn relay_valsets<S: Signer + 'static>(
 
     // we should determine if we need to relay one
     // to Ethereum for that we will find the latest confirmed valset and compare it to the ethereum chain
-    let latest_valset = get_latest_valset(grpc_client).await;
-    if latest_valset.is_err() {
-        error!("Failed to get latest valset! {:?}", latest_valset);
-        return;
-    }
-    let latest_valset = latest_valset.unwrap();
-    if latest_valset.is_none() {
-        return;
-    }
-
-    let latest_valset = latest_valset.unwrap();
-
-    // we only use the latest valsets endpoint to get a starting point, from there we will iterate
-    // backwards until we find the newest validator set that we can submit to the bridge. So if we
-    // have sets A-Z and it's possible to submit only A, L, and Q before reaching Z this code will do
-    // so.
-    let mut latest_nonce = latest_valset.nonce;
-    let mut latest_confirmed = None;
-    let mut latest_cosmos_valset = None;
-    // this is used to display the state of the last validator set to fail signature checks
-    let mut last_error = None;
-    while latest_nonce > 0 {
-        match get_valset(grpc_client, latest_nonce).await {
-            Ok(Some(cosmos_valset)) => {
-                assert_eq!(cosmos_valset.nonce, latest_nonce);
-
-                match get_all_valset_confirms(grpc_client, latest_nonce).await {
-                    Ok(confirms) => {
-                        debug!(
-                            "Considering cosmos_valset {:?} confirms {:?}",
-                            cosmos_valset, confirms
-                        );
-
-                        for confirm in confirms.iter() {
-                            assert_eq!(cosmos_valset.nonce, confirm.nonce);
-                        }
-
-                        let hash =
-                            encode_valset_confirm_hashed(gravity_id.clone(), cosmos_valset.clone());
-
-                        // there are two possible encoding problems that could cause the very rare sig failure bug,
-                        // one of them is that the hash is incorrect, that's not probable considering that
-                        // both Geth and Clarity agree on it. but this lets us check
-                        info!("New valset hash {}", bytes_to_hex_str(&hash));
-
-                        // order valset sigs prepares signatures for submission, notice we compare
-                        // them to the 'current' set in the bridge, this confirms for us that the validator set
-                        // we have here can be submitted to the bridge in it's current state
-                        match current_eth_valset.order_sigs(&hash, &confirms) {
-                            Ok(_) => {
-                                info!("Consideration: looks good");
-                                latest_confirmed = Some(confirms);
-                                latest_cosmos_valset = Some(cosmos_valset);
-                                // once we have the latest validator set we can submit exit
-                                break;
-                            }
-                            Err(err) => {
-                                warn!("Consideration: looks bad. {}", err);
-                                last_error = Some(err);
-                            }
-                        }
-                    }
-                    Err(err) => {
-                        error!(
-                            "Error getting Cosmos valset confirmations for nonce {}. {:?}",
-                            latest_nonce, err
-                        );
-                    }
-                }
-            }
-            // Bootstrap condition
-            Ok(None) => (),
-            Err(err) => {
-                error!(
-                    "Error getting Cosmos valset for nonce {}. {:?}",
-                    latest_nonce, err
-                );
-            }
-        }
-
-        latest_nonce -= 1
-    }
-
-    debug!("Relaying latest_confirmed {:?}", latest_confirmed);
-    debug!("Relaying latest_cosmos_valset {:?}", latest_cosmos_valset);
-
-    if latest_confirmed.is_none() {
-        error!("We don't have a latest confirmed valset?");
-        return;
-    }
-    // the latest cosmos validator set that it is possible to submit given the constraints
-    // of the validator set currently in the bridge
-    let latest_cosmos_valset = latest_cosmos_valset.unwrap();
-    // the signatures for the above
-    let latest_cosmos_confirmed = latest_confirmed.unwrap();
-
-    // this will print a message indicating the signing state of the latest validator
-    // set if the latest available validator set is not the latest one that is possible
-    // to submit. AKA if the bridge is behind where it should be
-    if latest_nonce > latest_cosmos_valset.nonce && last_error.is_some() {
-        warn!("{:?}", last_error)
-    }
+    let (latest_cosmos_valset, latest_cosmos_confirmed) = match get_latest_submittable_valset(
+        &current_eth_valset,
+        grpc_client,
+        &gravity_id,
+        valset_cache,
+    )
+    .await
+    {
+        Some(valset) => valset,
+        None => return,
+    };
 
     let should_relay = latest_cosmos_valset.nonce > current_eth_valset.nonce;
     info!(
@@ -148,7 +75,26 @@ pub async fn relay_valsets<S: Signer + 'static>(
         should_relay,
     );
 
+    valset_skips.retain(|nonce| *nonce > current_eth_valset.nonce);
     if should_relay {
+        metrics::inc_relays_considered(bridge, RelayType::Valset);
+        if valset_skips.should_skip(&latest_cosmos_valset.nonce) {
+            info!(
+                "Valset {} was rejected by the contract recently, waiting",
+                latest_cosmos_valset.nonce
+            );
+            metrics::inc_relays_skipped(bridge, RelayType::Valset, skip_reason::BACKING_OFF);
+            return;
+        }
+        if pending_relays.valset_pending(latest_cosmos_valset.nonce) {
+            info!(
+                "Valset {} is already being relayed by another relayer, waiting",
+                latest_cosmos_valset.nonce
+            );
+            metrics::inc_relays_skipped(bridge, RelayType::Valset, skip_reason::PENDING_ELSEWHERE);
+            return;
+        }
+
         let cost = ethereum_gravity::valset_update::estimate_valset_cost(
             &latest_cosmos_valset,
             &current_eth_valset,
@@ -159,36 +105,50 @@ pub async fn relay_valsets<S: Signer + 'static>(
         )
         .await;
 
-        if cost.is_err() {
-            error!(
-                "Valset cost estimate for Nonce {} failed with {:?}",
-                latest_cosmos_valset.nonce, cost
-            );
-            return;
-        }
-        let mut cost = cost.unwrap();
-        let total_cost = downcast_to_f32(cost.get_total());
-        if total_cost.is_none() {
-            error!(
-                "Total gas cost greater than f32 max, skipping valset submission: {}",
-                latest_cosmos_valset.nonce
-            );
-            return;
-        }
-        let total_cost = total_cost.unwrap();
-        let gas_price_as_f32 = downcast_to_f32(cost.gas_price).unwrap(); // if the total cost isn't greater, this isn't
-        let gas_as_f32 = downcast_to_f32(cost.gas).unwrap(); // same as above re: total cost
+        let cost = match cost {
+            Ok(cost) => cost,
+            Err(e) => {
+                let action = handle_estimate_error::<S>(
+                    bridge,
+                    RelayType::Valset,
+                    &format!("Valset {} cost estimate", latest_cosmos_valset.nonce),
+                    e,
+                );
+                valset_skips.record_failure(latest_cosmos_valset.nonce, action);
+                metrics::inc_relays_skipped(
+                    bridge,
+                    RelayType::Valset,
+                    skip_reason::ESTIMATE_FAILED,
+                );
+                return;
+            }
+        };
 
         info!(
-           "We have detected latest valset {} but latest on Ethereum is {} This valset is estimated to cost {} Gas / {:.4} ETH to submit",
+           "We have detected latest valset {} but latest on Ethereum is {} This valset is estimated to cost {} Gas / {} ETH to submit",
             latest_cosmos_valset.nonce, current_eth_valset.nonce,
             cost.gas_price.clone(),
-            total_cost / one_eth_f32()
+            format_eth(cost.get_total())
         );
 
-        cost.gas_price = ((gas_price_as_f32 * eth_gas_price_multiplier) as u128).into();
-        cost.gas = ((gas_as_f32 * eth_gas_multiplier) as u128).into();
+        let cost = cost.with_multipliers(eth_gas_price_multiplier_bps, eth_gas_multiplier_bps);
+
+        if let Err(reason) = budget.check(RelayType::Valset, &cost) {
+            warn!(
+                "Deferring valset {}: {}",
+                latest_cosmos_valset.nonce, reason
+            );
+            metrics::inc_relays_skipped(bridge, RelayType::Valset, skip_reason::SPEND_LIMIT);
+            return;
+        }
+        let max_cost = cost.get_total();
+        let gas_limit = cost.gas;
 
+        let span = info_span!(
+            "valset_relay",
+            valset_nonce = latest_cosmos_valset.nonce,
+            tx_hash = field::Empty
+        );
         let relay_response = send_eth_valset_update(
             latest_cosmos_valset.clone(),
             current_eth_valset.clone(),
@@ -198,12 +158,218 @@ pub async fn relay_valsets<S: Signer + 'static>(
             gravity_id,
             cost,
             eth_client.clone(),
+            account_nonce,
         )
+        .instrument(span.clone())
         .await;
+        let _enter = span.enter();
+        let reward = Erc20Token {
+            amount: latest_cosmos_valset.reward_amount,
+            token_contract_address: latest_cosmos_valset.reward_token,
+        };
+        budget.record_relay(RelayType::Valset, &relay_response, max_cost);
+        match relay_response {
+            Ok(outcome) => {
+                if let Some(tx_hash) = outcome.tx_hash() {
+                    span.record("tx_hash", &field::display(tx_hash));
+                }
+                metrics::record_relay_outcome(
+                    bridge,
+                    RelayType::Valset,
+                    gas_limit,
+                    &[reward.clone()],
+                    &outcome,
+                );
+                relay_history::record_outcome(
+                    bridge,
+                    RelayType::Valset,
+                    latest_cosmos_valset.nonce,
+                    latest_cosmos_valset.reward_token,
+                    &[reward],
+                    &outcome,
+                );
+                info!(
+                    "Relayed valset {} (current_eth_valset.nonce {})",
+                    latest_cosmos_valset.nonce, current_eth_valset.nonce,
+                );
+            }
+            Err(e) => {
+                if let GravityError::TransactionReverted(reverted) = &e {
+                    metrics::record_reverted_relay(bridge, RelayType::Valset, gas_limit, reverted);
+                }
+                relay_history::record_failure(
+                    bridge,
+                    RelayType::Valset,
+                    latest_cosmos_valset.nonce,
+                    latest_cosmos_valset.reward_token,
+                    &[reward],
+                    &e,
+                );
+                let action = handle_relay_error::<S>(
+                    bridge,
+                    RelayType::Valset,
+                    &format!("Valset {} submission", latest_cosmos_valset.nonce),
+                    e,
+                );
+                valset_skips.record_failure(latest_cosmos_valset.nonce, action);
+            }
+        }
+    }
+}
 
-        info!(
-            "relay_response {:?} (latest_cosmos_valset.nonce {} current_eth_valset.nonce {})",
-            relay_response, latest_cosmos_valset.nonce, current_eth_valset.nonce,
+/// The number of historical validator sets kept between relayer loops
+const VALSET_CACHE_SIZE: usize = 256;
+
+/// Validator sets never change once created on Cosmos, so the ones fetched while looking for a
+/// submittable set are kept between relayer loops. Confirmations are not cached as new ones keep arriving.
+pub struct ValsetCache {
+    valsets: LruCache<u64, Valset>,
+}
+
+impl Default for ValsetCache {
+    fn default() -> Self {
+        ValsetCache {
+            valsets: LruCache::new(VALSET_CACHE_SIZE),
+        }
+    }
+}
+
+impl ValsetCache {
+    /// Returns the validator sets with a nonce above `after_nonce` and up to `latest_nonce`, newest
+    /// first, fetching every set that isn't cached yet in one paginated query
+    pub async fn get_range(
+        &mut self,
+        grpc_client: &mut GravityQueryClient<Channel>,
+        after_nonce: u64,
+        latest_nonce: u64,
+    ) -> Result<Vec<Valset>, GravityError> {
+        let nonces = after_nonce.saturating_add(1)..=latest_nonce;
+        let mut fetched = HashMap::new();
+        if let Some(first_missing) = nonces
+            .clone()
+            .find(|nonce| !self.valsets.contains_key(nonce))
+        {
+            for valset in get_valsets_from(grpc_client, first_missing).await? {
+                if valset.nonce <= latest_nonce {
+                    fetched.insert(valset.nonce, valset);
+                }
+            }
+        }
+
+        let mut valsets = Vec::new();
+        for nonce in nonces.rev() {
+            let valset = match fetched.remove(&nonce) {
+                Some(valset) => {
+                    self.valsets.insert(nonce, valset.clone());
+                    valset
+                }
+                None => match self.valsets.get_mut(&nonce) {
+                    Some(valset) => valset.clone(),
+                    // pruned from the chain, or never created
+                    None => continue,
+                },
+            };
+            valsets.push(valset);
+        }
+        Ok(valsets)
+    }
+}
+
+/// Walks backwards from the latest Cosmos validator set to find the newest one that has enough
+/// signatures from the validator set currently in the bridge to be submitted, along with those signatures.
+/// Sets at or below the nonce in the bridge can't be submitted, so the walk stops there.
+pub(crate) async fn get_latest_submittable_valset(
+    current_eth_valset: &Valset,
+    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity_id: &str,
+    valset_cache: &mut ValsetCache,
+) -> Option<(Valset, Vec<ValsetConfirmResponse>)> {
+    let latest_valset = get_latest_valset(grpc_client).await;
+    if latest_valset.is_err() {
+        error!("Failed to get latest valset! {:?}", latest_valset);
+        return None;
+    }
+    // the bridge has no validator sets yet
+    let latest_valset = latest_valset.unwrap()?;
+    if latest_valset.nonce <= current_eth_valset.nonce {
+        debug!(
+            "Latest valset {} is already on Ethereum",
+            latest_valset.nonce
         );
+        return None;
     }
+
+    // we only use the latest valsets endpoint to get a starting point, from there we will iterate
+    // backwards until we find the newest validator set that we can submit to the bridge. So if we
+    // have sets A-Z and it's possible to submit only A, L, and Q before reaching Z this code will do
+    // so.
+    let candidates = match valset_cache
+        .get_range(grpc_client, current_eth_valset.nonce, latest_valset.nonce)
+        .await
+    {
+        Ok(candidates) => candidates,
+        Err(err) => {
+            error!(
+                "Error getting Cosmos valsets after nonce {}. {:?}",
+                current_eth_valset.nonce, err
+            );
+            return None;
+        }
+    };
+
+    // this is used to display the state of the last validator set to fail signature checks
+    let mut last_error = None;
+    for cosmos_valset in candidates {
+        let confirms = match get_all_valset_confirms(grpc_client, cosmos_valset.nonce).await {
+            Ok(confirms) => confirms,
+            Err(err) => {
+                error!(
+                    "Error getting Cosmos valset confirmations for nonce {}. {:?}",
+                    cosmos_valset.nonce, err
+                );
+                continue;
+            }
+        };
+        debug!(
+            "Considering cosmos_valset {:?} confirms {:?}",
+            cosmos_valset, confirms
+        );
+
+        for confirm in confirms.iter() {
+            assert_eq!(cosmos_valset.nonce, confirm.nonce);
+        }
+
+        let hash = encode_valset_confirm_hashed(gravity_id.to_string(), cosmos_valset.clone());
+
+        // there are two possible encoding problems that could cause the very rare sig failure bug,
+        // one of them is that the hash is incorrect, that's not probable considering that
+        // both Geth and Clarity agree on it. but this lets us check
+        info!("New valset hash {}", bytes_to_hex_str(&hash));
+
+        // order valset sigs prepares signatures for submission, notice we compare
+        // them to the 'current' set in the bridge, this confirms for us that the validator set
+        // we have here can be submitted to the bridge in it's current state
+        match current_eth_valset.order_sigs(&hash, &confirms) {
+            Ok(_) => {
+                info!("Consideration: looks good");
+                // this will print a message indicating the signing state of the latest validator
+                // set if the latest available validator set is not the latest one that is possible
+                // to submit. AKA if the bridge is behind where it should be
+                if let Some(err) = last_error {
+                    warn!("{:?}", err)
+                }
+                return Some((cosmos_valset, confirms));
+            }
+            Err(err) => {
+                warn!("Consideration: looks bad. {}", err);
+                last_error = Some(err);
+            }
+        }
+    }
+
+    error!(
+        "None of the valsets after nonce {} can be submitted yet, last error {:?}",
+        current_eth_valset.nonce, last_error
+    );
+    None
 }
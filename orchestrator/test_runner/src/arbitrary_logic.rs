@@ -5,11 +5,12 @@ use crate::TOTAL_TIMEOUT;
 use deep_space::Contact;
 use ethers::prelude::*;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::eth_transport::EthTransport;
 use tokio::time::sleep as delay_for;
 use tonic::transport::Channel;
 
 pub async fn arbitrary_logic_test(
-    _eth_provider: &Provider<Http>,
+    _eth_provider: &Provider<EthTransport>,
     _grpc_client: GravityQueryClient<Channel>,
     _contact: &Contact,
 ) {
@@ -15,13 +15,14 @@ use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::{
     query_client::QueryClient as GravityQueryClient, DenomToErc20Request,
 };
+use gravity_utils::eth_transport::EthTransport;
 use gravity_utils::ethereum::downcast_to_u64;
 use std::str::FromStr;
 use std::sync::Arc;
 use tonic::transport::Channel;
 
 pub async fn happy_path_test_v2(
-    eth_provider: &Provider<Http>,
+    eth_provider: &Provider<EthTransport>,
     grpc_client: GravityQueryClient<Channel>,
     contact: &Contact,
     keys: Vec<ValidatorKeys>,
@@ -22,11 +22,11 @@ use ethers::prelude::*;
 use ethers::providers::Provider;
 use ethers::types::Address as EthAddress;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::eth_transport::EthTransport;
 use gravity_utils::ethereum::hex_str_to_bytes;
 use happy_path::happy_path_test;
 use happy_path_v2::happy_path_test_v2;
 use orch_keys_update::orch_keys_update;
-use std::convert::TryFrom;
 use std::{env, sync::Arc, time::Duration};
 use transaction_stress_test::transaction_stress_test;
 use valset_stress::validator_set_stress_test;
@@ -71,8 +71,8 @@ lazy_static! {
         ).unwrap();
     static ref MINER_WALLET: LocalWallet = LocalWallet::from((*MINER_PRIVATE_KEY).clone());
     static ref MINER_ADDRESS: EthAddress = (*MINER_WALLET).address();
-    static ref MINER_PROVIDER: Provider<Http> = Provider::<Http>::try_from((*ETH_NODE).clone()).unwrap();
-    static ref MINER_SIGNER: SignerMiddleware<Provider<Http>, LocalWallet> =
+    static ref MINER_PROVIDER: Provider<EthTransport> = Provider::new(EthTransport::Http(ETH_NODE.parse().unwrap()));
+    static ref MINER_SIGNER: SignerMiddleware<Provider<EthTransport>, LocalWallet> =
         SignerMiddleware::new((*MINER_PROVIDER).clone(), (*MINER_WALLET).clone());
     static ref MINER_CLIENT: EthClient<LocalWallet> = Arc::new((*MINER_SIGNER).clone());
 
@@ -139,7 +139,7 @@ pub async fn main() {
     let grpc_client = GravityQueryClient::connect(COSMOS_NODE_GRPC.as_str())
         .await
         .unwrap();
-    let eth_provider = Provider::<Http>::try_from((*ETH_NODE).clone()).unwrap();
+    let eth_provider = Provider::new(EthTransport::Http(ETH_NODE.parse().unwrap()));
     let keys = get_keys();
 
     // // if we detect this env var we are only deploying contracts, do that then exit.
@@ -11,6 +11,7 @@ use ethereum_gravity::{
 use ethers::prelude::*;
 use ethers::types::Address as EthAddress;
 use futures::future::join_all;
+use gravity_utils::eth_transport::EthTransport;
 use gravity_utils::ethereum::downcast_to_u64;
 use std::{collections::HashSet, str::FromStr, sync::Arc, time::Duration};
 
@@ -31,7 +32,7 @@ const NUM_USERS: usize = 100;
 /// transactions and producing large batches
 #[allow(clippy::too_many_arguments)]
 pub async fn transaction_stress_test(
-    eth_provider: &Provider<Http>,
+    eth_provider: &Provider<EthTransport>,
     contact: &Contact,
     keys: Vec<ValidatorKeys>,
     gravity_address: EthAddress,